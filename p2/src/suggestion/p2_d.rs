@@ -18,8 +18,13 @@ fn update(_app: &App, _model: &mut Model, _update: Update) {}
 
 fn view(app: &App, _model: &Model, frame: Frame) {
     let draw = app.draw();
-    let win = app.window_rect();
+    draw_kaleidoscope(&draw, app.window_rect(), app.time);
+    draw.to_frame(app, &frame).unwrap();
+}
 
+//Everything the view draws, in a function of its own so it can be drawn
+// without a window as well, like `workshop::record` does to check it.
+pub fn draw_kaleidoscope(draw: &Draw, win: Rect, time: f32) {
     //Replace the `background()` with this to be able to use transparent colors
     draw.rect()
         .x_y(0.0, 0.0) 
@@ -36,11 +41,11 @@ fn view(app: &App, _model: &Model, frame: Frame) {
     builder.move_to(point(0.0, win.bottom())); //removed the offset to go to the center
 
     // Sine oscillator swinging slowly
-    let width = (app.time * 0.05).sin() * 400.0 + 40.0;
+    let width = (time * 0.05).sin() * 400.0 + 40.0;
 
     //Set bezier control points
-    let control_a = point((app.time * 0.3 ).cos() * width, (app.time * 0.1).sin() * width);
-    let control_b = point((app.time * 0.3 + 0.1).sin() * width, (app.time * 0.1).cos() * width);
+    let control_a = point((time * 0.3 ).cos() * width, (time * 0.1).sin() * width);
+    let control_b = point((time * 0.3 + 0.1).sin() * width, (time * 0.1).cos() * width);
 
     //Add the bezier segment to the path builder
     builder.cubic_bezier_to(
@@ -81,7 +86,4 @@ fn view(app: &App, _model: &Model, frame: Frame) {
             .weight(1.0)
             .events(path.iter());
     }
-
-    draw.to_frame(app, &frame).unwrap();
-
 }
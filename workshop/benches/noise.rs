@@ -0,0 +1,57 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use nannou::glam::Vec4;
+use nannou::prelude::*;
+use workshop::noise::{Fbm, NoiseField, Perlin, Simplex, Warp, Worley};
+
+//Sample a 64x64 grid, which is about what a sketch does when it draws a field of dots
+fn sample_grid(field: &impl NoiseField) -> f32 {
+    let mut sum = 0.0;
+    for y in 0..64 {
+        for x in 0..64 {
+            sum += field.sample2(vec2(x as f32, y as f32) * 0.05);
+        }
+    }
+    sum
+}
+
+fn bases(c: &mut Criterion) {
+    let mut group = c.benchmark_group("noise 2d grid");
+    group.bench_function("perlin", |b| {
+        let field = Perlin::new(1);
+        b.iter(|| sample_grid(black_box(&field)))
+    });
+    group.bench_function("simplex", |b| {
+        let field = Simplex::new(1);
+        b.iter(|| sample_grid(black_box(&field)))
+    });
+    group.bench_function("worley", |b| {
+        let field = Worley::new(1);
+        b.iter(|| sample_grid(black_box(&field)))
+    });
+    group.bench_function("fbm 5 octaves", |b| {
+        let field = Fbm::new(Simplex::new(1)).octaves(5);
+        b.iter(|| sample_grid(black_box(&field)))
+    });
+    group.bench_function("warped fbm", |b| {
+        let field = Warp::new(Fbm::new(Simplex::new(1)).octaves(5), Simplex::new(2));
+        b.iter(|| sample_grid(black_box(&field)))
+    });
+    group.finish();
+}
+
+fn dimensions(c: &mut Criterion) {
+    let field = Perlin::new(1);
+    let mut group = c.benchmark_group("perlin by dimension");
+    group.bench_function("1d", |b| b.iter(|| field.sample1(black_box(0.5))));
+    group.bench_function("2d", |b| b.iter(|| field.sample2(black_box(vec2(0.5, 0.5)))));
+    group.bench_function("3d", |b| {
+        b.iter(|| field.sample3(black_box(vec3(0.5, 0.5, 0.5))))
+    });
+    group.bench_function("4d", |b| {
+        b.iter(|| field.sample4(black_box(Vec4::new(0.5, 0.5, 0.5, 0.5))))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bases, dimensions);
+criterion_main!(benches);
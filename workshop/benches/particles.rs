@@ -0,0 +1,54 @@
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+use nannou::prelude::*;
+use workshop::noise::Simplex;
+use workshop::particles::{NoiseFlow, ParticleSystem};
+use workshop::rng::SketchRng;
+
+const DT: f32 = 1.0 / 60.0;
+
+fn filled_system(count: usize, trail_length: usize) -> ParticleSystem {
+    let mut rng = SketchRng::from_seed(1);
+    let mut particles = ParticleSystem::new(SketchRng::from_seed(2))
+        .max_particles(count)
+        .trail_length(trail_length);
+    let area = Rect::from_w_h(1024.0, 720.0);
+    for _ in 0..count {
+        //Long lifetimes so that no particles die during the benchmark
+        particles.spawn(rng.in_rect(area), Vec2::ZERO, 1000.0);
+    }
+    particles
+}
+
+//A single frame's update of 100k particles should stay well within the 16 ms of a 60 fps frame
+fn update(c: &mut Criterion) {
+    let mut group = c.benchmark_group("particle update");
+    let noise_flow = NoiseFlow::new(Simplex::new(1));
+    let swirl = |p: Vec2, _t: f32| vec2(-p.y, p.x) * 0.5;
+    for &count in &[10_000, 100_000] {
+        group.bench_function(format!("{} particles, closure field", count), |b| {
+            b.iter_batched_ref(
+                || filled_system(count, 0),
+                |particles| particles.update(&swirl, DT, 0.0),
+                BatchSize::LargeInput,
+            )
+        });
+        group.bench_function(format!("{} particles, noise field", count), |b| {
+            b.iter_batched_ref(
+                || filled_system(count, 0),
+                |particles| particles.update(&noise_flow, DT, 0.0),
+                BatchSize::LargeInput,
+            )
+        });
+        group.bench_function(format!("{} particles, noise field, trails", count), |b| {
+            b.iter_batched_ref(
+                || filled_system(count, 8),
+                |particles| particles.update(&noise_flow, DT, 0.0),
+                BatchSize::LargeInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, update);
+criterion_main!(benches);
@@ -0,0 +1,221 @@
+//! Benchmarks for the drawing done in the workshop sketches, on the CPU only.
+//!
+//! The GPU part of drawing is not measured, but building the draw list and tessellating the
+//! shapes into triangles is where most of the frame time goes in sketches like `p2_d`.
+//!
+//! Save a baseline with `cargo bench --bench tessellation -- --save-baseline before`, and compare
+//! a later commit with it using `cargo bench --bench tessellation -- --baseline before`.
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use nannou::color::{Hue, Lch, Srgb};
+use nannou::lyon::math::{point, vector, Angle, Point, Transform};
+use nannou::lyon::path::iterator::PathIterator;
+use nannou::lyon::path::Path;
+use nannou::lyon::tessellation::{
+    BuffersBuilder, FillOptions, FillTessellator, FillVertex, StrokeOptions, StrokeTessellator,
+    StrokeVertex, VertexBuffers,
+};
+use nannou::math::ConvertAngle;
+use nannou::prelude::*;
+use workshop::batch::{ShapeBatch, DEFAULT_RESOLUTION};
+use workshop::geometry::Mesh;
+
+const WIN_H: f32 = 768.0;
+
+//The bezier line from `p2_b`, at `time`
+fn bezier(time: f32) -> Path {
+    let mut builder = nannou::geom::path::Builder::new().with_svg();
+    builder.move_to(point(0.0, -WIN_H * 0.5 + 20.0));
+    let width = (time * 0.05).sin() * 400.0 + 40.0;
+    let control_a = point((time * 0.3).cos() * width, (time * 0.1).sin() * width);
+    let control_b = point((time * 0.3 + 0.1).sin() * width, (time * 0.1).cos() * width);
+    builder.cubic_bezier_to(control_a, control_b, point(0.0, WIN_H * 0.5 - 20.0));
+    builder.build().into()
+}
+
+fn stroke(path: &Path, tessellator: &mut StrokeTessellator) -> usize {
+    let mut geometry: VertexBuffers<Point, u16> = VertexBuffers::new();
+    tessellator
+        .tessellate_path(
+            path,
+            &StrokeOptions::default().with_line_width(1.0),
+            &mut BuffersBuilder::new(&mut geometry, |v: StrokeVertex| v.position()),
+        )
+        .unwrap();
+    geometry.indices.len()
+}
+
+fn bezier_path(c: &mut Criterion) {
+    let mut group = c.benchmark_group("p2_b bezier");
+    group.bench_function("build", |b| b.iter(|| bezier(black_box(10.0))));
+    let path = bezier(10.0);
+    group.bench_function("flatten", |b| {
+        b.iter(|| black_box(&path).iter().flattened(0.1).count())
+    });
+    group.bench_function("stroke tessellation", |b| {
+        let mut tessellator = StrokeTessellator::new();
+        b.iter(|| stroke(black_box(&path), &mut tessellator))
+    });
+    group.finish();
+}
+
+//The two paths of one arm in `p2_d`, transformed the same way as the draw context
+fn arm_transforms(i: u32, num_arms: u32) -> [Transform; 2] {
+    let angle = Angle::radians((i as f32 * (360.0 / num_arms as f32)).deg_to_rad());
+    let arm = |mirror: f32| {
+        Transform::scale(mirror, 1.0)
+            .then_translate(vector(0.0, WIN_H * 0.5))
+            .then_rotate(angle)
+            .then_scale(0.5, 0.5)
+    };
+    [arm(1.0), arm(-1.0)]
+}
+
+fn kaleidoscope(c: &mut Criterion) {
+    let path = bezier(10.0);
+    let mut group = c.benchmark_group("p2_d kaleidoscope");
+    for num_arms in [12_u32, 48, 192, 768] {
+        group.throughput(Throughput::Elements(num_arms as u64 * 2));
+        group.bench_with_input(BenchmarkId::new("draw list", num_arms), &num_arms, |b, &n| {
+            b.iter(|| {
+                let draw = Draw::new();
+                let draw = draw.scale(0.5);
+                for i in 0..n {
+                    let angle = i as f32 * (360.0 / n as f32);
+                    draw.rotate(angle.deg_to_rad())
+                        .translate(vec3(0.0, WIN_H * 0.5, 0.0))
+                        .path()
+                        .stroke()
+                        .color(WHITE)
+                        .weight(1.0)
+                        .events(path.iter());
+                    draw.rotate(angle.deg_to_rad())
+                        .translate(vec3(0.0, WIN_H * 0.5, 0.0))
+                        .scale_x(-1.0)
+                        .path()
+                        .stroke()
+                        .color(WHITE)
+                        .weight(1.0)
+                        .events(path.iter());
+                }
+                draw
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("tessellation", num_arms), &num_arms, |b, &n| {
+            let mut tessellator = StrokeTessellator::new();
+            b.iter(|| {
+                let mut indices = 0;
+                for i in 0..n {
+                    for transform in arm_transforms(i, n).iter() {
+                        let arm = path.clone().transformed(transform);
+                        indices += stroke(&arm, &mut tessellator);
+                    }
+                }
+                indices
+            })
+        });
+        //Tessellate once, and only transform the vertices for each arm
+        group.bench_with_input(BenchmarkId::new("cached mesh", num_arms), &num_arms, |b, &n| {
+            b.iter(|| {
+                let mesh = Mesh::stroke(&path, 1.0);
+                let transforms = (0..n).flat_map(|i| {
+                    let angle = (i as f32 * (360.0 / n as f32)).deg_to_rad();
+                    let arm = Mat4::from_scale(Vec3::splat(0.5))
+                        * Mat4::from_rotation_z(angle)
+                        * Mat4::from_translation(vec3(0.0, WIN_H * 0.5, 0.0));
+                    [arm, arm * Mat4::from_scale(vec3(-1.0, 1.0, 1.0))]
+                });
+                mesh.merge_instances(transforms).indices.len()
+            })
+        });
+    }
+    group.finish();
+}
+
+//The ring of green circles in `p0/src/main.rs`, with many more circles
+fn ellipses(c: &mut Criterion) {
+    let mut group = c.benchmark_group("p0 ellipse loop");
+    for num_items in [1_000_u32, 5_000, 20_000, 50_000] {
+        group.throughput(Throughput::Elements(num_items as u64));
+        group.bench_with_input(BenchmarkId::new("draw list", num_items), &num_items, |b, &n| {
+            b.iter(|| {
+                let draw = Draw::new();
+                let rotation_radius = 120.0;
+                for i in 0..n {
+                    let angle = i as f32 * (n as f32).recip() * TAU;
+                    draw.ellipse()
+                        .x_y(rotation_radius * angle.cos(), rotation_radius * angle.sin())
+                        .radius(20.0)
+                        .color(GREEN);
+                }
+                draw
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("tessellation", num_items), &num_items, |b, &n| {
+            let mut tessellator = FillTessellator::new();
+            b.iter(|| {
+                let mut geometry: VertexBuffers<Point, u32> = VertexBuffers::new();
+                let rotation_radius = 120.0;
+                for i in 0..n {
+                    let angle = i as f32 * (n as f32).recip() * TAU;
+                    tessellator
+                        .tessellate_circle(
+                            point(rotation_radius * angle.cos(), rotation_radius * angle.sin()),
+                            20.0,
+                            &FillOptions::default(),
+                            &mut BuffersBuilder::new(&mut geometry, |v: FillVertex| v.position()),
+                        )
+                        .unwrap();
+                }
+                geometry.indices.len()
+            })
+        });
+        //One mesh for all the circles, sharing a unit circle
+        group.bench_with_input(BenchmarkId::new("batch", num_items), &num_items, |b, &n| {
+            let mut batch = ShapeBatch::circles(DEFAULT_RESOLUTION);
+            b.iter(|| {
+                batch.clear();
+                let rotation_radius = 120.0;
+                for i in 0..n {
+                    let angle = i as f32 * (n as f32).recip() * TAU;
+                    batch.push_circle(
+                        vec2(rotation_radius * angle.cos(), rotation_radius * angle.sin()),
+                        20.0,
+                        GREEN,
+                    );
+                }
+                let draw = Draw::new();
+                batch.draw(&draw);
+                draw
+            })
+        });
+    }
+    group.finish();
+}
+
+//From `basic-sketch/src/circle-animation-sketch.rs`
+fn get_lab_complementary(color: &Srgb) -> Srgb {
+    let c: Lch = color.into_linear().into();
+    let c = c.shift_hue(180.0);
+    Srgb::from(c)
+}
+
+fn colors(c: &mut Criterion) {
+    let colors: Vec<Srgb> = (0..1000)
+        .map(|i| hsv(i as f32 / 1000.0, 1.0, 0.5).into())
+        .collect();
+    let mut group = c.benchmark_group("colour");
+    group.throughput(Throughput::Elements(colors.len() as u64));
+    group.bench_function("get_lab_complementary", |b| {
+        b.iter(|| {
+            black_box(&colors)
+                .iter()
+                .map(get_lab_complementary)
+                .fold(0.0, |sum, c| sum + c.red)
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bezier_path, kaleidoscope, ellipses, colors);
+criterion_main!(benches);
@@ -0,0 +1,265 @@
+//! Drawing thousands of circles or rects as one mesh.
+//!
+//! Every `draw.ellipse()` is tessellated on its own, which is fine for the five circles in
+//! `p0`, but not for 50 000 particles.
+//! A `ShapeBatch` tessellates one unit circle (or square) once, and each instance only has a
+//! position, size, rotation and colour.
+//! All the instances are drawn together with a single `draw.mesh()`.
+
+use nannou::color::IntoLinSrgba;
+use nannou::prelude::*;
+
+use crate::geometry::Mesh;
+
+/// How many segments the circles are made of by default.
+pub const DEFAULT_RESOLUTION: usize = 24;
+
+/// One shape in a batch.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Instance {
+    pub position: Vec2,
+    /// The radius for circles, and half the width and height for rects.
+    pub scale: Vec2,
+    /// Rotation in radians.
+    pub rotation: f32,
+    pub color: LinSrgba,
+}
+
+/// Many shapes of the same kind, sharing one tessellated unit shape.
+#[derive(Clone, Debug)]
+pub struct ShapeBatch {
+    unit: Mesh,
+    instances: Vec<Instance>,
+}
+
+impl ShapeBatch {
+    /// A batch of circles, each made of `resolution` triangles.
+    pub fn circles(resolution: usize) -> Self {
+        ShapeBatch::new(unit_circle(resolution.max(3)))
+    }
+
+    /// A batch of rects.
+    pub fn rects() -> Self {
+        let vertices = vec![
+            vec2(-1.0, -1.0),
+            vec2(1.0, -1.0),
+            vec2(1.0, 1.0),
+            vec2(-1.0, 1.0),
+        ];
+        ShapeBatch::new(Mesh {
+            vertices,
+            indices: vec![0, 1, 2, 0, 2, 3],
+        })
+    }
+
+    /// A batch of any shape, e.g. a `Mesh::fill` of a path that fits within `-1.0..=1.0`.
+    pub fn new(unit: Mesh) -> Self {
+        ShapeBatch {
+            unit,
+            instances: Vec::new(),
+        }
+    }
+
+    pub fn with_capacity(mut self, capacity: usize) -> Self {
+        self.instances.reserve(capacity);
+        self
+    }
+
+    /// Add a circle, or a square when the batch is made of rects.
+    pub fn push_circle(&mut self, position: Vec2, radius: f32, color: impl IntoLinSrgba<f32>) {
+        self.push(Instance {
+            position,
+            scale: Vec2::splat(radius),
+            rotation: 0.0,
+            color: color.into_lin_srgba(),
+        });
+    }
+
+    /// Add a rect with width and height `wh`.
+    pub fn push_rect(&mut self, position: Vec2, wh: Vec2, color: impl IntoLinSrgba<f32>) {
+        self.push(Instance {
+            position,
+            scale: wh * 0.5,
+            rotation: 0.0,
+            color: color.into_lin_srgba(),
+        });
+    }
+
+    pub fn push(&mut self, instance: Instance) {
+        self.instances.push(instance);
+    }
+
+    /// The instances, to change them in place between frames.
+    pub fn instances_mut(&mut self) -> &mut [Instance] {
+        &mut self.instances
+    }
+
+    pub fn instances(&self) -> &[Instance] {
+        &self.instances
+    }
+
+    pub fn len(&self) -> usize {
+        self.instances.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.instances.is_empty()
+    }
+
+    /// Remove all the instances, keeping the memory for the next frame.
+    pub fn clear(&mut self) {
+        self.instances.clear();
+    }
+
+    /// Draw all the instances as one mesh.
+    pub fn draw(&self, draw: &Draw) {
+        if self.instances.is_empty() {
+            return;
+        }
+        draw.mesh().indexed_colored(self.vertices(), self.indices());
+    }
+
+    //The unit shape's vertices moved into place for every instance, one after the other
+    fn vertices(&self) -> impl Iterator<Item = (Vec3, LinSrgba)> + '_ {
+        let unit = &self.unit;
+        self.instances.iter().flat_map(move |instance| {
+            let (sin, cos) = instance.rotation.sin_cos();
+            unit.vertices.iter().map(move |v| {
+                let v = *v * instance.scale;
+                let p = vec2(v.x * cos - v.y * sin, v.x * sin + v.y * cos) + instance.position;
+                (p.extend(0.0), instance.color)
+            })
+        })
+    }
+
+    //The unit shape's indices, offset to the vertices of each instance
+    fn indices(&self) -> impl Iterator<Item = usize> + '_ {
+        let unit = &self.unit;
+        let stride = unit.vertices.len();
+        (0..self.instances.len()).flat_map(move |i| {
+            unit.indices.iter().map(move |&index| i * stride + index as usize)
+        })
+    }
+}
+
+//A circle with radius 1.0 as a fan of triangles around the center
+fn unit_circle(resolution: usize) -> Mesh {
+    let mut vertices = Vec::with_capacity(resolution + 1);
+    vertices.push(Vec2::ZERO);
+    for i in 0..resolution {
+        let angle = i as f32 / resolution as f32 * TAU;
+        vertices.push(vec2(angle.cos(), angle.sin()));
+    }
+    let mut indices = Vec::with_capacity(resolution * 3);
+    for i in 0..resolution as u32 {
+        indices.extend([0, i + 1, (i + 1) % resolution as u32 + 1]);
+    }
+    Mesh { vertices, indices }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::record::{self, record};
+
+    fn assert_close(a: Vec3, b: Vec2) {
+        assert!(a.truncate().distance(b) < 1e-4, "{:?} != {:?}", a, b);
+    }
+
+    #[test]
+    fn unit_circle_is_a_fan() {
+        let circle = unit_circle(6);
+        assert_eq!(circle.vertices.len(), 7);
+        assert_eq!(circle.vertices[0], Vec2::ZERO);
+        for v in &circle.vertices[1..] {
+            assert!((v.length() - 1.0).abs() < 1e-5);
+        }
+        assert_eq!(circle.vertices[1], vec2(1.0, 0.0));
+        assert_eq!(circle.triangle_count(), 6);
+        //Every triangle starts at the center, and the last one closes the circle
+        assert_eq!(&circle.indices[..3], &[0, 1, 2]);
+        assert_eq!(&circle.indices[15..], &[0, 6, 1]);
+        assert!(circle.indices.chunks(3).all(|t| t[0] == 0));
+    }
+
+    #[test]
+    fn circles_have_at_least_three_segments() {
+        let batch = ShapeBatch::circles(1);
+        assert_eq!(batch.unit.vertices.len(), 4);
+    }
+
+    #[test]
+    fn indices_are_offset_per_instance() {
+        let mut batch = ShapeBatch::rects();
+        for i in 0..3 {
+            batch.push_rect(vec2(i as f32 * 10.0, 0.0), vec2(2.0, 2.0), WHITE);
+        }
+        let vertices: Vec<_> = batch.vertices().collect();
+        let indices: Vec<_> = batch.indices().collect();
+        assert_eq!(vertices.len(), 12);
+        assert_eq!(indices, vec![0, 1, 2, 0, 2, 3, 4, 5, 6, 4, 6, 7, 8, 9, 10, 8, 10, 11]);
+        //The indices of each instance point at its own vertices
+        for (i, triangle) in indices.chunks(6).enumerate() {
+            for &index in triangle {
+                let x = vertices[index].0.x;
+                assert!((x - i as f32 * 10.0).abs() <= 1.0 + 1e-5);
+            }
+        }
+    }
+
+    #[test]
+    fn rects_are_scaled_by_half_their_size() {
+        let mut batch = ShapeBatch::rects();
+        batch.push_rect(vec2(100.0, 50.0), vec2(20.0, 10.0), rgb(1.0, 0.0, 0.0));
+        let vertices: Vec<_> = batch.vertices().collect();
+        assert_close(vertices[0].0, vec2(90.0, 45.0));
+        assert_close(vertices[2].0, vec2(110.0, 55.0));
+        let red = rgb(1.0, 0.0, 0.0).into_lin_srgba();
+        assert!(vertices.iter().all(|(_, color)| *color == red));
+    }
+
+    #[test]
+    fn circles_are_scaled_by_their_radius() {
+        let mut batch = ShapeBatch::circles(4);
+        batch.push_circle(vec2(-10.0, 0.0), 5.0, BLUE);
+        batch.push_circle(vec2(10.0, 0.0), 2.0, WHITE);
+        let vertices: Vec<_> = batch.vertices().collect();
+        assert_eq!(vertices.len(), 10);
+        assert_close(vertices[0].0, vec2(-10.0, 0.0));
+        assert_close(vertices[1].0, vec2(-5.0, 0.0));
+        assert_close(vertices[2].0, vec2(-10.0, 5.0));
+        assert_close(vertices[5].0, vec2(10.0, 0.0));
+        assert_close(vertices[6].0, vec2(12.0, 0.0));
+        assert_eq!(vertices[0].1, BLUE.into_lin_srgba());
+        assert_eq!(vertices[9].1, WHITE.into_lin_srgba());
+    }
+
+    #[test]
+    fn instances_are_rotated() {
+        let mut batch = ShapeBatch::rects();
+        batch.push(Instance {
+            position: Vec2::ZERO,
+            scale: vec2(2.0, 1.0),
+            rotation: PI / 2.0,
+            color: WHITE.into_lin_srgba(),
+        });
+        let vertices: Vec<_> = batch.vertices().collect();
+        //(-2, -1) turned a quarter to the left
+        assert_close(vertices[0].0, vec2(1.0, -2.0));
+    }
+
+    #[test]
+    fn drawn_as_one_mesh() {
+        let mut batch = ShapeBatch::circles(DEFAULT_RESOLUTION);
+        let empty = record(|draw| batch.draw(draw));
+        empty.assert_count::<record::Mesh>(0);
+        for i in 0..100 {
+            batch.push_circle(vec2(i as f32, 0.0), 1.0, WHITE);
+        }
+        let recording = record(|draw| batch.draw(draw));
+        recording.assert_count::<record::Mesh>(1);
+        assert_eq!(recording.primitives.len(), 1);
+        batch.clear();
+        assert!(batch.is_empty());
+    }
+}
@@ -0,0 +1,493 @@
+//! Tools for cubic bezier curves, like the one we build in `p2_b`.
+//!
+//! A lyon `Path` is great for drawing, but it doesn't tell us where on the curve we are.
+//! `CubicBezier` can give the point, tangent and normal anywhere on the curve, split it in
+//! pieces, find its bounding box, and make offset curves for ribbons of varying thickness.
+//!
+//! A curve is evaluated with a parameter `t` going from `0.0` at the start to `1.0` at the end.
+//! Equal steps of `t` do _not_ give equally long steps along the curve, so `ArcLengthTable` is
+//! used to go from a distance along the curve to `t`.
+
+use nannou::lyon::path::path::Builder;
+use nannou::lyon::path::PathEvent;
+use nannou::lyon::math::point;
+use nannou::prelude::*;
+
+/// A cubic bezier curve with a start point, two control points and an end point.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct CubicBezier {
+    pub from: Vec2,
+    pub ctrl1: Vec2,
+    pub ctrl2: Vec2,
+    pub to: Vec2,
+}
+
+impl CubicBezier {
+    pub fn new(from: Vec2, ctrl1: Vec2, ctrl2: Vec2, to: Vec2) -> Self {
+        CubicBezier {
+            from,
+            ctrl1,
+            ctrl2,
+            to,
+        }
+    }
+
+    /// The curves in a path, e.g. `path.iter().filter_map(CubicBezier::from_event)`.
+    /// Straight lines and quadratic curves are converted to cubic curves.
+    pub fn from_event(event: PathEvent) -> Option<Self> {
+        let v = |p: nannou::lyon::math::Point| vec2(p.x, p.y);
+        match event {
+            PathEvent::Line { from, to } => Some(CubicBezier::line(v(from), v(to))),
+            PathEvent::Quadratic { from, ctrl, to } => {
+                Some(CubicBezier::quadratic(v(from), v(ctrl), v(to)))
+            }
+            PathEvent::Cubic {
+                from,
+                ctrl1,
+                ctrl2,
+                to,
+            } => Some(CubicBezier::new(v(from), v(ctrl1), v(ctrl2), v(to))),
+            PathEvent::End {
+                last, first, close, ..
+            } if close => Some(CubicBezier::line(v(last), v(first))),
+            _ => None,
+        }
+    }
+
+    /// A straight line as a cubic curve.
+    pub fn line(from: Vec2, to: Vec2) -> Self {
+        CubicBezier::new(from, from.lerp(to, 1.0 / 3.0), from.lerp(to, 2.0 / 3.0), to)
+    }
+
+    /// A quadratic curve as a cubic curve.
+    pub fn quadratic(from: Vec2, ctrl: Vec2, to: Vec2) -> Self {
+        CubicBezier::new(
+            from,
+            from + (ctrl - from) * (2.0 / 3.0),
+            to + (ctrl - to) * (2.0 / 3.0),
+            to,
+        )
+    }
+
+    /// Add the curve to a path builder, starting a new sub path at `from`.
+    pub fn append_to(&self, builder: &mut Builder) {
+        builder.begin(point(self.from.x, self.from.y));
+        builder.cubic_bezier_to(
+            point(self.ctrl1.x, self.ctrl1.y),
+            point(self.ctrl2.x, self.ctrl2.y),
+            point(self.to.x, self.to.y),
+        );
+        builder.end(false);
+    }
+
+    /// The point at `t`.
+    pub fn point(&self, t: f32) -> Vec2 {
+        let mt = 1.0 - t;
+        self.from * (mt * mt * mt)
+            + self.ctrl1 * (3.0 * mt * mt * t)
+            + self.ctrl2 * (3.0 * mt * t * t)
+            + self.to * (t * t * t)
+    }
+
+    /// The derivative at `t`, i.e. the direction and speed of the curve.
+    pub fn derivative(&self, t: f32) -> Vec2 {
+        let mt = 1.0 - t;
+        (self.ctrl1 - self.from) * (3.0 * mt * mt)
+            + (self.ctrl2 - self.ctrl1) * (6.0 * mt * t)
+            + (self.to - self.ctrl2) * (3.0 * t * t)
+    }
+
+    /// The direction of the curve at `t`, with length 1.0.
+    pub fn tangent(&self, t: f32) -> Vec2 {
+        let d = self.derivative(t);
+        if d.length_squared() > f32::EPSILON {
+            return d.normalize();
+        }
+        //The derivative is zero when a control point is on top of an end point, so we look a
+        // tiny bit further along the curve
+        let nudged = if t < 0.5 { t + 1e-3 } else { t - 1e-3 };
+        self.derivative(nudged).normalize_or_zero()
+    }
+
+    /// The tangent turned a quarter turn counter clockwise, i.e. pointing to the left of the
+    /// curve.
+    pub fn normal(&self, t: f32) -> Vec2 {
+        self.tangent(t).perp()
+    }
+
+    /// Split the curve in two at `t` (de Casteljau's algorithm).
+    pub fn split(&self, t: f32) -> (CubicBezier, CubicBezier) {
+        let ab = self.from.lerp(self.ctrl1, t);
+        let bc = self.ctrl1.lerp(self.ctrl2, t);
+        let cd = self.ctrl2.lerp(self.to, t);
+        let abc = ab.lerp(bc, t);
+        let bcd = bc.lerp(cd, t);
+        let p = abc.lerp(bcd, t);
+        (
+            CubicBezier::new(self.from, ab, abc, p),
+            CubicBezier::new(p, bcd, cd, self.to),
+        )
+    }
+
+    /// The part of the curve between `t0` and `t1`.
+    pub fn trim(&self, t0: f32, t1: f32) -> CubicBezier {
+        let (t0, t1) = (t0.clamp(0.0, 1.0), t1.clamp(0.0, 1.0));
+        if t1 <= t0 {
+            let p = self.point(t0);
+            return CubicBezier::new(p, p, p, p);
+        }
+        let (_, after) = self.split(t0);
+        let (between, _) = after.split((t1 - t0) / (1.0 - t0).max(f32::EPSILON));
+        between
+    }
+
+    /// The length of the curve, calculated by adding up `segments` straight pieces.
+    pub fn length(&self, segments: usize) -> f32 {
+        let segments = segments.max(1);
+        let mut length = 0.0;
+        let mut last = self.from;
+        for i in 1..=segments {
+            let p = self.point(i as f32 / segments as f32);
+            length += last.distance(p);
+            last = p;
+        }
+        length
+    }
+
+    /// The smallest rectangle the curve fits within.
+    ///
+    /// The curve can't go further out than where it turns, so we only have to check the end
+    /// points and the points where the derivative is zero along each axis.
+    pub fn bounding_rect(&self) -> Rect {
+        let mut min = self.from.min(self.to);
+        let mut max = self.from.max(self.to);
+        let axes = [
+            [self.from.x, self.ctrl1.x, self.ctrl2.x, self.to.x],
+            [self.from.y, self.ctrl1.y, self.ctrl2.y, self.to.y],
+        ];
+        for axis in axes.iter() {
+            for t in derivative_roots(*axis) {
+                let p = self.point(t);
+                min = min.min(p);
+                max = max.max(p);
+            }
+        }
+        let center = (min + max) * 0.5;
+        let size = max - min;
+        Rect::from_x_y_w_h(center.x, center.y, size.x, size.y)
+    }
+
+    /// Points along a curve offset `width(t)` pixels to the left of the curve.
+    /// Negative widths offsets to the right.
+    pub fn offset_points<F>(&self, samples: usize, width: F) -> Vec<Vec2>
+    where
+        F: Fn(f32) -> f32,
+    {
+        let samples = samples.max(2);
+        (0..samples)
+            .map(|i| {
+                let t = i as f32 / (samples - 1) as f32;
+                self.point(t) + self.normal(t) * width(t)
+            })
+            .collect()
+    }
+
+    /// The outline of a ribbon following the curve, where the thickness at `t` is
+    /// `thickness(t)`.
+    /// The points go along the left side and back along the right side, so they can be drawn
+    /// with `draw.polygon().points(...)`.
+    pub fn ribbon<F>(&self, samples: usize, thickness: F) -> Vec<Vec2>
+    where
+        F: Fn(f32) -> f32,
+    {
+        let mut points = self.offset_points(samples, |t| thickness(t) * 0.5);
+        let right = self.offset_points(samples, |t| -thickness(t) * 0.5);
+        points.extend(right.into_iter().rev());
+        points
+    }
+
+    /// A table for finding `t` from the distance along the curve.
+    pub fn arc_length_table(&self, samples: usize) -> ArcLengthTable {
+        ArcLengthTable::new(self, samples)
+    }
+}
+
+//The values of t within 0..1 where the derivative of one axis of the curve is zero.
+// The derivative of a cubic bezier is a quadratic: a*t^2 + b*t + c.
+fn derivative_roots(p: [f32; 4]) -> Vec<f32> {
+    let a = 3.0 * (-p[0] + 3.0 * p[1] - 3.0 * p[2] + p[3]);
+    let b = 6.0 * (p[0] - 2.0 * p[1] + p[2]);
+    let c = 3.0 * (p[1] - p[0]);
+    let mut roots = Vec::with_capacity(2);
+    if a.abs() < 1e-6 {
+        if b.abs() > 1e-6 {
+            roots.push(-c / b);
+        }
+    } else {
+        let discriminant = b * b - 4.0 * a * c;
+        if discriminant >= 0.0 {
+            let sqrt = discriminant.sqrt();
+            roots.push((-b + sqrt) / (2.0 * a));
+            roots.push((-b - sqrt) / (2.0 * a));
+        }
+    }
+    roots.retain(|t| (0.0..=1.0).contains(t));
+    roots
+}
+
+/// Maps distances along a curve to `t`, so that we can move along a curve with even speed.
+#[derive(Clone, Debug)]
+pub struct ArcLengthTable {
+    curve: CubicBezier,
+    //The distance along the curve at `t = i / (lengths.len() - 1)`
+    lengths: Vec<f32>,
+}
+
+impl ArcLengthTable {
+    pub fn new(curve: &CubicBezier, samples: usize) -> Self {
+        let samples = samples.max(2);
+        let mut lengths = Vec::with_capacity(samples);
+        let mut length = 0.0;
+        let mut last = curve.from;
+        lengths.push(0.0);
+        for i in 1..samples {
+            let p = curve.point(i as f32 / (samples - 1) as f32);
+            length += last.distance(p);
+            lengths.push(length);
+            last = p;
+        }
+        ArcLengthTable {
+            curve: *curve,
+            lengths,
+        }
+    }
+
+    /// The total length of the curve.
+    pub fn length(&self) -> f32 {
+        self.lengths[self.lengths.len() - 1]
+    }
+
+    /// The `t` that is `distance` pixels along the curve.
+    pub fn t_at_length(&self, distance: f32) -> f32 {
+        let distance = distance.clamp(0.0, self.length());
+        //Find the first sample that is past the distance, and interpolate between it and the one
+        // before
+        let i = self
+            .lengths
+            .partition_point(|&l| l < distance)
+            .clamp(1, self.lengths.len() - 1);
+        let (l0, l1) = (self.lengths[i - 1], self.lengths[i]);
+        let local = if l1 > l0 { (distance - l0) / (l1 - l0) } else { 0.0 };
+        (i as f32 - 1.0 + local) / (self.lengths.len() - 1) as f32
+    }
+
+    /// The `t` at a fraction of the length, from `0.0` to `1.0`.
+    pub fn t_at_fraction(&self, fraction: f32) -> f32 {
+        self.t_at_length(fraction * self.length())
+    }
+
+    /// The point `distance` pixels along the curve.
+    pub fn point_at_length(&self, distance: f32) -> Vec2 {
+        self.curve.point(self.t_at_length(distance))
+    }
+
+    /// `count` points with the same distance between them along the curve, including both end
+    /// points.
+    pub fn evenly_spaced(&self, count: usize) -> Vec<Vec2> {
+        match count {
+            0 => Vec::new(),
+            1 => vec![self.curve.from],
+            _ => (0..count)
+                .map(|i| self.curve.point(self.t_at_fraction(i as f32 / (count - 1) as f32)))
+                .collect(),
+        }
+    }
+
+    /// Points placed every `spacing` pixels along the curve, starting at the start point.
+    pub fn every(&self, spacing: f32) -> Vec<Vec2> {
+        if spacing <= 0.0 {
+            return Vec::new();
+        }
+        let count = (self.length() / spacing) as usize + 1;
+        (0..count)
+            .map(|i| self.point_at_length(i as f32 * spacing))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: Vec2, b: Vec2) {
+        assert!(a.distance(b) < 1e-4, "{:?} is not {:?}", a, b);
+    }
+
+    //A hump from (0, 0) to (1, 0), which is highest at t = 0.5
+    fn hump() -> CubicBezier {
+        CubicBezier::new(vec2(0.0, 0.0), vec2(0.0, 1.0), vec2(1.0, 1.0), vec2(1.0, 0.0))
+    }
+
+    #[test]
+    fn point_tangent_and_normal() {
+        let curve = hump();
+        assert_close(curve.point(0.0), vec2(0.0, 0.0));
+        assert_close(curve.point(1.0), vec2(1.0, 0.0));
+        assert_close(curve.point(0.5), vec2(0.5, 0.75));
+        assert_close(curve.derivative(0.5), vec2(1.5, 0.0));
+        assert_close(curve.tangent(0.0), vec2(0.0, 1.0));
+        assert_close(curve.tangent(0.5), vec2(1.0, 0.0));
+        assert_close(curve.tangent(1.0), vec2(0.0, -1.0));
+        assert_close(curve.normal(0.0), vec2(-1.0, 0.0));
+        assert_close(curve.normal(0.5), vec2(0.0, 1.0));
+    }
+
+    #[test]
+    fn tangent_where_the_derivative_is_zero() {
+        let p = vec2(1.0, 1.0);
+        let curve = CubicBezier::new(Vec2::ZERO, Vec2::ZERO, p, p);
+        assert_close(curve.tangent(0.0), p.normalize());
+        assert_close(curve.tangent(1.0), p.normalize());
+    }
+
+    #[test]
+    fn split_pieces_join_at_the_split_point() {
+        let curve = hump();
+        for &t in &[0.25, 0.5, 0.8] {
+            let (before, after) = curve.split(t);
+            assert_close(before.from, curve.from);
+            assert_close(before.to, curve.point(t));
+            assert_close(after.from, curve.point(t));
+            assert_close(after.to, curve.to);
+            //The pieces follow the original curve
+            assert_close(before.point(0.5), curve.point(t * 0.5));
+            assert_close(after.point(0.5), curve.point(t + (1.0 - t) * 0.5));
+        }
+        let trimmed = curve.trim(0.25, 0.75);
+        assert_close(trimmed.from, curve.point(0.25));
+        assert_close(trimmed.to, curve.point(0.75));
+        assert_close(trimmed.point(0.5), curve.point(0.5));
+    }
+
+    #[test]
+    fn bounding_rect_uses_the_derivative_roots() {
+        //x goes below 0 and beyond 1 before coming back. x'(t) = -24t^2 + 24t - 3, which is
+        // zero at t = (1 ± sqrt(0.5)) / 2
+        let curve =
+            CubicBezier::new(vec2(0.0, 0.0), vec2(-1.0, 1.0), vec2(2.0, 1.0), vec2(1.0, 0.0));
+        let t0 = (1.0 - 0.5f32.sqrt()) * 0.5;
+        let t1 = (1.0 + 0.5f32.sqrt()) * 0.5;
+        let mut roots = derivative_roots([0.0, -1.0, 2.0, 1.0]);
+        roots.sort_by(f32::total_cmp);
+        assert_eq!(roots.len(), 2);
+        assert!((roots[0] - t0).abs() < 1e-5);
+        assert!((roots[1] - t1).abs() < 1e-5);
+
+        let rect = curve.bounding_rect();
+        assert!((rect.left() - curve.point(t0).x).abs() < 1e-5);
+        assert!((rect.right() - curve.point(t1).x).abs() < 1e-5);
+        assert!(rect.left() < 0.0 && rect.right() > 1.0);
+        assert!((rect.top() - 0.75).abs() < 1e-5);
+        assert!(rect.bottom().abs() < 1e-5);
+
+        //Nothing on the curve is outside the rect
+        for i in 0..=1000 {
+            let p = curve.point(i as f32 / 1000.0);
+            assert!(p.x >= rect.left() - 1e-5 && p.x <= rect.right() + 1e-5);
+            assert!(p.y >= rect.bottom() - 1e-5 && p.y <= rect.top() + 1e-5);
+        }
+    }
+
+    #[test]
+    fn length_of_a_straight_line() {
+        let curve = CubicBezier::line(vec2(0.0, 0.0), vec2(30.0, 40.0));
+        assert!((curve.length(16) - 50.0).abs() < 1e-3);
+        let table = curve.arc_length_table(64);
+        assert!((table.length() - 50.0).abs() < 1e-3);
+        assert!(table.t_at_length(0.0).abs() < 1e-5);
+        assert!((table.t_at_length(25.0) - 0.5).abs() < 1e-4);
+        assert!((table.t_at_length(50.0) - 1.0).abs() < 1e-5);
+        assert!((table.t_at_length(100.0) - 1.0).abs() < 1e-5);
+        assert_close(table.point_at_length(10.0), vec2(6.0, 8.0));
+    }
+
+    //A straight line going up and right, with tangent (0.6, 0.8) and normal (-0.8, 0.6)
+    fn slope() -> CubicBezier {
+        CubicBezier::line(vec2(0.0, 0.0), vec2(30.0, 40.0))
+    }
+
+    #[test]
+    fn offsets_are_along_the_normal() {
+        let points = slope().offset_points(5, |_| 2.0);
+        assert_eq!(points.len(), 5);
+        for (i, &p) in points.iter().enumerate() {
+            let on_line = vec2(7.5, 10.0) * i as f32;
+            assert_close(p, on_line + vec2(-1.6, 1.2));
+        }
+        //Negative widths go to the right
+        let right = slope().offset_points(2, |_| -5.0);
+        assert_close(right[0], vec2(4.0, -3.0));
+        assert_close(right[1], vec2(34.0, 37.0));
+    }
+
+    #[test]
+    fn offsets_follow_the_width() {
+        let points = slope().offset_points(3, |t| t * 10.0);
+        assert_close(points[0], vec2(0.0, 0.0));
+        assert_close(points[1], vec2(15.0, 20.0) + vec2(-4.0, 3.0));
+        assert_close(points[2], vec2(30.0, 40.0) + vec2(-8.0, 6.0));
+
+        //On a curve, each offset point is `width` away from the curve at the same t
+        let curve = hump();
+        let points = curve.offset_points(11, |_| 0.25);
+        for (i, &p) in points.iter().enumerate() {
+            let t = i as f32 / 10.0;
+            assert!((p.distance(curve.point(t)) - 0.25).abs() < 1e-5);
+            assert!((p - curve.point(t)).dot(curve.tangent(t)).abs() < 1e-5);
+        }
+        assert_close(points[5], vec2(0.5, 1.0));
+    }
+
+    #[test]
+    fn ribbon_goes_out_on_the_left_and_back_on_the_right() {
+        //Thickness 2 at the start and 6 at the end, so 1 and 3 to each side
+        let ribbon = slope().ribbon(3, |t| 2.0 + 4.0 * t);
+        let normal = vec2(-0.8, 0.6);
+        let expected = [
+            vec2(0.0, 0.0) + normal,
+            vec2(15.0, 20.0) + normal * 2.0,
+            vec2(30.0, 40.0) + normal * 3.0,
+            vec2(30.0, 40.0) - normal * 3.0,
+            vec2(15.0, 20.0) - normal * 2.0,
+            vec2(0.0, 0.0) - normal,
+        ];
+        assert_eq!(ribbon.len(), expected.len());
+        for (&p, &e) in ribbon.iter().zip(expected.iter()) {
+            assert_close(p, e);
+        }
+    }
+
+    #[test]
+    fn evenly_spaced_points_are_evenly_spaced() {
+        let line = CubicBezier::line(vec2(0.0, 0.0), vec2(30.0, 40.0)).arc_length_table(64);
+        let points = line.evenly_spaced(5);
+        assert_eq!(points.len(), 5);
+        for pair in points.windows(2) {
+            assert!((pair[0].distance(pair[1]) - 12.5).abs() < 1e-3);
+        }
+
+        //On a curve, the points are spaced evenly along the curve, not in straight lines
+        let table = hump().arc_length_table(1024);
+        let points = table.evenly_spaced(10);
+        assert_close(points[0], hump().from);
+        assert_close(points[9], hump().to);
+        let spacing = table.length() / 9.0;
+        for (i, &p) in points.iter().take(9).enumerate() {
+            let t0 = table.t_at_fraction(i as f32 / 9.0);
+            let t1 = table.t_at_fraction((i + 1) as f32 / 9.0);
+            assert_close(hump().point(t0), p);
+            let between = hump().trim(t0, t1).length(64);
+            assert!((between - spacing).abs() < spacing * 0.01);
+        }
+    }
+}
@@ -0,0 +1,323 @@
+//! Check your solution to an exercise.
+//!
+//! `cargo run --bin check -- p0 f` compiles your `p0/src/main.rs`, renders a few frames of it
+//! without opening a window, and checks that it does what exercise `f` asks for.
+//! Give another file with `--file <path>`.
+
+use std::path::{Path, PathBuf};
+use std::process;
+
+use nannou::prelude::*;
+use workshop::check::{self, CapturedFrame, Outcome};
+
+//The times the sketches are rendered at, in seconds
+const TIMES: [f32; 5] = [1.0, 2.0, 3.0, 4.0, 5.0];
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let (part, exercise) = match parse_exercise(&args) {
+        Some(exercise) => exercise,
+        None => {
+            eprintln!("Usage: cargo run --bin check -- <part> <exercise> [--file <path>]");
+            eprintln!("E.g.:  cargo run --bin check -- p0 f");
+            process::exit(2);
+        }
+    };
+    let workspace = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .parent()
+        .expect("the workshop crate is in the workspace")
+        .to_path_buf();
+    let name = format!("{}_{}", part, exercise);
+    let suggestion = workspace
+        .join(&part)
+        .join("src")
+        .join("suggestion")
+        .join(format!("{}.rs", name));
+    if !suggestion.exists() {
+        eprintln!("There is no exercise {} (looked for {})", name, suggestion.display());
+        process::exit(2);
+    }
+    let attempt = args
+        .iter()
+        .position(|a| a == "--file")
+        .and_then(|i| args.get(i + 1))
+        .map(PathBuf::from)
+        .unwrap_or_else(|| workspace.join(&part).join("src").join("main.rs"));
+
+    println!("Checking {} against exercise {}", attempt.display(), name);
+    let frames = match check::render(&workspace, &attempt, &name, &TIMES) {
+        Ok(frames) if !frames.is_empty() => frames,
+        Ok(_) => fail_early("no frames were rendered"),
+        Err(e) => fail_early(&e.to_string()),
+    };
+
+    let outcomes = match name.as_str() {
+        "p0_f" => check_p0_f(&frames),
+        _ => {
+            //For the other exercises we compare the colours with the suggestion
+            let suggestion_name = format!("{}_suggestion", name);
+            let expected = match check::render(&workspace, &suggestion, &suggestion_name, &TIMES) {
+                Ok(frames) => frames,
+                Err(e) => fail_early(&format!("could not render the suggestion: {}", e)),
+            };
+            compare_coverage(&frames, &expected)
+        }
+    };
+
+    let mut failed = 0;
+    for outcome in outcomes.iter() {
+        match outcome {
+            Outcome::Pass(check) => println!("  PASS {}", check),
+            Outcome::Fail { check, hint } => {
+                failed += 1;
+                println!("  FAIL {}", check);
+                println!("       hint: {}", hint);
+            }
+        }
+    }
+    if failed == 0 {
+        println!("All {} checks passed!", outcomes.len());
+    } else {
+        println!("{} of {} checks failed.", failed, outcomes.len());
+        process::exit(1);
+    }
+}
+
+//Accepts both `p0 f` and `p0_f`
+fn parse_exercise(args: &[String]) -> Option<(String, String)> {
+    let first = args.first()?;
+    if let Some((part, exercise)) = first.split_once('_') {
+        return Some((part.to_string(), exercise.to_string()));
+    }
+    let exercise = args.get(1).filter(|a| !a.starts_with("--"))?;
+    Some((first.clone(), exercise.clone()))
+}
+
+fn fail_early(message: &str) -> ! {
+    println!("  FAIL {}", message);
+    process::exit(1);
+}
+
+//p0_f: a magenta circle moving from side to side, with a smaller circle orbiting it
+fn check_p0_f(frames: &[CapturedFrame]) -> Vec<Outcome> {
+    if frames.is_empty() {
+        return vec![no_frames()];
+    }
+    let mut outcomes = Vec::new();
+    let magenta: Vec<_> = frames.iter().map(|f| f.blob(MAGENTA, 40)).collect();
+    let found = magenta.iter().all(Option::is_some);
+    outcomes.push(Outcome::new(
+        found,
+        "there is a magenta circle",
+        "draw an ellipse with `.color(MAGENTA)`",
+    ));
+    if !found {
+        return outcomes;
+    }
+    let magenta: Vec<_> = magenta.into_iter().flatten().collect();
+
+    let radius_ok = magenta.iter().all(|b| (b.radius - 50.0).abs() < 6.0);
+    outcomes.push(Outcome::new(
+        radius_ok,
+        "the magenta circle has a radius of about 50",
+        format!(
+            "the magenta circle has a radius of about {:.0}, use `.radius(50.0)`",
+            magenta[0].radius
+        ),
+    ));
+
+    let moves = magenta
+        .iter()
+        .any(|b| b.center.distance(magenta[0].center) > 10.0);
+    outcomes.push(Outcome::new(
+        moves,
+        "the magenta circle moves",
+        "use `app.time` in the position of the magenta circle, e.g. `(app.time * 0.5).sin()`",
+    ));
+
+    //The orbiting circle can have any colour, as long as it is not the background or magenta.
+    // The anti-aliased edge of the magenta circle is neither, so the pixels just around it don't
+    // count
+    let orbiters: Vec<_> = frames
+        .iter()
+        .zip(magenta.iter())
+        .map(|(f, m)| {
+            let background = f.background();
+            f.blob_where_at(|p, c| {
+                p.distance(m.center) > m.radius + 3.0
+                    && !check::close(c, background, 40)
+                    && !check::close(c, [255, 0, 255], 40)
+            })
+        })
+        .collect();
+    let found = orbiters.iter().all(Option::is_some);
+    outcomes.push(Outcome::new(
+        found,
+        "there is a second circle",
+        "draw another ellipse, in a colour other than the background and magenta",
+    ));
+    if !found {
+        return outcomes;
+    }
+    let orbiters: Vec<_> = orbiters.into_iter().flatten().collect();
+
+    let radius_ok = orbiters.iter().all(|b| (b.radius - 25.0).abs() < 5.0);
+    outcomes.push(Outcome::new(
+        radius_ok,
+        "the second circle has a radius of about 25",
+        format!(
+            "the second circle has a radius of about {:.0}, it should be half the radius of the magenta circle",
+            orbiters[0].radius
+        ),
+    ));
+
+    let distances: Vec<f32> = magenta
+        .iter()
+        .zip(orbiters.iter())
+        .map(|(m, o)| m.center.distance(o.center))
+        .collect();
+    let follows = distances.iter().all(|d| (d - 100.0).abs() < 15.0);
+    let worst = distances
+        .iter()
+        .copied()
+        .max_by(|a, b| (a - 100.0).abs().total_cmp(&(b - 100.0).abs()))
+        .unwrap_or(0.0);
+    outcomes.push(Outcome::new(
+        follows,
+        "the second circle stays about 100 from the magenta circle",
+        format!(
+            "the distance between the circles was {:.0}. Draw the second circle with `draw.xy(pos_a)`, so that it is placed relative to the magenta circle",
+            worst
+        ),
+    ));
+
+    let angles: Vec<f32> = magenta
+        .iter()
+        .zip(orbiters.iter())
+        .map(|(m, o)| {
+            let d = o.center - m.center;
+            d.y.atan2(d.x)
+        })
+        .collect();
+    let orbits = angles.windows(2).any(|a| {
+        let turn = (a[1] - a[0] + PI).rem_euclid(TAU) - PI;
+        turn.abs() > 0.1
+    });
+    outcomes.push(Outcome::new(
+        orbits,
+        "the second circle goes around the magenta circle",
+        "use `sin` and `cos` of `app.time` for the x and y position of the second circle",
+    ));
+    outcomes
+}
+
+//Each colour that covers a good part of the suggestion should cover about as much of the attempt
+fn compare_coverage(frames: &[CapturedFrame], expected: &[CapturedFrame]) -> Vec<Outcome> {
+    if frames.is_empty() || expected.is_empty() {
+        return vec![no_frames()];
+    }
+    let average = |frames: &[CapturedFrame], color: [u8; 3]| -> f32 {
+        let sum: f32 = frames
+            .iter()
+            .map(|f| {
+                f.coverage()
+                    .iter()
+                    .filter(|(c, _)| check::close(*c, color, 32))
+                    .map(|(_, n)| n)
+                    .sum::<f32>()
+            })
+            .sum();
+        sum / frames.len().max(1) as f32
+    };
+    let colors: Vec<[u8; 3]> = expected[0]
+        .coverage()
+        .into_iter()
+        .filter(|(_, n)| *n > 0.005)
+        .take(5)
+        .map(|(c, _)| c)
+        .collect();
+    colors
+        .into_iter()
+        .map(|color| {
+            let want = average(expected, color);
+            let got = average(frames, color);
+            let ok = (got - want).abs() <= (want * 0.5).max(0.01);
+            Outcome::new(
+                ok,
+                format!(
+                    "rgb({}, {}, {}) covers about {:.1}% of the window",
+                    color[0],
+                    color[1],
+                    color[2],
+                    want * 100.0
+                ),
+                format!(
+                    "it covers {:.1}% in your sketch. Compare the sizes and colours of your shapes with the exercise",
+                    got * 100.0
+                ),
+            )
+        })
+        .collect()
+}
+
+fn no_frames() -> Outcome {
+    Outcome::new(
+        false,
+        "the sketch was rendered",
+        "no frames were rendered, so there was nothing to check",
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nannou::image::{Rgba, RgbaImage};
+
+    //What the `p0_f` suggestion draws at `time`, with anti-aliased edges
+    fn p0_f_frame(time: f32) -> CapturedFrame {
+        let (w, h) = (1024, 768);
+        let pos_a = vec2((time * 0.5).sin() * w as f32 * 0.25, 0.0);
+        let pos_b = pos_a + vec2((3.0 * time).sin(), (3.0 * time).cos()) * 100.0;
+        let circles = [(pos_a, 50.0, [255.0, 0.0, 255.0]), (pos_b, 25.0, [255.0, 165.0, 0.0])];
+        let image = RgbaImage::from_fn(w, h, |x, y| {
+            let p = vec2(x as f32 + 0.5 - w as f32 * 0.5, h as f32 * 0.5 - y as f32 - 0.5);
+            let mut color = [0.0, 255.0, 255.0];
+            for (center, radius, circle_color) in circles.iter() {
+                let coverage = (radius - p.distance(*center) + 0.5).clamp(0.0, 1.0);
+                for (c, target) in color.iter_mut().zip(circle_color.iter()) {
+                    *c += (target - *c) * coverage;
+                }
+            }
+            Rgba([color[0] as u8, color[1] as u8, color[2] as u8, 255])
+        });
+        CapturedFrame {
+            time,
+            scale_factor: 1.0,
+            image,
+        }
+    }
+
+    #[test]
+    fn p0_f_suggestion_passes() {
+        let frames: Vec<_> = TIMES.iter().map(|&t| p0_f_frame(t)).collect();
+        for outcome in check_p0_f(&frames) {
+            assert!(outcome.passed(), "{:?}", outcome);
+        }
+    }
+
+    #[test]
+    fn no_frames_fail() {
+        assert_eq!(check_p0_f(&[]), vec![no_frames()]);
+        let frames = vec![p0_f_frame(1.0)];
+        assert_eq!(compare_coverage(&frames, &[]), vec![no_frames()]);
+        assert_eq!(compare_coverage(&[], &frames), vec![no_frames()]);
+    }
+
+    #[test]
+    fn same_frames_have_the_same_coverage() {
+        let frames: Vec<_> = TIMES.iter().map(|&t| p0_f_frame(t)).collect();
+        let outcomes = compare_coverage(&frames, &frames);
+        assert!(!outcomes.is_empty());
+        assert!(outcomes.iter().all(Outcome::passed));
+    }
+}
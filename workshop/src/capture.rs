@@ -0,0 +1,109 @@
+//! Save frames to disk together with the metadata needed to recreate them.
+//!
+//! Next to every captured image we write a small `.json` file with the sketch name, the frame
+//! number, the app time and the random seed, so a nice frame can be rendered again later with
+//! `--seed <number>`.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use nannou::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// The key that captures the current frame in the sketches.
+pub const CAPTURE_KEY: Key = Key::S;
+
+/// Everything we need to know to render the same frame again.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct FrameMetadata {
+    pub sketch: String,
+    pub frame: u64,
+    pub time: f32,
+    pub seed: u64,
+}
+
+impl FrameMetadata {
+    pub fn new(app: &App, seed: u64) -> Self {
+        FrameMetadata {
+            sketch: sketch_name(app),
+            frame: app.elapsed_frames(),
+            time: app.time,
+            seed,
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        let mut json = serde_json::to_string_pretty(self).expect("the metadata is always valid JSON");
+        json.push('\n');
+        json
+    }
+}
+
+/// The folder captures for this sketch are saved to, i.e. `captures/<sketch name>`.
+pub fn capture_dir(app: &App) -> PathBuf {
+    Path::new("captures").join(sketch_name(app))
+}
+
+/// Capture the next frame of the main window as a PNG, and write the metadata next to it.
+/// Returns the path of the image.
+pub fn capture_frame(app: &App, metadata: &FrameMetadata) -> io::Result<PathBuf> {
+    let dir = capture_dir(app);
+    fs::create_dir_all(&dir)?;
+    let image_path = dir.join(format!("frame_{:06}.png", metadata.frame));
+    app.main_window().capture_frame(&image_path);
+    fs::write(image_path.with_extension("json"), metadata.to_json())?;
+    println!("Captured {}", image_path.display());
+    Ok(image_path)
+}
+
+fn sketch_name(app: &App) -> String {
+    app.exe_name().unwrap_or_else(|_| "sketch".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::Randomness;
+
+    fn metadata(sketch: &str, seed: u64) -> FrameMetadata {
+        FrameMetadata {
+            sketch: sketch.to_string(),
+            frame: 120,
+            time: 2.5,
+            seed,
+        }
+    }
+
+    #[test]
+    fn json_has_every_field() {
+        let json = metadata("skyline", 42).to_json();
+        let json: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(json["sketch"], "skyline");
+        assert_eq!(json["frame"], 120);
+        assert_eq!(json["time"], 2.5);
+        assert_eq!(json["seed"], 42);
+    }
+
+    #[test]
+    fn json_round_trips() {
+        //Names with quotes, backslashes and control characters must still give valid JSON
+        for name in ["skyline", "a \"quoted\" name", "back\\slash", "new\nline\ttab"] {
+            let original = metadata(name, u64::MAX);
+            let read: FrameMetadata = serde_json::from_str(&original.to_json()).unwrap();
+            assert_eq!(read, original);
+        }
+    }
+
+    #[test]
+    fn seed_recreates_the_frame() {
+        let mut randomness = Randomness::new(0x5eed_1234_abcd_ef01);
+        let original = metadata("skyline", randomness.seed());
+        let drawn: Vec<f32> = (0..10).map(|_| randomness.stream("buildings").f32()).collect();
+
+        let read: FrameMetadata = serde_json::from_str(&original.to_json()).unwrap();
+        let mut again = Randomness::new(read.seed);
+        let redrawn: Vec<f32> = (0..10).map(|_| again.stream("buildings").f32()).collect();
+        assert_eq!(redrawn, drawn);
+    }
+}
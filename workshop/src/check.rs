@@ -0,0 +1,561 @@
+//! Rendering sketches without looking at them, and finding shapes in the rendered frames.
+//!
+//! This is what the `check` binary uses to tell attendees whether their sketch does what the
+//! exercise asks for.
+//! The sketch is copied into a small _harness_ crate, where its `main` is replaced by one that
+//! runs it as a `sketch::Headless`, and nannou's `App` by a stand-in that gets its time from
+//! the `Headless` clock. So the frames are rendered offscreen at exactly the times asked for,
+//! without a window, however fast or slow the computer is.
+//! The frames are then searched for blobs of colour, e.g. "a magenta circle with a radius of
+//! about 50".
+
+use std::fs;
+use std::future::Future;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::process::Command;
+use std::sync::mpsc;
+use std::sync::Arc;
+use std::task::{Context as TaskContext, Poll, Wake, Waker};
+
+use nannou::image::{self, RgbaImage};
+use nannou::prelude::*;
+use nannou::wgpu;
+
+use crate::sketch::{Headless, Sketch};
+
+/// The sketch is stepped at this many frames per second between the times it is rendered at.
+pub const FRAMES_PER_SECOND: f32 = 60.0;
+
+//Added to the sketch, in place of its `main`. `App`, `Frame` and `Update` shadow the ones from
+// `nannou::prelude`, so the sketch's `model`, `update` and `view` can run without a window.
+// `App` only has what the exercises use: `app.time`, `app.draw()` and `app.window_rect()`.
+const HARNESS: &str = r#"
+struct App {
+    time: f32,
+    draw: Draw,
+    window_rect: Rect,
+}
+
+#[allow(dead_code)]
+impl App {
+    fn new(ctx: &workshop::sketch::Context, draw: &Draw) -> Self {
+        App {
+            time: ctx.time,
+            draw: draw.clone(),
+            window_rect: ctx.window_rect,
+        }
+    }
+
+    fn draw(&self) -> Draw {
+        self.draw.clone()
+    }
+
+    fn window_rect(&self) -> Rect {
+        self.window_rect
+    }
+}
+
+#[allow(dead_code)]
+struct Update {
+    since_last: std::time::Duration,
+    since_start: std::time::Duration,
+}
+
+struct Frame;
+
+//The sketch calls `check_to_frame` where it called `to_frame`, as there is no frame to draw to:
+// `check::render_headless` renders the draw instead
+trait CheckToFrame {
+    fn check_to_frame(&self, app: &App, frame: &Frame) -> Result<(), ()>;
+}
+
+impl CheckToFrame for Draw {
+    fn check_to_frame(&self, _app: &App, _frame: &Frame) -> Result<(), ()> {
+        Ok(())
+    }
+}
+
+struct Checked {
+    model: Model,
+}
+
+impl workshop::sketch::Sketch for Checked {
+    //The size of a nannou window that doesn't set one
+    const SIZE: (u32, u32) = (1024, 768);
+
+    fn init(ctx: &workshop::sketch::Context) -> Self {
+        Checked {
+            model: model(&App::new(ctx, &Draw::new())),
+        }
+    }
+
+    fn update(&mut self, ctx: &workshop::sketch::Context, dt: f32) {
+        let event = Update {
+            since_last: std::time::Duration::from_secs_f32(dt),
+            since_start: std::time::Duration::from_secs_f32(ctx.time),
+        };
+        update(&App::new(ctx, &Draw::new()), &mut self.model, event);
+    }
+
+    fn view(&self, ctx: &workshop::sketch::Context, draw: &Draw) {
+        view(&App::new(ctx, draw), &self.model, Frame);
+    }
+}
+
+fn main() {
+    let times: Vec<f32> = std::env::var("CHECK_TIMES")
+        .unwrap_or_default()
+        .split(',')
+        .filter_map(|s| s.parse().ok())
+        .collect();
+    let dir = std::path::PathBuf::from(std::env::var("CHECK_DIR").unwrap_or_default());
+    if let Err(e) = workshop::check::capture::<Checked>(&times, &dir) {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+}
+"#;
+
+/// A frame captured from a sketch.
+#[derive(Clone, Debug)]
+pub struct CapturedFrame {
+    pub time: f32,
+    /// Pixels per point, e.g. 2.0 on a retina display.
+    pub scale_factor: f32,
+    pub image: RgbaImage,
+}
+
+/// A connected area of pixels of the same colour, measured as if it was a circle.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Blob {
+    /// The center in window coordinates, i.e. the same coordinates as the sketch uses.
+    pub center: Vec2,
+    /// The radius of a circle with the same area, in points.
+    pub radius: f32,
+    /// The area in points.
+    pub area: f32,
+}
+
+impl CapturedFrame {
+    /// The colour in the top left corner, which is the background in all the exercises.
+    pub fn background(&self) -> [u8; 3] {
+        rgb_of(self.image.get_pixel(0, 0))
+    }
+
+    /// The pixels close to `color`, within `tolerance` on each channel.
+    pub fn blob(&self, color: Rgb<u8>, tolerance: u8) -> Option<Blob> {
+        let target = [color.red, color.green, color.blue];
+        self.blob_where(|c| close(c, target, tolerance))
+    }
+
+    /// The pixels where `select` returns `true`.
+    pub fn blob_where<F>(&self, select: F) -> Option<Blob>
+    where
+        F: Fn([u8; 3]) -> bool,
+    {
+        self.blob_where_at(|_, c| select(c))
+    }
+
+    /// The pixels where `select` returns `true` for their position, in window coordinates, and
+    /// their colour. E.g. to leave out the area around another blob, where its anti-aliased
+    /// edge has colours of its own.
+    pub fn blob_where_at<F>(&self, select: F) -> Option<Blob>
+    where
+        F: Fn(Vec2, [u8; 3]) -> bool,
+    {
+        let (w, h) = self.image.dimensions();
+        let mut sum = Vec2::ZERO;
+        let mut count = 0.0;
+        for (x, y, pixel) in self.image.enumerate_pixels() {
+            //Image pixels go down from the top left, nannou goes up from the center
+            let p = vec2(x as f32 + 0.5 - w as f32 * 0.5, h as f32 * 0.5 - y as f32 - 0.5);
+            if select(p / self.scale_factor, rgb_of(pixel)) {
+                sum += p;
+                count += 1.0;
+            }
+        }
+        if count == 0.0 {
+            return None;
+        }
+        let area = count / (self.scale_factor * self.scale_factor);
+        Some(Blob {
+            center: sum / count / self.scale_factor,
+            radius: (area / PI).sqrt(),
+            area,
+        })
+    }
+
+    /// How much of the frame each colour covers, from `0.0` to `1.0`, with the most used colour
+    /// first. Colours are rounded to steps of 32 so that anti-aliased edges don't count as
+    /// colours of their own.
+    pub fn coverage(&self) -> Vec<([u8; 3], f32)> {
+        let mut counts: Vec<([u8; 3], usize)> = Vec::new();
+        for pixel in self.image.pixels() {
+            let c = rgb_of(pixel).map(|v| ((v as u16 + 16) / 32 * 32).min(255) as u8);
+            match counts.iter_mut().find(|(color, _)| *color == c) {
+                Some((_, n)) => *n += 1,
+                None => counts.push((c, 1)),
+            }
+        }
+        let total = (self.image.width() * self.image.height()).max(1) as f32;
+        counts.sort_by_key(|&(_, n)| std::cmp::Reverse(n));
+        counts
+            .into_iter()
+            .map(|(color, n)| (color, n as f32 / total))
+            .collect()
+    }
+}
+
+/// Whether two colours are within `tolerance` on each channel.
+pub fn close(a: [u8; 3], b: [u8; 3], tolerance: u8) -> bool {
+    a.iter()
+        .zip(b.iter())
+        .all(|(&x, &y)| (x as i16 - y as i16).unsigned_abs() <= tolerance as u16)
+}
+
+fn rgb_of(pixel: &image::Rgba<u8>) -> [u8; 3] {
+    [pixel[0], pixel[1], pixel[2]]
+}
+
+/// Turn the source of a sketch into a harness that renders it headless.
+/// Returns `None` if the sketch doesn't have the `main`, `model`, `update` and `view` functions
+/// from the workshop template.
+pub fn harness_source(sketch: &str) -> Option<String> {
+    for function in ["fn model(", "fn update(", "fn view("] {
+        if !sketch.contains(function) {
+            return None;
+        }
+    }
+    //`main` starts a nannou app, which needs a window, so it goes
+    let start = sketch.find("fn main()")?;
+    let open = start + sketch[start..].find('{')?;
+    let mut depth = 0;
+    let mut end = None;
+    for (i, c) in sketch[open..].char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' => depth -= 1,
+            _ => continue,
+        }
+        if depth == 0 {
+            end = Some(open + i + 1);
+            break;
+        }
+    }
+    let sketch = format!("{}{}", &sketch[..start], &sketch[end?..]);
+    let sketch = sketch.replace(".to_frame(", ".check_to_frame(");
+    Some(format!("{}\n{}", sketch, HARNESS))
+}
+
+/// Compile the sketch at `source` in a harness, run it, and return the frames rendered at
+/// `times` seconds.
+///
+/// `workspace` is the root of the workshop repo. The harness is built in
+/// `target/check/<name>`, sharing the `target` folder and `Cargo.lock` of the workspace so
+/// nannou isn't compiled again.
+/// Compiler errors are printed as usual, and returned as an error.
+pub fn render(
+    workspace: &Path,
+    source: &Path,
+    name: &str,
+    times: &[f32],
+) -> io::Result<Vec<CapturedFrame>> {
+    let sketch = fs::read_to_string(source)?;
+    let harness = harness_source(&sketch).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "{} must have the `main`, `model`, `update` and `view` functions of the workshop template",
+                source.display()
+            ),
+        )
+    })?;
+
+    let dir = workspace.join("target").join("check").join(name);
+    let frames_dir = dir.join("frames");
+    let _ = fs::remove_dir_all(&frames_dir);
+    fs::create_dir_all(dir.join("src"))?;
+    fs::create_dir_all(&frames_dir)?;
+    fs::write(
+        dir.join("Cargo.toml"),
+        format!(
+            "[package]\nname = \"check-{}\"\nversion = \"0.1.0\"\nedition = \"2021\"\n\n\
+             [dependencies]\nnannou = \"0.18.1\"\nworkshop = {{ path = {:?} }}\n\n[workspace]\n",
+            name.replace('_', "-"),
+            workspace.join("workshop").display().to_string()
+        ),
+    )?;
+    fs::write(dir.join("src").join("main.rs"), harness)?;
+    if let Ok(lock) = fs::read(workspace.join("Cargo.lock")) {
+        fs::write(dir.join("Cargo.lock"), lock)?;
+    }
+
+    let times_arg: Vec<String> = times.iter().map(|t| t.to_string()).collect();
+    let status = Command::new(std::env::var("CARGO").unwrap_or_else(|_| "cargo".to_string()))
+        .arg("run")
+        .arg("--quiet")
+        .arg("--manifest-path")
+        .arg(dir.join("Cargo.toml"))
+        .env("CARGO_TARGET_DIR", workspace.join("target"))
+        .env("CHECK_TIMES", times_arg.join(","))
+        .env("CHECK_DIR", &frames_dir)
+        .status()?;
+    if !status.success() {
+        return Err(io::Error::other(format!(
+            "{} did not compile or run ({})",
+            source.display(),
+            status
+        )));
+    }
+    read_frames(&frames_dir)
+}
+
+/// Render `S` at `times` with `render_headless`, and save the frames to `dir` for `render` to
+/// read. This is the `main` of the harness.
+pub fn capture<S: Sketch>(times: &[f32], dir: &Path) -> io::Result<()> {
+    fs::create_dir_all(dir)?;
+    let mut index = String::new();
+    for (i, frame) in render_headless::<S>(times)?.iter().enumerate() {
+        let path = dir.join(format!("frame_{}.png", i));
+        frame
+            .image
+            .save(&path)
+            .map_err(|e| {
+                io::Error::other(format!("{}: {}", path.display(), e))
+            })?;
+        index.push_str(&format!("{} {} {}\n", i, frame.time, frame.scale_factor));
+    }
+    fs::write(dir.join("frames.txt"), index)
+}
+
+/// Step `S` in a `Headless` at `FRAMES_PER_SECOND`, and render a frame at each of `times`
+/// into a texture of `S::SIZE`. No window is opened, only the GPU is used.
+pub fn render_headless<S: Sketch>(times: &[f32]) -> io::Result<Vec<CapturedFrame>> {
+    let (device, queue) = headless_device()?;
+    let (w, h) = S::SIZE;
+    let texture = wgpu::TextureBuilder::new()
+        .size([w, h])
+        .usage(wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING)
+        .sample_count(1)
+        .format(wgpu::TextureFormat::Rgba8UnormSrgb)
+        .build(&device);
+    let mut renderer = nannou::draw::RendererBuilder::new()
+        .build_from_texture_descriptor(&device, texture.descriptor());
+    let capturer = wgpu::TextureCapturer::default();
+
+    let mut sketch = Headless::<S>::new();
+    let mut frames = Vec::new();
+    for &time in times {
+        sketch.run_until(time, 1.0 / FRAMES_PER_SECOND);
+        let draw = Draw::new();
+        sketch.view(&draw);
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("check"),
+        });
+        renderer.render_to_texture(&device, &mut encoder, &draw, &texture);
+        let snapshot = capturer.capture(&device, &mut encoder, &texture);
+        queue.submit(Some(encoder.finish()));
+
+        let (sender, receiver) = mpsc::channel();
+        snapshot
+            .read(move |result| {
+                let _ = sender.send(result.map(|buffer| buffer.to_owned()));
+            })
+            .map_err(|e| io::Error::other(format!("{:?}", e)))?;
+        //One poll can return before the buffer is mapped, so poll until the read is done
+        capturer
+            .await_active_snapshots(&device)
+            .map_err(|e| io::Error::other(format!("{:?}", e)))?;
+        let image = receiver
+            .recv()
+            .map_err(io::Error::other)?
+            .map_err(|e| io::Error::other(format!("{:?}", e)))?;
+        frames.push(CapturedFrame {
+            time: sketch.ctx.time,
+            scale_factor: 1.0,
+            image,
+        });
+    }
+    Ok(frames)
+}
+
+//A GPU device without a window or surface
+fn headless_device() -> io::Result<(wgpu::Device, wgpu::Queue)> {
+    let instance = wgpu::Instance::new(wgpu::Backends::PRIMARY);
+    let adapter = block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
+        power_preference: wgpu::PowerPreference::default(),
+        compatible_surface: None,
+        force_fallback_adapter: false,
+    }))
+    .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no GPU found to render on"))?;
+    block_on(adapter.request_device(
+        &wgpu::DeviceDescriptor {
+            label: Some("check"),
+            features: wgpu::Features::empty(),
+            limits: wgpu::Limits::default(),
+        },
+        None,
+    ))
+    .map_err(|e| io::Error::other(format!("{:?}", e)))
+}
+
+//Just enough of an async runtime to wait for the adapter and device
+fn block_on<F: Future>(future: F) -> F::Output {
+    struct Unpark(std::thread::Thread);
+
+    impl Wake for Unpark {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    let waker = Waker::from(Arc::new(Unpark(std::thread::current())));
+    let mut cx = TaskContext::from_waker(&waker);
+    let mut future = Box::pin(future);
+    loop {
+        match Pin::as_mut(&mut future).poll(&mut cx) {
+            Poll::Ready(output) => return output,
+            Poll::Pending => std::thread::park(),
+        }
+    }
+}
+
+fn read_frames(dir: &Path) -> io::Result<Vec<CapturedFrame>> {
+    let index = fs::read_to_string(dir.join("frames.txt"))?;
+    let mut frames = Vec::new();
+    for line in index.lines() {
+        let parts: Vec<&str> = line.split_whitespace().collect();
+        let (i, time, scale_factor) = match parts.as_slice() {
+            [i, time, scale] => (*i, time.parse().unwrap_or(0.0), scale.parse().unwrap_or(1.0)),
+            _ => continue,
+        };
+        let path: PathBuf = dir.join(format!("frame_{}.png", i));
+        let image = image::open(&path)
+            .map_err(|e| io::Error::other(format!("{}: {}", path.display(), e)))?
+            .to_rgba8();
+        frames.push(CapturedFrame {
+            time,
+            scale_factor,
+            image,
+        });
+    }
+    Ok(frames)
+}
+
+/// The result of one check.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Outcome {
+    Pass(String),
+    Fail { check: String, hint: String },
+}
+
+impl Outcome {
+    /// A pass or a fail with a hint, depending on `ok`.
+    pub fn new(ok: bool, check: impl Into<String>, hint: impl Into<String>) -> Self {
+        if ok {
+            Outcome::Pass(check.into())
+        } else {
+            Outcome::Fail {
+                check: check.into(),
+                hint: hint.into(),
+            }
+        }
+    }
+
+    pub fn passed(&self) -> bool {
+        matches!(self, Outcome::Pass(_))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SKETCH: &str = "use nannou::prelude::*;
+
+fn main() {
+    nannou::app(model).update(update).simple_window(view).run();
+}
+
+struct Model {}
+
+fn model(_app: &App) -> Model {
+    Model {}
+}
+
+fn update(_app: &App, _model: &mut Model, _update: Update) {}
+
+fn view(app: &App, _model: &Model, frame: Frame) {
+    let draw = app.draw();
+    if app.time > 1.0 {
+        draw.background().color(CYAN);
+    }
+    draw.to_frame(app, &frame).unwrap();
+}
+";
+
+    //A cyan frame of 40 by 20 with a magenta square of 8 by 8 around (10, 0)
+    fn frame() -> CapturedFrame {
+        let image = RgbaImage::from_fn(40, 20, |x, y| {
+            if (26..34).contains(&x) && (6..14).contains(&y) {
+                image::Rgba([255, 0, 255, 255])
+            } else {
+                image::Rgba([0, 255, 255, 255])
+            }
+        });
+        CapturedFrame {
+            time: 1.0,
+            scale_factor: 1.0,
+            image,
+        }
+    }
+
+    #[test]
+    fn harness_replaces_main() {
+        let harness = harness_source(SKETCH).unwrap();
+        assert!(!harness.contains("nannou::app(model)"));
+        assert!(!harness.contains(".to_frame("));
+        assert!(harness.contains("draw.check_to_frame(app, &frame).unwrap();"));
+        //The rest of the sketch is kept as it is
+        assert!(harness.contains("    if app.time > 1.0 {\n        draw.background()"));
+        assert_eq!(harness.matches("fn main()").count(), 1);
+    }
+
+    #[test]
+    fn harness_needs_the_template() {
+        assert!(harness_source(&SKETCH.replace("fn view(", "fn show(")).is_none());
+        assert!(harness_source(&SKETCH.replace("fn main()", "fn start()")).is_none());
+    }
+
+    #[test]
+    fn blob_of_a_color() {
+        let frame = frame();
+        assert_eq!(frame.background(), [0, 255, 255]);
+        let blob = frame.blob(MAGENTA, 40).unwrap();
+        assert_eq!(blob.center, vec2(10.0, 0.0));
+        assert_eq!(blob.area, 64.0);
+        assert!(frame.blob(ORANGE, 40).is_none());
+    }
+
+    #[test]
+    fn blob_at_positions() {
+        let frame = frame();
+        let left = frame.blob_where_at(|p, _| p.x < 0.0).unwrap();
+        assert_eq!(left.center, vec2(-10.0, 0.0));
+        assert_eq!(left.area, 400.0);
+        let away = frame.blob_where_at(|p, c| p.distance(vec2(10.0, 0.0)) > 8.0 && c[0] > 0);
+        assert!(away.is_none());
+    }
+
+    #[test]
+    fn scale_factor_gives_points() {
+        let mut frame = frame();
+        frame.scale_factor = 2.0;
+        let blob = frame.blob(MAGENTA, 40).unwrap();
+        assert_eq!(blob.center, vec2(5.0, 0.0));
+        assert_eq!(blob.area, 16.0);
+    }
+}
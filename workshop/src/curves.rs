@@ -0,0 +1,519 @@
+//! Parametric curves: Lissajous figures, harmonographs, roses, spirographs and superformula
+//! shapes.
+//!
+//! In `p1` the circles go around with `ts.sin()` and `ts.cos()`, which is the simplest
+//! parametric curve there is: a circle.
+//! Every curve here gives a point for a parameter `t`, so it can be used the same way, e.g.
+//! `circle.position = curve.point(app.time * circle.speed)`.
+//! It can also be turned into a closed path with `to_path`, and stroked with `draw.path()`.
+
+use nannou::lyon::path::path::Builder;
+use nannou::lyon::math::point;
+use nannou::lyon::path::Path;
+use nannou::prelude::*;
+
+/// A curve that gives a point for any `t`.
+pub trait ParametricCurve {
+    fn point(&self, t: f32) -> Vec2;
+
+    /// The range of `t` needed to draw the whole curve. After this the curve repeats itself, or
+    /// for curves that never repeat, like the harmonograph, it is how long it is drawn.
+    /// Curves with frequencies that never line up are drawn for `FALLBACK_TURNS` turns.
+    fn period(&self) -> f32 {
+        TAU
+    }
+
+    /// `samples` points spread evenly over the period.
+    fn points(&self, samples: usize) -> Vec<Vec2> {
+        let samples = samples.max(2);
+        let period = self.period();
+        (0..samples)
+            .map(|i| self.point(i as f32 / samples as f32 * period))
+            .collect()
+    }
+
+    /// The whole curve as a closed path with `samples` straight pieces.
+    fn to_path(&self, samples: usize) -> Path {
+        let mut builder = Builder::new();
+        let mut points = self.points(samples).into_iter();
+        if let Some(first) = points.next() {
+            builder.begin(point(first.x, first.y));
+            for p in points {
+                builder.line_to(point(p.x, p.y));
+            }
+            builder.end(self.is_closed());
+        }
+        builder.build()
+    }
+
+    /// Whether the curve ends where it starts.
+    fn is_closed(&self) -> bool {
+        true
+    }
+}
+
+/// A Lissajous figure: `x = sin(a * t + phase)`, `y = sin(b * t)`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Lissajous {
+    /// Frequency along the x axis.
+    pub a: f32,
+    /// Frequency along the y axis.
+    pub b: f32,
+    pub phase: f32,
+    /// Half the width and height of the figure.
+    pub size: Vec2,
+}
+
+impl Lissajous {
+    pub fn new(a: f32, b: f32, phase: f32, size: Vec2) -> Self {
+        Lissajous { a, b, phase, size }
+    }
+}
+
+impl ParametricCurve for Lissajous {
+    fn point(&self, t: f32) -> Vec2 {
+        vec2(
+            (self.a * t + self.phase).sin() * self.size.x,
+            (self.b * t).sin() * self.size.y,
+        )
+    }
+
+    fn period(&self) -> f32 {
+        common_period(self.a, self.b)
+    }
+}
+
+/// One of the pendulums of a harmonograph.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Pendulum {
+    pub amplitude: f32,
+    pub frequency: f32,
+    pub phase: f32,
+    /// How quickly the swing dies out.
+    pub damping: f32,
+}
+
+impl Pendulum {
+    pub fn new(amplitude: f32, frequency: f32, phase: f32, damping: f32) -> Self {
+        Pendulum {
+            amplitude,
+            frequency,
+            phase,
+            damping,
+        }
+    }
+
+    pub fn swing(&self, t: f32) -> f32 {
+        self.amplitude * (self.frequency * t + self.phase).sin() * (-self.damping * t).exp()
+    }
+}
+
+/// A damped harmonograph: the pen is moved by pendulums along x and y that slowly come to rest.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Harmonograph {
+    pub x: Vec<Pendulum>,
+    pub y: Vec<Pendulum>,
+    /// How long to draw, since a harmonograph never repeats itself.
+    pub duration: f32,
+}
+
+impl Harmonograph {
+    pub fn new(x: Vec<Pendulum>, y: Vec<Pendulum>, duration: f32) -> Self {
+        Harmonograph { x, y, duration }
+    }
+}
+
+impl ParametricCurve for Harmonograph {
+    fn point(&self, t: f32) -> Vec2 {
+        vec2(
+            self.x.iter().map(|p| p.swing(t)).sum(),
+            self.y.iter().map(|p| p.swing(t)).sum(),
+        )
+    }
+
+    fn period(&self) -> f32 {
+        self.duration
+    }
+
+    fn is_closed(&self) -> bool {
+        false
+    }
+}
+
+/// A rose curve, with `n / d` petals (or twice as many when `n / d` is even).
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Rose {
+    pub n: u32,
+    pub d: u32,
+    pub radius: f32,
+}
+
+impl Rose {
+    pub fn new(n: u32, d: u32, radius: f32) -> Self {
+        Rose {
+            n,
+            d: d.max(1),
+            radius,
+        }
+    }
+}
+
+impl ParametricCurve for Rose {
+    fn point(&self, t: f32) -> Vec2 {
+        let k = self.n as f32 / self.d as f32;
+        let r = (k * t).cos() * self.radius;
+        vec2(t.cos(), t.sin()) * r
+    }
+
+    fn period(&self) -> f32 {
+        let g = gcd_u32(self.n, self.d);
+        let (n, d) = (self.n / g, self.d / g);
+        if n % 2 == 1 && d % 2 == 1 {
+            PI * d as f32
+        } else {
+            TAU * d as f32
+        }
+    }
+}
+
+/// The curve drawn by a pen in a wheel rolling around a fixed wheel, like a spirograph.
+///
+/// With `inside` the wheel rolls on the inside of the fixed wheel (a hypotrochoid), otherwise
+/// on the outside (an epitrochoid).
+/// When the pen is on the edge of the rolling wheel, i.e. `pen == rolling`, the curves are
+/// called hypocycloids and epicycloids.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Spirograph {
+    /// Radius of the fixed wheel.
+    pub fixed: f32,
+    /// Radius of the rolling wheel.
+    pub rolling: f32,
+    /// Distance from the center of the rolling wheel to the pen.
+    pub pen: f32,
+    pub inside: bool,
+}
+
+impl Spirograph {
+    pub fn hypotrochoid(fixed: f32, rolling: f32, pen: f32) -> Self {
+        Spirograph {
+            fixed,
+            rolling,
+            pen,
+            inside: true,
+        }
+    }
+
+    pub fn epitrochoid(fixed: f32, rolling: f32, pen: f32) -> Self {
+        Spirograph {
+            fixed,
+            rolling,
+            pen,
+            inside: false,
+        }
+    }
+
+    pub fn epicycloid(fixed: f32, rolling: f32) -> Self {
+        Spirograph::epitrochoid(fixed, rolling, rolling)
+    }
+
+    pub fn hypocycloid(fixed: f32, rolling: f32) -> Self {
+        Spirograph::hypotrochoid(fixed, rolling, rolling)
+    }
+}
+
+impl ParametricCurve for Spirograph {
+    fn point(&self, t: f32) -> Vec2 {
+        let (big, small, pen) = (self.fixed, self.rolling, self.pen);
+        if self.inside {
+            let k = (big - small) / small;
+            vec2(
+                (big - small) * t.cos() + pen * (k * t).cos(),
+                (big - small) * t.sin() - pen * (k * t).sin(),
+            )
+        } else {
+            let k = (big + small) / small;
+            vec2(
+                (big + small) * t.cos() - pen * (k * t).cos(),
+                (big + small) * t.sin() - pen * (k * t).sin(),
+            )
+        }
+    }
+
+    //The pen is back where it started when both the center of the rolling wheel (angle `t`) and
+    // the pen around it (angle `k * t`) have made whole turns
+    fn period(&self) -> f32 {
+        let k = if self.inside {
+            (self.fixed - self.rolling) / self.rolling
+        } else {
+            (self.fixed + self.rolling) / self.rolling
+        };
+        common_period(1.0, k)
+    }
+}
+
+/// Gielis' superformula, which can make stars, flowers, and a lot of other shapes.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Superformula {
+    /// The number of symmetries.
+    pub m: f32,
+    pub n1: f32,
+    pub n2: f32,
+    pub n3: f32,
+    pub a: f32,
+    pub b: f32,
+    pub radius: f32,
+}
+
+impl Superformula {
+    pub fn new(m: f32, n1: f32, n2: f32, n3: f32, radius: f32) -> Self {
+        Superformula {
+            m,
+            n1,
+            n2,
+            n3,
+            a: 1.0,
+            b: 1.0,
+            radius,
+        }
+    }
+}
+
+impl ParametricCurve for Superformula {
+    fn point(&self, t: f32) -> Vec2 {
+        let angle = self.m * t / 4.0;
+        let c = (angle.cos() / self.a).abs().powf(self.n2);
+        let s = (angle.sin() / self.b).abs().powf(self.n3);
+        let sum = c + s;
+        let r = if sum > 0.0 {
+            sum.powf(-1.0 / self.n1)
+        } else {
+            0.0
+        };
+        vec2(t.cos(), t.sin()) * r * self.radius
+    }
+
+    //The radius repeats every `4 * PI / m`, i.e. like an angle going `m / 2` times as fast as
+    // `t`. So with an even number of symmetries the shape closes after one turn, and with an odd
+    // number after two turns.
+    fn period(&self) -> f32 {
+        common_period(1.0, self.m / 2.0)
+    }
+}
+
+/// Ratios of frequencies with a larger denominator than this are treated as if they never repeat.
+pub const MAX_DENOMINATOR: u32 = 64;
+
+/// How many turns of the slowest frequency are drawn when the frequencies never line up, e.g.
+/// a Lissajous figure with `a = 2.0.sqrt()`.
+pub const FALLBACK_TURNS: f32 = 8.0;
+
+//The smallest `t` where both `a * t` and `b * t` are whole turns.
+// When `a / b = p / q` in lowest terms, that is after `q` turns of `b`.
+fn common_period(a: f32, b: f32) -> f32 {
+    let (a, b) = (a.abs(), b.abs());
+    let (slowest, fastest) = (a.min(b), a.max(b));
+    if fastest < 1e-6 {
+        return TAU;
+    }
+    if slowest < 1e-6 {
+        //A frequency of zero doesn't move, so only the other one counts
+        return TAU / fastest;
+    }
+    match denominator(a / b) {
+        Some(q) => TAU * q as f32 / b,
+        None => TAU * FALLBACK_TURNS / slowest,
+    }
+}
+
+//The denominator `q` of the simplest fraction `p / q` that is close to `x`, with `q` at most
+// `MAX_DENOMINATOR`, found with continued fractions. `None` when no such fraction is close enough.
+fn denominator(x: f32) -> Option<u32> {
+    let x = x as f64;
+    let tolerance = 1e-4 * x.max(1.0);
+    //The last two convergents, h / k
+    let (mut h0, mut h1) = (0u64, 1u64);
+    let (mut k0, mut k1) = (1u64, 0u64);
+    let mut rest = x;
+    for _ in 0..32 {
+        let whole = rest.floor() as u64;
+        let h = whole.saturating_mul(h1).saturating_add(h0);
+        let k = whole.saturating_mul(k1).saturating_add(k0);
+        if k > MAX_DENOMINATOR as u64 {
+            return None;
+        }
+        if (h as f64 / k as f64 - x).abs() <= tolerance {
+            return Some(k as u32);
+        }
+        (h0, h1, k0, k1) = (h1, h, k1, k);
+        let fraction = rest - rest.floor();
+        if fraction < 1e-12 {
+            break;
+        }
+        rest = 1.0 / fraction;
+    }
+    None
+}
+
+fn gcd_u32(a: u32, b: u32) -> u32 {
+    if b == 0 {
+        a.max(1)
+    } else {
+        gcd_u32(b, a % b)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: Vec2, b: Vec2, tolerance: f32) {
+        assert!(a.distance(b) < tolerance, "{:?} is not {:?}", a, b);
+    }
+
+    fn assert_period(curve: &impl ParametricCurve, expected: f32) {
+        let period = curve.period();
+        assert!((period - expected).abs() < 1e-3, "period {} is not {}", period, expected);
+    }
+
+    //The curve is back where it started, going the same way, after one period
+    fn assert_repeats(curve: &impl ParametricCurve, size: f32) {
+        let period = curve.period();
+        for i in 0..10 {
+            let t = i as f32 * 0.37;
+            assert_close(curve.point(t + period), curve.point(t), size * 1e-3);
+        }
+        let step = 1e-2;
+        let start = curve.point(step) - curve.point(0.0);
+        let end = curve.point(period + step) - curve.point(period);
+        assert_close(end, start, size * 1e-3);
+    }
+
+    //The path from `to_path` ends where it starts
+    fn assert_closed_path(curve: &impl ParametricCurve, size: f32) {
+        let points = curve.points(2000);
+        let gap = points[0].distance(points[points.len() - 1]);
+        assert!(gap < size * 0.02, "the path has a gap of {}", gap);
+        assert!(curve.is_closed());
+    }
+
+    #[test]
+    fn lissajous_period() {
+        let size = vec2(200.0, 150.0);
+        let whole = Lissajous::new(3.0, 2.0, 0.3, size);
+        assert_period(&whole, TAU);
+        assert_repeats(&whole, 200.0);
+        assert_closed_path(&whole, 200.0);
+
+        //A common factor makes the figure repeat sooner
+        assert_period(&Lissajous::new(2.0, 4.0, 0.3, size), PI);
+
+        //With a = 1.5, the x axis needs two turns to line up with y
+        let half = Lissajous::new(1.5, 1.0, 0.3, size);
+        assert_period(&half, 2.0 * TAU);
+        assert_repeats(&half, 200.0);
+        assert_closed_path(&half, 200.0);
+        assert!(half.point(TAU).distance(half.point(0.0)) > 10.0);
+
+        let thirds = Lissajous::new(2.0 / 3.0, 0.5, 0.0, size);
+        assert_period(&thirds, 6.0 * TAU);
+        assert_repeats(&thirds, 200.0);
+    }
+
+    #[test]
+    fn lissajous_that_never_repeats() {
+        let curve = Lissajous::new(2.0.sqrt(), 1.0, 0.0, vec2(100.0, 100.0));
+        assert_period(&curve, FALLBACK_TURNS * TAU);
+        //One frequency of zero is a line, which only the other frequency moves along
+        assert_period(&Lissajous::new(0.0, 2.0, 0.0, vec2(1.0, 1.0)), PI);
+        assert_period(&Lissajous::new(0.0, 0.0, 0.0, vec2(1.0, 1.0)), TAU);
+    }
+
+    #[test]
+    fn harmonograph_dies_out() {
+        let x = vec![Pendulum::new(100.0, 2.0, 0.0, 0.02), Pendulum::new(50.0, 6.0, 1.0, 0.05)];
+        let y = vec![Pendulum::new(100.0, 3.0, PI / 2.0, 0.02)];
+        let curve = Harmonograph::new(x, y, 120.0);
+        assert_period(&curve, 120.0);
+        assert!(!curve.is_closed());
+
+        //Each pendulum swings within its damped amplitude
+        let pendulum = Pendulum::new(100.0, 2.0, 0.0, 0.02);
+        assert!((pendulum.swing(PI / 4.0) - 100.0 * (-0.02 * PI / 4.0).exp()).abs() < 1e-3);
+        for i in 0..1000 {
+            let t = i as f32 * 0.1;
+            assert!(pendulum.swing(t).abs() <= 100.0 * (-0.02 * t).exp() + 1e-3);
+        }
+
+        //So the pen comes to rest
+        let reach = |from: f32| {
+            (0..500)
+                .map(|i| curve.point(from + i as f32 * 0.02).length())
+                .fold(0.0, f32::max)
+        };
+        assert!(reach(0.0) > 100.0);
+        assert!(reach(100.0) < reach(0.0) * (-0.02 * 90.0).exp());
+    }
+
+    #[test]
+    fn rose_period() {
+        //An odd number of petals is drawn in half a turn, an even number in a whole turn
+        let three = Rose::new(3, 1, 100.0);
+        assert_period(&three, PI);
+        assert_repeats(&three, 100.0);
+        assert_closed_path(&three, 100.0);
+        assert_period(&Rose::new(2, 1, 100.0), TAU);
+
+        let halves = Rose::new(1, 2, 100.0);
+        assert_period(&halves, 2.0 * TAU);
+        assert_repeats(&halves, 100.0);
+        assert_closed_path(&halves, 100.0);
+
+        //A common factor doesn't change the curve
+        assert_period(&Rose::new(6, 4, 100.0), Rose::new(3, 2, 100.0).period());
+        //The tip of the first petal
+        assert_close(three.point(0.0), vec2(100.0, 0.0), 1e-3);
+    }
+
+    #[test]
+    fn spirograph_period() {
+        //An astroid: four cusps in one turn
+        let astroid = Spirograph::hypocycloid(4.0, 1.0);
+        assert_period(&astroid, TAU);
+        assert_close(astroid.point(0.0), vec2(4.0, 0.0), 1e-4);
+        assert_close(astroid.point(PI / 2.0), vec2(0.0, 4.0), 1e-4);
+
+        let whole = Spirograph::epitrochoid(5.0, 3.0, 1.0);
+        assert_period(&whole, 3.0 * TAU);
+        assert_repeats(&whole, 10.0);
+        assert_closed_path(&whole, 10.0);
+
+        //A rolling wheel of 0.3 goes around 3 times before it lines up with the fixed wheel of 1
+        let fraction = Spirograph::hypotrochoid(1.0, 0.3, 0.5);
+        assert_period(&fraction, 3.0 * TAU);
+        assert_repeats(&fraction, 1.0);
+        assert_closed_path(&fraction, 1.0);
+        assert!(fraction.point(TAU).distance(fraction.point(0.0)) > 0.1);
+    }
+
+    #[test]
+    fn superformula_period() {
+        //With n1 = n2 = n3 = 2 and m = 4 it is a circle
+        let circle = Superformula::new(4.0, 2.0, 2.0, 2.0, 50.0);
+        for i in 0..20 {
+            assert!((circle.point(i as f32 * 0.3).length() - 50.0).abs() < 1e-3);
+        }
+
+        let even = Superformula::new(6.0, 1.0, 1.0, 1.0, 50.0);
+        assert_period(&even, TAU);
+        assert_repeats(&even, 50.0);
+        let odd = Superformula::new(5.0, 1.0, 1.0, 1.0, 50.0);
+        assert_period(&odd, 2.0 * TAU);
+        assert_repeats(&odd, 50.0);
+        assert_closed_path(&odd, 50.0);
+
+        let fraction = Superformula::new(2.5, 1.0, 1.0, 1.0, 50.0);
+        assert_period(&fraction, 4.0 * TAU);
+        assert_repeats(&fraction, 50.0);
+        assert_closed_path(&fraction, 50.0);
+    }
+}
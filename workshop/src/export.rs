@@ -0,0 +1,311 @@
+//! High resolution renders for print, made from tiles.
+//!
+//! A sketch drawn in design coordinates (see `layout::DesignSpace`) can be rendered at any size.
+//! Very large images, like `--export-size 7016x9921` for A2 at 300 dpi, are bigger than the
+//! largest texture the GPU can render to, so the image is rendered as a grid of tiles that are
+//! stitched together into one image.
+
+use std::io;
+use std::path::Path;
+use std::sync::mpsc;
+
+use nannou::image::{GenericImage, RgbaImage};
+use nannou::prelude::*;
+use nannou::wgpu;
+
+use crate::layout::DesignSpace;
+
+/// The width and height of each tile in pixels. Most GPUs handle textures up to 8192x8192, but
+/// smaller tiles use less memory.
+pub const DEFAULT_TILE_SIZE: u32 = 2048;
+
+/// The key that starts an export in the sketches.
+pub const EXPORT_KEY: Key = Key::E;
+
+/// One tile of the output image.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Tile {
+    /// The pixel position of the top left corner of the tile in the output image.
+    pub x: u32,
+    pub y: u32,
+    pub w: u32,
+    pub h: u32,
+    /// The part of the design that the tile shows.
+    pub design_rect: Rect,
+}
+
+/// Renders a design at a fixed output size, one tile at a time.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct TiledExport {
+    pub design: DesignSpace,
+    pub width: u32,
+    pub height: u32,
+    pub tile_size: u32,
+}
+
+impl TiledExport {
+    pub fn new(design: DesignSpace, width: u32, height: u32) -> Self {
+        TiledExport {
+            design,
+            width: width.max(1),
+            height: height.max(1),
+            tile_size: DEFAULT_TILE_SIZE,
+        }
+    }
+
+    /// An export with the size given by `--export-size <width>x<height>`, if there is one.
+    pub fn from_args(design: DesignSpace) -> Option<Self> {
+        let (width, height) = export_size_from_args(std::env::args())?;
+        Some(TiledExport::new(design, width, height))
+    }
+
+    pub fn tile_size(mut self, tile_size: u32) -> Self {
+        self.tile_size = tile_size.max(1);
+        self
+    }
+
+    /// The output image as a rect centered on the origin, in pixels.
+    pub fn output_rect(&self) -> Rect {
+        Rect::from_w_h(self.width as f32, self.height as f32)
+    }
+
+    /// How many pixels one design unit becomes.
+    pub fn scale(&self) -> Vec2 {
+        self.design.scale(self.output_rect())
+    }
+
+    /// All the tiles, row by row from the top left.
+    pub fn tiles(&self) -> Vec<Tile> {
+        let output = self.output_rect();
+        let scale = self.scale();
+        let mut tiles = Vec::new();
+        for y in (0..self.height).step_by(self.tile_size as usize) {
+            for x in (0..self.width).step_by(self.tile_size as usize) {
+                let w = self.tile_size.min(self.width - x);
+                let h = self.tile_size.min(self.height - y);
+                //Image pixels go down from the top left, while nannou goes up from the center
+                let center = vec2(
+                    output.left() + x as f32 + w as f32 * 0.5,
+                    output.top() - y as f32 - h as f32 * 0.5,
+                );
+                let design_center = center / scale;
+                let design_wh = vec2(w as f32, h as f32) / scale;
+                tiles.push(Tile {
+                    x,
+                    y,
+                    w,
+                    h,
+                    design_rect: Rect::from_x_y_w_h(
+                        design_center.x,
+                        design_center.y,
+                        design_wh.x,
+                        design_wh.y,
+                    ),
+                });
+            }
+        }
+        tiles
+    }
+
+    /// Render every tile with `view`, stitch them together and save the image to `path`.
+    ///
+    /// `view` gets a draw context in design coordinates, and should draw the sketch just like
+    /// the `view` function does, except for `draw.to_frame()`.
+    /// This blocks until all the tiles are rendered, which can take a few seconds.
+    pub fn render<F>(&self, window: &Window, path: impl AsRef<Path>, view: F) -> io::Result<()>
+    where
+        F: Fn(&Draw),
+    {
+        let device = window.device();
+        let mut image = RgbaImage::new(self.width, self.height);
+        let scale = self.scale();
+        let capturer = wgpu::TextureCapturer::default();
+
+        for tile in self.tiles() {
+            let texture = wgpu::TextureBuilder::new()
+                .size([tile.w, tile.h])
+                .usage(wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING)
+                .sample_count(window.msaa_samples())
+                .format(wgpu::TextureFormat::Rgba16Float)
+                .build(device);
+            let mut renderer = nannou::draw::RendererBuilder::new()
+                .build_from_texture_descriptor(device, texture.descriptor());
+
+            //Move the part of the design this tile shows to the middle of the texture
+            let draw = Draw::new();
+            let tile_draw = draw
+                .scale_axes(scale.extend(1.0))
+                .xy(-tile.design_rect.xy());
+            view(&tile_draw);
+
+            let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("tiled export"),
+            });
+            renderer.render_to_texture(device, &mut encoder, &draw, &texture);
+            let snapshot = capturer.capture(device, &mut encoder, &texture);
+            window.queue().submit(Some(encoder.finish()));
+
+            //Wait for the GPU to finish the tile before copying it into the image. A single poll
+            // can return before the buffer is mapped, so keep polling until the read is done.
+            let (sender, receiver) = mpsc::channel();
+            snapshot
+                .read(move |result| {
+                    let _ = sender.send(result.map(|buffer| buffer.to_owned()));
+                })
+                .map_err(|e| io::Error::other(format!("{:?}", e)))?;
+            capturer
+                .await_active_snapshots(device)
+                .map_err(|e| io::Error::other(format!("{:?}", e)))?;
+            let pixels = receiver
+                .recv()
+                .map_err(io::Error::other)?
+                .map_err(|e| io::Error::other(format!("{:?}", e)))?;
+            image
+                .copy_from(&pixels, tile.x, tile.y)
+                .map_err(io::Error::other)?;
+            println!(
+                "Rendered tile at {},{} ({}x{})",
+                tile.x, tile.y, tile.w, tile.h
+            );
+        }
+
+        if let Some(dir) = path.as_ref().parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        image
+            .save(path.as_ref())
+            .map_err(io::Error::other)?;
+        println!("Exported {}", path.as_ref().display());
+        Ok(())
+    }
+}
+
+/// Parse a size like `7016x9921`.
+pub fn parse_size(s: &str) -> Option<(u32, u32)> {
+    let (w, h) = s.split_once(['x', 'X'])?;
+    let (w, h) = (w.trim().parse().ok()?, h.trim().parse().ok()?);
+    if w == 0 || h == 0 {
+        return None;
+    }
+    Some((w, h))
+}
+
+fn export_size_from_args(args: impl Iterator<Item = String>) -> Option<(u32, u32)> {
+    let args: Vec<String> = args.collect();
+    args.iter()
+        .position(|arg| arg == "--export-size")
+        .and_then(|i| args.get(i + 1))
+        .and_then(|s| parse_size(s))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: Vec2, b: Vec2) {
+        assert!(a.distance(b) < 1e-3, "{:?} is not {:?}", a, b);
+    }
+
+    //Twice the size of the design, in tiles that don't divide it evenly
+    fn export() -> TiledExport {
+        TiledExport::new(DesignSpace::new(1024.0, 720.0), 2048, 1440).tile_size(1000)
+    }
+
+    #[test]
+    fn tiles_cover_the_image() {
+        let tiles = export().tiles();
+        let corners: Vec<(u32, u32, u32, u32)> =
+            tiles.iter().map(|t| (t.x, t.y, t.w, t.h)).collect();
+        assert_eq!(
+            corners,
+            vec![
+                (0, 0, 1000, 1000),
+                (1000, 0, 1000, 1000),
+                (2000, 0, 48, 1000),
+                (0, 1000, 1000, 440),
+                (1000, 1000, 1000, 440),
+                (2000, 1000, 48, 440),
+            ]
+        );
+
+        //Every pixel is in exactly one tile
+        let mut covered = vec![0u8; 2048 * 1440];
+        for t in &tiles {
+            for y in t.y..t.y + t.h {
+                for x in t.x..t.x + t.w {
+                    covered[(y * 2048 + x) as usize] += 1;
+                }
+            }
+        }
+        assert!(covered.iter().all(|&n| n == 1));
+    }
+
+    #[test]
+    fn one_tile_when_it_fits() {
+        let export = TiledExport::new(DesignSpace::new(1024.0, 720.0), 1024, 720);
+        let tiles = export.tiles();
+        assert_eq!(tiles.len(), 1);
+        assert_eq!(tiles[0].design_rect, Rect::from_w_h(1024.0, 720.0));
+    }
+
+    #[test]
+    fn tiles_show_their_part_of_the_design() {
+        let export = export();
+        assert_eq!(export.scale(), Vec2::splat(2.0));
+        let tiles = export.tiles();
+
+        //The top left tile shows the top left of the design
+        let first = tiles[0].design_rect;
+        assert_close(first.top_left(), vec2(-512.0, 360.0));
+        assert_close(first.wh(), vec2(500.0, 500.0));
+
+        //The bottom right tile is the partial one in the corner
+        let last = tiles[5].design_rect;
+        assert_close(last.xy(), vec2(500.0, -250.0));
+        assert_close(last.wh(), vec2(24.0, 220.0));
+        assert_close(last.bottom_right(), vec2(512.0, -360.0));
+
+        //Neighbouring tiles meet without a gap
+        assert!((tiles[1].design_rect.left() - first.right()).abs() < 1e-3);
+        assert!((tiles[3].design_rect.top() - first.bottom()).abs() < 1e-3);
+    }
+
+    #[test]
+    fn design_is_centered_in_a_different_shape() {
+        //A4 portrait from a landscape design: the design is contained, with room above and below
+        let export = TiledExport::new(DesignSpace::new(1024.0, 720.0), 2480, 3508).tile_size(4096);
+        let scale = 2480.0 / 1024.0;
+        assert_close(export.scale(), Vec2::splat(scale));
+        let rect = export.tiles()[0].design_rect;
+        assert_close(rect.xy(), Vec2::ZERO);
+        assert_close(rect.wh(), vec2(1024.0, 3508.0 / scale));
+    }
+
+    #[test]
+    fn sizes() {
+        assert_eq!(parse_size("7016x9921"), Some((7016, 9921)));
+        assert_eq!(parse_size("640X480"), Some((640, 480)));
+        assert_eq!(parse_size(" 640 x 480 "), Some((640, 480)));
+        assert_eq!(parse_size("0x480"), None);
+        assert_eq!(parse_size("640"), None);
+        assert_eq!(parse_size("640x"), None);
+        assert_eq!(parse_size("-640x480"), None);
+        assert_eq!(parse_size("axb"), None);
+    }
+
+    #[test]
+    fn sizes_from_args() {
+        let args = |a: &[&str]| a.iter().map(|s| s.to_string()).collect::<Vec<_>>().into_iter();
+        assert_eq!(
+            export_size_from_args(args(&["sketch", "--export-size", "800x600"])),
+            Some((800, 600))
+        );
+        assert_eq!(
+            export_size_from_args(args(&["sketch", "--seed", "3", "--export-size", "1x2"])),
+            Some((1, 2))
+        );
+        assert_eq!(export_size_from_args(args(&["sketch"])), None);
+        assert_eq!(export_size_from_args(args(&["sketch", "--export-size"])), None);
+        assert_eq!(export_size_from_args(args(&["sketch", "--export-size", "big"])), None);
+    }
+}
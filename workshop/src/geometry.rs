@@ -0,0 +1,263 @@
+//! Paths tessellated once and drawn many times.
+//!
+//! `draw.path()` turns the path into triangles every time it is drawn, so the kaleidoscope in
+//! `p2_d` tessellates the same bezier 24 times every frame.
+//! A `Mesh` holds the triangles of a path, and can be drawn again and again with different
+//! transforms for the cost of copying the vertices.
+//! A `GeometryCache` keeps a mesh until the parameters of the path change.
+
+use nannou::color::IntoLinSrgba;
+use nannou::lyon::math::Point;
+use nannou::lyon::path::Path;
+use nannou::lyon::tessellation::{
+    BuffersBuilder, FillOptions, FillTessellator, FillVertex, StrokeOptions, StrokeTessellator,
+    StrokeVertex, VertexBuffers,
+};
+use nannou::prelude::*;
+
+/// The triangles of a path.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Mesh {
+    pub vertices: Vec<Vec2>,
+    /// Three indices into `vertices` for each triangle.
+    pub indices: Vec<u32>,
+}
+
+impl Mesh {
+    /// The triangles of a stroke along the path, `weight` pixels wide.
+    pub fn stroke(path: &Path, weight: f32) -> Self {
+        let mut buffers: VertexBuffers<Point, u32> = VertexBuffers::new();
+        let options = StrokeOptions::default().with_line_width(weight);
+        let result = StrokeTessellator::new().tessellate_path(
+            path,
+            &options,
+            &mut BuffersBuilder::new(&mut buffers, |v: StrokeVertex| v.position()),
+        );
+        if let Err(e) = result {
+            eprintln!("Could not tessellate the stroke: {:?}", e);
+        }
+        Mesh::from_buffers(buffers)
+    }
+
+    /// The triangles filling the path.
+    pub fn fill(path: &Path) -> Self {
+        let mut buffers: VertexBuffers<Point, u32> = VertexBuffers::new();
+        let result = FillTessellator::new().tessellate_path(
+            path,
+            &FillOptions::default(),
+            &mut BuffersBuilder::new(&mut buffers, |v: FillVertex| v.position()),
+        );
+        if let Err(e) = result {
+            eprintln!("Could not tessellate the fill: {:?}", e);
+        }
+        Mesh::from_buffers(buffers)
+    }
+
+    fn from_buffers(buffers: VertexBuffers<Point, u32>) -> Self {
+        Mesh {
+            vertices: buffers.vertices.iter().map(|p| vec2(p.x, p.y)).collect(),
+            indices: buffers.indices,
+        }
+    }
+
+    pub fn triangle_count(&self) -> usize {
+        self.indices.len() / 3
+    }
+
+    /// The vertices moved by `transform`, e.g. to combine many instances into one mesh.
+    pub fn transformed(&self, transform: Mat4) -> Vec<Vec2> {
+        self.vertices
+            .iter()
+            .map(|p| transform.transform_point3(p.extend(0.0)).truncate())
+            .collect()
+    }
+
+    /// Draw the mesh with the transform of `draw`.
+    pub fn draw(&self, draw: &Draw, color: impl IntoLinSrgba<f32>) {
+        let color = color.into_lin_srgba();
+        draw.mesh().indexed_colored(
+            self.vertices.iter().map(|p| (p.extend(0.0), color)),
+            self.indices.iter().map(|&i| i as usize),
+        );
+    }
+
+    /// Draw the mesh once for every transform.
+    pub fn draw_instances<I>(&self, draw: &Draw, transforms: I, color: impl IntoLinSrgba<f32>)
+    where
+        I: IntoIterator<Item = Mat4>,
+    {
+        let color = color.into_lin_srgba();
+        for transform in transforms {
+            self.draw(&draw.transform(transform), color);
+        }
+    }
+
+    /// Put all the instances into one mesh, which is drawn with a single `draw.mesh()`.
+    /// This is faster than `draw_instances` when there are many small instances.
+    pub fn merge_instances<I>(&self, transforms: I) -> Mesh
+    where
+        I: IntoIterator<Item = Mat4>,
+    {
+        let mut merged = Mesh::default();
+        for transform in transforms {
+            let offset = merged.vertices.len() as u32;
+            merged.vertices.extend(self.transformed(transform));
+            merged.indices.extend(self.indices.iter().map(|i| i + offset));
+        }
+        merged
+    }
+}
+
+/// Keeps the mesh of a path until the key, i.e. the parameters the path is made from, changes.
+#[derive(Clone, Debug)]
+pub struct GeometryCache<K> {
+    entry: Option<(K, Mesh)>,
+    hits: u64,
+    misses: u64,
+}
+
+impl<K> Default for GeometryCache<K> {
+    fn default() -> Self {
+        GeometryCache::new()
+    }
+}
+
+impl<K> GeometryCache<K> {
+    pub fn new() -> Self {
+        GeometryCache {
+            entry: None,
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    /// The kept mesh, without building it, e.g. in `view` after `get` was called in `update`.
+    pub fn mesh(&self) -> Option<&Mesh> {
+        self.entry.as_ref().map(|(_, mesh)| mesh)
+    }
+
+    /// Forget the mesh, so the next `get` builds it again.
+    pub fn invalidate(&mut self) {
+        self.entry = None;
+    }
+
+    /// How many times `get` could use the kept mesh.
+    pub fn hits(&self) -> u64 {
+        self.hits
+    }
+
+    /// How many times `get` had to build the mesh.
+    pub fn misses(&self) -> u64 {
+        self.misses
+    }
+}
+
+impl<K: PartialEq> GeometryCache<K> {
+    /// The mesh for `key`. `build` is only called when `key` is different from the last time.
+    pub fn get<F>(&mut self, key: K, build: F) -> &Mesh
+    where
+        F: FnOnce() -> Mesh,
+    {
+        match self.entry.as_ref() {
+            Some((cached, _)) if *cached == key => self.hits += 1,
+            _ => {
+                self.misses += 1;
+                self.entry = Some((key, build()));
+            }
+        }
+        &self.entry.as_ref().expect("the entry was just set").1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::record::{self, record};
+
+    fn square() -> Path {
+        let mut builder = Path::builder();
+        builder.begin(nannou::lyon::math::point(0.0, 0.0));
+        builder.line_to(nannou::lyon::math::point(10.0, 0.0));
+        builder.line_to(nannou::lyon::math::point(10.0, 10.0));
+        builder.line_to(nannou::lyon::math::point(0.0, 10.0));
+        builder.end(true);
+        builder.build()
+    }
+
+    #[test]
+    fn fill_and_stroke() {
+        let fill = Mesh::fill(&square());
+        assert_eq!(fill.vertices.len(), 4);
+        assert_eq!(fill.triangle_count(), 2);
+        //The stroke goes around both sides of the outline
+        let stroke = Mesh::stroke(&square(), 2.0);
+        assert_eq!(stroke.indices.len() % 3, 0);
+        assert!(stroke.triangle_count() >= 8);
+        for v in &stroke.vertices {
+            assert!(v.x >= -1.0 - 1e-4 && v.x <= 11.0 + 1e-4, "{:?}", v);
+        }
+    }
+
+    #[test]
+    fn cache_hits_and_misses() {
+        let mut cache = GeometryCache::new();
+        let builds = std::cell::Cell::new(0);
+        let get = |cache: &mut GeometryCache<u32>, key: u32| {
+            cache
+                .get(key, || {
+                    builds.set(builds.get() + 1);
+                    Mesh::fill(&square())
+                })
+                .clone()
+        };
+        assert!(cache.mesh().is_none());
+        get(&mut cache, 1);
+        get(&mut cache, 1);
+        get(&mut cache, 1);
+        assert_eq!((cache.misses(), cache.hits()), (1, 2));
+        //Another key builds it again, and the old key is forgotten
+        get(&mut cache, 2);
+        get(&mut cache, 1);
+        assert_eq!((cache.misses(), cache.hits()), (3, 2));
+        assert_eq!(cache.mesh().unwrap().vertices.len(), 4);
+
+        cache.invalidate();
+        assert!(cache.mesh().is_none());
+        get(&mut cache, 1);
+        assert_eq!((cache.misses(), cache.hits()), (4, 2));
+        assert_eq!(builds.get(), 4);
+    }
+
+    #[test]
+    fn merged_instances_offset_the_indices() {
+        let mesh = Mesh::fill(&square());
+        let transforms = [
+            Mat4::IDENTITY,
+            Mat4::from_translation(vec3(100.0, 0.0, 0.0)),
+            Mat4::from_rotation_z(PI),
+        ];
+        let merged = mesh.merge_instances(transforms);
+        let n = mesh.vertices.len();
+        assert_eq!(merged.vertices.len(), n * 3);
+        assert_eq!(merged.indices.len(), mesh.indices.len() * 3);
+        for (instance, transform) in transforms.iter().enumerate() {
+            let indices = &merged.indices[instance * mesh.indices.len()..][..mesh.indices.len()];
+            for (&merged_i, &i) in indices.iter().zip(mesh.indices.iter()) {
+                assert_eq!(merged_i, i + (instance * n) as u32);
+                //The index points at the transformed copy of the same vertex
+                let expected = transform.transform_point3(mesh.vertices[i as usize].extend(0.0));
+                let v = merged.vertices[merged_i as usize];
+                assert!(v.distance(expected.truncate()) < 1e-4);
+            }
+        }
+    }
+
+    #[test]
+    fn instances_are_drawn_as_meshes() {
+        let mesh = Mesh::fill(&square());
+        let transforms = (0..24).map(|i| Mat4::from_rotation_z(i as f32 * 0.1));
+        let recording = record(|draw| mesh.draw_instances(draw, transforms, WHITE));
+        recording.assert_count::<record::Mesh>(24);
+        assert_eq!(recording.rotations::<record::Mesh>().len(), 24);
+    }
+}
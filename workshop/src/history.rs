@@ -0,0 +1,380 @@
+//! Undo and redo for sketches that can be edited with the mouse.
+//!
+//! Every change to the model is made by a _command_ that knows how to do the change and how to
+//! undo it.
+//! The `History` keeps the commands that have been done, so they can be undone with `Ctrl+Z`
+//! and redone with `Ctrl+Shift+Z` or `Ctrl+Y`.
+//!
+//! A drag moves a circle a little bit every frame, but should be undone in one go.
+//! Call `begin_group` when the mouse is pressed and `end_group` when it is released, and all
+//! the commands in between become one entry in the history.
+//! Outside of a group, commands are only merged when they follow each other quickly, e.g. the
+//! ticks of one turn of the scroll wheel.
+
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use nannou::prelude::*;
+
+/// How many entries are kept by default. The oldest entries are forgotten after this.
+pub const DEFAULT_CAPACITY: usize = 200;
+
+/// How soon a command has to follow the one before to be merged with it outside of a group.
+pub const DEFAULT_MERGE_WINDOW: Duration = Duration::from_millis(500);
+
+/// A reversible change to a model of type `M`.
+pub trait Command<M> {
+    /// Make the change.
+    fn apply(&mut self, model: &mut M);
+
+    /// Put the model back the way it was before `apply`.
+    fn undo(&mut self, model: &mut M);
+
+    /// Try to combine `next` into this command, e.g. two resizes of the same circle become one
+    /// resize. Return `true` if `next` was combined, and it will not be kept on its own.
+    fn merge(&mut self, _next: &Self) -> bool {
+        false
+    }
+}
+
+/// The undo and redo stacks.
+#[derive(Clone, Debug)]
+pub struct History<C> {
+    undo: VecDeque<Vec<C>>,
+    redo: Vec<Vec<C>>,
+    group: Option<Vec<C>>,
+    capacity: usize,
+    merge_window: Duration,
+    //When the last command was executed
+    last_execute: Option<Instant>,
+}
+
+impl<C> Default for History<C> {
+    fn default() -> Self {
+        History::new()
+    }
+}
+
+impl<C> History<C> {
+    pub fn new() -> Self {
+        History {
+            undo: VecDeque::new(),
+            redo: Vec::new(),
+            group: None,
+            capacity: DEFAULT_CAPACITY,
+            merge_window: DEFAULT_MERGE_WINDOW,
+            last_execute: None,
+        }
+    }
+
+    pub fn capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity.max(1);
+        self
+    }
+
+    /// Commands outside of a group that follow each other within `window` are merged.
+    pub fn merge_window(mut self, window: Duration) -> Self {
+        self.merge_window = window;
+        self
+    }
+
+    /// Apply `command` to the model and remember it. Anything that was undone can no longer be
+    /// redone.
+    pub fn execute<M>(&mut self, model: &mut M, command: C)
+    where
+        C: Command<M>,
+    {
+        self.execute_at(model, command, Instant::now());
+    }
+
+    /// `execute`, as if it happened at `now`.
+    pub fn execute_at<M>(&mut self, model: &mut M, mut command: C, now: Instant)
+    where
+        C: Command<M>,
+    {
+        command.apply(model);
+        self.redo.clear();
+        let recent = self
+            .last_execute
+            .is_some_and(|last| now.saturating_duration_since(last) <= self.merge_window);
+        self.last_execute = Some(now);
+
+        //Inside a group we only merge with the group, so that two drags stay two entries.
+        // Outside of a group we only merge with a command that came just before.
+        let last = match self.group.as_mut() {
+            Some(group) => group.last_mut(),
+            None if recent => self.undo.back_mut().and_then(|entry| entry.last_mut()),
+            None => None,
+        };
+        if let Some(last) = last {
+            if last.merge(&command) {
+                return;
+            }
+        }
+        match self.group.as_mut() {
+            Some(group) => group.push(command),
+            None => self.push_entry(vec![command]),
+        }
+    }
+
+    /// Start collecting commands into one entry, e.g. when a drag starts.
+    pub fn begin_group(&mut self) {
+        self.end_group();
+        self.group = Some(Vec::new());
+    }
+
+    /// Stop collecting commands into one entry.
+    pub fn end_group(&mut self) {
+        if let Some(group) = self.group.take() {
+            if !group.is_empty() {
+                self.push_entry(group);
+            }
+        }
+    }
+
+    /// Undo the last entry. Returns `false` if there was nothing to undo.
+    pub fn undo<M>(&mut self, model: &mut M) -> bool
+    where
+        C: Command<M>,
+    {
+        self.end_group();
+        self.last_execute = None;
+        match self.undo.pop_back() {
+            Some(mut entry) => {
+                for command in entry.iter_mut().rev() {
+                    command.undo(model);
+                }
+                self.redo.push(entry);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Redo the last undone entry. Returns `false` if there was nothing to redo.
+    pub fn redo<M>(&mut self, model: &mut M) -> bool
+    where
+        C: Command<M>,
+    {
+        self.end_group();
+        self.last_execute = None;
+        match self.redo.pop() {
+            Some(mut entry) => {
+                for command in entry.iter_mut() {
+                    command.apply(model);
+                }
+                self.undo.push_back(entry);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Call this from your `key_pressed` function to undo with `Ctrl+Z` (or `Cmd+Z` on macOS)
+    /// and redo with `Ctrl+Shift+Z` or `Ctrl+Y`.
+    /// Returns `true` when the model was changed.
+    pub fn key_pressed<M>(&mut self, app: &App, model: &mut M, key: Key) -> bool
+    where
+        C: Command<M>,
+    {
+        let mods = &app.keys.mods;
+        if !(mods.ctrl() || mods.logo()) {
+            return false;
+        }
+        match key {
+            Key::Z if mods.shift() => self.redo(model),
+            Key::Z => self.undo(model),
+            Key::Y => self.redo(model),
+            _ => false,
+        }
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty() || self.group.as_ref().is_some_and(|g| !g.is_empty())
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    /// Forget everything, e.g. after loading a snapshot.
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+        self.group = None;
+        self.last_execute = None;
+    }
+
+    fn push_entry(&mut self, entry: Vec<C>) {
+        self.undo.push_back(entry);
+        while self.undo.len() > self.capacity {
+            self.undo.pop_front();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    //Adds to a number, and merges with the add before
+    #[derive(Debug)]
+    struct Add(i32);
+
+    impl Command<i32> for Add {
+        fn apply(&mut self, model: &mut i32) {
+            *model += self.0;
+        }
+
+        fn undo(&mut self, model: &mut i32) {
+            *model -= self.0;
+        }
+
+        fn merge(&mut self, next: &Self) -> bool {
+            self.0 += next.0;
+            true
+        }
+    }
+
+    //Never merges
+    struct Set {
+        to: i32,
+        from: i32,
+    }
+
+    impl Command<i32> for Set {
+        fn apply(&mut self, model: &mut i32) {
+            self.from = *model;
+            *model = self.to;
+        }
+
+        fn undo(&mut self, model: &mut i32) {
+            *model = self.from;
+        }
+    }
+
+    fn set(to: i32) -> Set {
+        Set { to, from: 0 }
+    }
+
+    #[test]
+    fn undo_and_redo() {
+        let mut model = 0;
+        let mut history = History::new();
+        assert!(!history.can_undo());
+        history.execute(&mut model, set(1));
+        history.execute(&mut model, set(2));
+        assert_eq!(model, 2);
+
+        assert!(history.undo(&mut model));
+        assert_eq!(model, 1);
+        assert!(history.undo(&mut model));
+        assert_eq!(model, 0);
+        assert!(!history.undo(&mut model));
+
+        assert!(history.redo(&mut model));
+        assert_eq!(model, 1);
+        //A new command clears what can be redone
+        history.execute(&mut model, set(5));
+        assert!(!history.can_redo());
+        assert!(!history.redo(&mut model));
+        assert!(history.undo(&mut model));
+        assert_eq!(model, 1);
+    }
+
+    #[test]
+    fn capacity_forgets_the_oldest_entries() {
+        let mut model = 0;
+        let mut history = History::new().capacity(3);
+        for i in 1..=5 {
+            history.execute(&mut model, set(i));
+        }
+        let mut undone = 0;
+        while history.undo(&mut model) {
+            undone += 1;
+        }
+        assert_eq!(undone, 3);
+        assert_eq!(model, 2);
+    }
+
+    #[test]
+    fn groups_are_undone_in_one_go() {
+        let mut model = 0;
+        let mut history = History::new();
+        history.begin_group();
+        history.execute(&mut model, set(1));
+        history.execute(&mut model, set(2));
+        history.execute(&mut model, set(3));
+        history.end_group();
+        history.execute(&mut model, set(4));
+
+        history.undo(&mut model);
+        assert_eq!(model, 3);
+        history.undo(&mut model);
+        assert_eq!(model, 0);
+        history.redo(&mut model);
+        assert_eq!(model, 3);
+    }
+
+    #[test]
+    fn empty_groups_are_not_kept() {
+        let mut model = 0;
+        let mut history: History<Set> = History::new();
+        history.begin_group();
+        history.end_group();
+        assert!(!history.can_undo());
+        assert!(!history.undo(&mut model));
+    }
+
+    #[test]
+    fn commands_merge_inside_a_group_but_not_across_groups() {
+        let mut model = 0;
+        let mut history = History::new();
+        let now = Instant::now();
+        for _ in 0..2 {
+            history.begin_group();
+            history.execute_at(&mut model, Add(1), now);
+            history.execute_at(&mut model, Add(1), now);
+            history.end_group();
+        }
+        assert_eq!(model, 4);
+        history.undo(&mut model);
+        assert_eq!(model, 2);
+        history.undo(&mut model);
+        assert_eq!(model, 0);
+    }
+
+    #[test]
+    fn commands_outside_a_group_only_merge_when_they_follow_quickly() {
+        let mut model = 0;
+        let mut history = History::new().merge_window(Duration::from_millis(500));
+        let start = Instant::now();
+        //Three scroll ticks in quick succession
+        for i in 0..3 {
+            history.execute_at(&mut model, Add(1), start + Duration::from_millis(100 * i));
+        }
+        //And another scroll a minute later
+        history.execute_at(&mut model, Add(10), start + Duration::from_secs(60));
+        assert_eq!(model, 13);
+
+        history.undo(&mut model);
+        assert_eq!(model, 3);
+        history.undo(&mut model);
+        assert_eq!(model, 0);
+    }
+
+    #[test]
+    fn commands_do_not_merge_into_an_entry_after_undo() {
+        let mut model = 0;
+        let mut history = History::new();
+        let now = Instant::now();
+        history.execute_at(&mut model, Add(1), now);
+        history.execute_at(&mut model, Add(2), now);
+        history.undo(&mut model);
+        history.redo(&mut model);
+        history.execute_at(&mut model, Add(4), now);
+        assert_eq!(model, 7);
+        history.undo(&mut model);
+        assert_eq!(model, 3);
+    }
+}
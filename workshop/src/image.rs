@@ -0,0 +1,530 @@
+//! Images as input for sketches.
+//!
+//! An `Image` is a PNG or JPEG loaded into memory, where the colour anywhere in the image can
+//! be looked up with a _normalised_ coordinate: `(0.0, 0.0)` is the top left corner and
+//! `(1.0, 1.0)` the bottom right, whatever the size of the image.
+//!
+//! On top of that there are two ways of drawing an image with circles, `Halftone` and
+//! `pointillism`, and `palette`, which finds the main colours of an image as `Lch` colours,
+//! ready for the colour code in `p2`.
+
+use std::io;
+use std::path::Path;
+
+use nannou::color::{IntoLinSrgba, Lab, Lch};
+use nannou::image::RgbaImage;
+use nannou::prelude::*;
+
+use crate::batch::ShapeBatch;
+use crate::layout::{DesignSpace, Fit};
+use crate::rng::SketchRng;
+
+/// The crab in the `images` folder of the workshop.
+pub const CRAB_PATH: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/../images/HappyCrab1_inocean.jpg"
+);
+
+/// An image to sample colours from.
+#[derive(Clone, Debug)]
+pub struct Image {
+    pub pixels: RgbaImage,
+}
+
+impl Image {
+    /// Load a PNG or JPEG file.
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let image = nannou::image::open(path.as_ref())
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))?;
+        Image::from_pixels(image.to_rgba8())
+    }
+
+    /// The image, or an error when it has no pixels to sample.
+    pub fn from_pixels(pixels: RgbaImage) -> io::Result<Self> {
+        if pixels.width() == 0 || pixels.height() == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "the image has no pixels",
+            ));
+        }
+        Ok(Image { pixels })
+    }
+
+    pub fn width(&self) -> u32 {
+        self.pixels.width()
+    }
+
+    pub fn height(&self) -> u32 {
+        self.pixels.height()
+    }
+
+    /// The width and height in pixels.
+    pub fn size(&self) -> Vec2 {
+        vec2(self.width() as f32, self.height() as f32)
+    }
+
+    /// Where the image goes in `within`, keeping its shape. `Fit::Contain` shows the whole
+    /// image, `Fit::Cover` fills `within`.
+    pub fn rect_in(&self, within: Rect, fit: Fit) -> Rect {
+        DesignSpace::new(self.size().x, self.size().y)
+            .fit(fit)
+            .viewport(within)
+    }
+
+    /// The normalised coordinate of the point `p` in the image placed at `rect`.
+    pub fn uv_at(rect: Rect, p: Vec2) -> Vec2 {
+        vec2(
+            (p.x - rect.left()) / rect.w(),
+            (rect.top() - p.y) / rect.h(),
+        )
+    }
+
+    /// The colour of the nearest pixel.
+    pub fn nearest(&self, uv: Vec2) -> Srgba<u8> {
+        let x = (uv.x * self.width() as f32).floor() as i64;
+        let y = (uv.y * self.height() as f32).floor() as i64;
+        let [r, g, b, a] = self.pixel(x, y);
+        Srgba::new(r, g, b, a)
+    }
+
+    /// The colour at `uv`, blended between the four nearest pixels.
+    pub fn sample(&self, uv: Vec2) -> LinSrgba {
+        let p = uv * self.size() - 0.5;
+        let (x, y) = (p.x.floor(), p.y.floor());
+        let (tx, ty) = (p.x - x, p.y - y);
+        let (x, y) = (x as i64, y as i64);
+        let linear = |x, y| {
+            let [r, g, b, a] = self.pixel(x, y);
+            let c = Srgba::new(r, g, b, a).into_lin_srgba();
+            [c.red, c.green, c.blue, c.alpha]
+        };
+        let mix = |a: [f32; 4], b: [f32; 4], t: f32| [0, 1, 2, 3].map(|i| a[i] + (b[i] - a[i]) * t);
+        let top = mix(linear(x, y), linear(x + 1, y), tx);
+        let bottom = mix(linear(x, y + 1), linear(x + 1, y + 1), tx);
+        let [r, g, b, a] = mix(top, bottom, ty);
+        LinSrgba::new(r, g, b, a)
+    }
+
+    /// How bright the image is at `uv`, from `0.0` for black to `1.0` for white.
+    ///
+    /// This is the lightness of `Lch`, so it follows how bright we see a colour, e.g. yellow is
+    /// brighter than blue.
+    pub fn brightness(&self, uv: Vec2) -> f32 {
+        let c = self.sample(uv);
+        let lab: Lab = LinSrgb::new(c.red, c.green, c.blue).into();
+        (lab.l / 100.0).clamp(0.0, 1.0)
+    }
+
+    //The pixel, with coordinates outside the image clamped to the edge
+    fn pixel(&self, x: i64, y: i64) -> [u8; 4] {
+        let x = x.clamp(0, self.width() as i64 - 1) as u32;
+        let y = y.clamp(0, self.height() as i64 - 1) as u32;
+        self.pixels.get_pixel(x, y).0
+    }
+}
+
+/// A circle made from an image.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Dot {
+    pub position: Vec2,
+    pub radius: f32,
+    pub color: LinSrgba,
+}
+
+/// Put all the dots into a batch, so thousands of them can be drawn every frame.
+pub fn push_dots(batch: &mut ShapeBatch, dots: &[Dot]) {
+    for dot in dots {
+        batch.push_circle(dot.position, dot.radius, dot.color);
+    }
+}
+
+/// How the dots of a halftone are laid out.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Pattern {
+    /// Rows and columns.
+    Grid,
+    /// Every other row is moved half a step, like in printed newspapers.
+    Hex,
+}
+
+/// The image as dots on a grid, big where it is dark and small where it is light, like a
+/// newspaper photo.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Halftone {
+    /// The distance between the dots, in pixels.
+    pub spacing: f32,
+    pub pattern: Pattern,
+    /// Big dots where the image is light instead, for light dots on a dark background.
+    pub invert: bool,
+    /// Give each dot the colour of the image, instead of black, or white when inverted.
+    pub colored: bool,
+}
+
+impl Halftone {
+    pub fn new(spacing: f32) -> Self {
+        Halftone {
+            spacing: spacing.max(1.0),
+            pattern: Pattern::Hex,
+            invert: false,
+            colored: false,
+        }
+    }
+
+    pub fn pattern(mut self, pattern: Pattern) -> Self {
+        self.pattern = pattern;
+        self
+    }
+
+    pub fn invert(mut self, invert: bool) -> Self {
+        self.invert = invert;
+        self
+    }
+
+    pub fn colored(mut self, colored: bool) -> Self {
+        self.colored = colored;
+        self
+    }
+
+    /// The dots for `image` placed at `rect`. Dots that aren't coloured are black, or white when
+    /// inverted.
+    pub fn dots(&self, image: &Image, rect: Rect) -> Vec<Dot> {
+        let max_radius = self.spacing * 0.5 * 2.0f32.sqrt();
+        let row_height = match self.pattern {
+            Pattern::Grid => self.spacing,
+            Pattern::Hex => self.spacing * 3.0f32.sqrt() * 0.5,
+        };
+        let rows = (rect.h() / row_height) as usize + 1;
+        let columns = (rect.w() / self.spacing) as usize + 1;
+        let plain = if self.invert { WHITE } else { BLACK }.into_lin_srgba();
+
+        let mut dots = Vec::with_capacity(rows * columns);
+        for row in 0..rows {
+            let shift = match self.pattern {
+                Pattern::Hex if row % 2 == 1 => self.spacing * 0.5,
+                _ => 0.0,
+            };
+            for column in 0..columns {
+                let position = vec2(
+                    rect.left() + shift + column as f32 * self.spacing,
+                    rect.top() - row as f32 * row_height,
+                );
+                if !rect.contains(position) {
+                    continue;
+                }
+                let uv = Image::uv_at(rect, position);
+                let brightness = image.brightness(uv);
+                let ink = if self.invert { brightness } else { 1.0 - brightness };
+                //The area of the dot follows the ink, so the radius follows its square root
+                let radius = max_radius * ink.sqrt();
+                if radius < 0.25 {
+                    continue;
+                }
+                let color = if self.colored { image.sample(uv) } else { plain };
+                dots.push(Dot {
+                    position,
+                    radius,
+                    color,
+                });
+            }
+        }
+        dots
+    }
+}
+
+/// The image as `count` dots of its own colours, scattered at random like a pointillist
+/// painting. The dots are a bit smaller where the image is dark.
+pub fn pointillism(
+    image: &Image,
+    rect: Rect,
+    count: usize,
+    radius: f32,
+    rng: &mut SketchRng,
+) -> Vec<Dot> {
+    (0..count)
+        .map(|_| {
+            let position = rng.in_rect(rect);
+            let uv = Image::uv_at(rect, position);
+            let brightness = image.brightness(uv);
+            Dot {
+                position,
+                radius: radius * (0.5 + 0.5 * brightness) * rng.range(0.7, 1.3),
+                color: image.sample(uv),
+            }
+        })
+        .collect()
+}
+
+/// One of the main colours of an image.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct PaletteColor {
+    pub color: Lch,
+    /// How much of the image has this colour, from `0.0` to `1.0`.
+    pub share: f32,
+}
+
+/// The `k` main colours of the image, found with k-means clustering, most common first.
+///
+/// The colours are compared in `Lab`, where the distance between two colours follows how
+/// different they look. `samples` pixels are picked at random, so large images don't take long.
+pub fn palette(image: &Image, k: usize, samples: usize, rng: &mut SketchRng) -> Vec<PaletteColor> {
+    let k = k.max(1);
+    let points: Vec<[f32; 3]> = (0..samples.max(k))
+        .map(|_| {
+            let c = image.sample(vec2(rng.f32(), rng.f32()));
+            let lab: Lab = LinSrgb::new(c.red, c.green, c.blue).into();
+            [lab.l, lab.a, lab.b]
+        })
+        .collect();
+
+    let mut centers = initial_centers(&points, k, rng);
+    let mut assignment = vec![0; points.len()];
+    for _ in 0..32 {
+        let mut changed = false;
+        for (point, assigned) in points.iter().zip(assignment.iter_mut()) {
+            let nearest = nearest(&centers, point);
+            if nearest != *assigned {
+                *assigned = nearest;
+                changed = true;
+            }
+        }
+        //Move each center to the middle of its points
+        let mut sums = vec![([0.0; 3], 0usize); k];
+        for (point, &assigned) in points.iter().zip(assignment.iter()) {
+            let (sum, n) = &mut sums[assigned];
+            for (s, p) in sum.iter_mut().zip(point) {
+                *s += p;
+            }
+            *n += 1;
+        }
+        for (center, (sum, n)) in centers.iter_mut().zip(sums) {
+            if n > 0 {
+                *center = sum.map(|s| s / n as f32);
+            }
+        }
+        if !changed {
+            break;
+        }
+    }
+
+    let mut counts = vec![0usize; k];
+    for &assigned in assignment.iter() {
+        counts[assigned] += 1;
+    }
+    let mut colors: Vec<PaletteColor> = centers
+        .iter()
+        .zip(counts)
+        .filter(|(_, n)| *n > 0)
+        .map(|(&[l, a, b], n)| PaletteColor {
+            color: Lab::new(l, a, b).into(),
+            share: n as f32 / points.len() as f32,
+        })
+        .collect();
+    colors.sort_by(|a, b| b.share.total_cmp(&a.share));
+    colors
+}
+
+//k-means++: each new center is picked with a chance that grows with its distance to the
+// centers picked so far, so the centers start out spread over the colours
+fn initial_centers(points: &[[f32; 3]], k: usize, rng: &mut SketchRng) -> Vec<[f32; 3]> {
+    let mut centers = vec![points[rng.index(points.len())]];
+    while centers.len() < k {
+        let distances: Vec<f32> = points
+            .iter()
+            .map(|p| distance_squared(&centers[nearest(&centers, p)], p))
+            .collect();
+        let total: f32 = distances.iter().sum();
+        if total <= 0.0 {
+            //Fewer different colours than `k`
+            break;
+        }
+        let mut pick = rng.f32() * total;
+        let mut chosen = points.len() - 1;
+        for (i, d) in distances.iter().enumerate() {
+            if pick < *d {
+                chosen = i;
+                break;
+            }
+            pick -= d;
+        }
+        centers.push(points[chosen]);
+    }
+    centers
+}
+
+fn nearest(centers: &[[f32; 3]], point: &[f32; 3]) -> usize {
+    (0..centers.len())
+        .min_by(|&a, &b| {
+            distance_squared(&centers[a], point).total_cmp(&distance_squared(&centers[b], point))
+        })
+        .unwrap_or(0)
+}
+
+fn distance_squared(a: &[f32; 3], b: &[f32; 3]) -> f32 {
+    a.iter().zip(b).map(|(a, b)| (a - b) * (a - b)).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nannou::image::Rgba;
+
+    fn assert_close(a: f32, b: f32, tolerance: f32) {
+        assert!((a - b).abs() <= tolerance, "{} != {}", a, b);
+    }
+
+    fn flat(w: u32, h: u32, rgb: [u8; 3]) -> Image {
+        let [r, g, b] = rgb;
+        Image::from_pixels(RgbaImage::from_pixel(w, h, Rgba([r, g, b, 255]))).unwrap()
+    }
+
+    //Black on the left half, white on the right
+    fn black_white() -> Image {
+        let pixels = RgbaImage::from_fn(2, 1, |x, _| {
+            Rgba([x as u8 * 255, x as u8 * 255, x as u8 * 255, 255])
+        });
+        Image::from_pixels(pixels).unwrap()
+    }
+
+    #[test]
+    fn empty_images_are_rejected() {
+        assert!(Image::from_pixels(RgbaImage::new(0, 0)).is_err());
+        assert!(Image::from_pixels(RgbaImage::new(4, 0)).is_err());
+        assert!(Image::open("no such image.png").is_err());
+        assert!(Image::from_pixels(RgbaImage::new(1, 1)).is_ok());
+    }
+
+    #[test]
+    fn uv_from_the_top_left() {
+        let rect = Rect::from_x_y_w_h(100.0, 50.0, 200.0, 100.0);
+        assert_eq!(Image::uv_at(rect, vec2(0.0, 100.0)), vec2(0.0, 0.0));
+        assert_eq!(Image::uv_at(rect, vec2(200.0, 0.0)), vec2(1.0, 1.0));
+        assert_eq!(Image::uv_at(rect, vec2(100.0, 50.0)), vec2(0.5, 0.5));
+        assert_eq!(Image::uv_at(rect, vec2(150.0, 75.0)), vec2(0.75, 0.25));
+    }
+
+    #[test]
+    fn sample_blends_between_pixel_centers() {
+        let image = black_white();
+        //On the centers of the pixels
+        assert_close(image.sample(vec2(0.25, 0.5)).red, 0.0, 1e-6);
+        assert_close(image.sample(vec2(0.75, 0.5)).red, 1.0, 1e-6);
+        //Halfway between them, blended in linear colour
+        assert_close(image.sample(vec2(0.5, 0.5)).red, 0.5, 1e-6);
+        assert_close(image.sample(vec2(0.375, 0.5)).red, 0.25, 1e-6);
+        assert_close(image.sample(vec2(0.5, 0.5)).alpha, 1.0, 1e-6);
+    }
+
+    #[test]
+    fn sample_clamps_at_the_edges() {
+        let image = black_white();
+        assert_close(image.sample(vec2(0.0, 0.0)).red, 0.0, 1e-6);
+        assert_close(image.sample(vec2(1.0, 1.0)).red, 1.0, 1e-6);
+        assert_close(image.sample(vec2(-5.0, 0.5)).red, 0.0, 1e-6);
+        assert_close(image.sample(vec2(5.0, 0.5)).red, 1.0, 1e-6);
+        assert_eq!(
+            image.nearest(vec2(2.0, -1.0)),
+            Srgba::new(255, 255, 255, 255)
+        );
+        assert_eq!(image.nearest(vec2(0.49, 0.5)), Srgba::new(0, 0, 0, 255));
+    }
+
+    #[test]
+    fn brightness_follows_what_we_see() {
+        assert_close(flat(1, 1, [0, 0, 0]).brightness(Vec2::ZERO), 0.0, 1e-3);
+        assert_close(
+            flat(1, 1, [255, 255, 255]).brightness(Vec2::ZERO),
+            1.0,
+            1e-3,
+        );
+        let yellow = flat(1, 1, [255, 255, 0]).brightness(Vec2::ZERO);
+        let blue = flat(1, 1, [0, 0, 255]).brightness(Vec2::ZERO);
+        assert!(yellow > blue * 2.0, "{} {}", yellow, blue);
+    }
+
+    #[test]
+    fn dot_area_follows_the_ink() {
+        let rect = Rect::from_w_h(100.0, 100.0);
+        let halftone = Halftone::new(10.0).pattern(Pattern::Grid);
+        let max_radius = 5.0 * 2.0f32.sqrt();
+
+        let black = halftone.dots(&flat(4, 4, [0, 0, 0]), rect);
+        assert_eq!(black.len(), 11 * 11);
+        assert!(black.iter().all(|d| (d.radius - max_radius).abs() < 1e-3));
+        assert!(black.iter().all(|d| d.color == BLACK.into_lin_srgba()));
+        assert!(halftone.dots(&flat(4, 4, [255, 255, 255]), rect).is_empty());
+
+        let gray = flat(4, 4, [128, 128, 128]);
+        let ink = 1.0 - gray.brightness(vec2(0.5, 0.5));
+        let dots = halftone.dots(&gray, rect);
+        assert_close(dots[0].radius, max_radius * ink.sqrt(), 1e-3);
+        //Inverted, the light part of the image is the ink
+        let inverted = halftone.invert(true).dots(&gray, rect);
+        assert_close(inverted[0].radius, max_radius * (1.0 - ink).sqrt(), 1e-3);
+        assert_eq!(inverted[0].color, WHITE.into_lin_srgba());
+        let colored = halftone.colored(true).dots(&gray, rect);
+        assert_eq!(colored[0].color, gray.sample(vec2(0.0, 0.0)));
+    }
+
+    #[test]
+    fn hex_rows_are_shifted() {
+        let rect = Rect::from_w_h(100.0, 100.0);
+        let dots = Halftone::new(10.0).dots(&flat(4, 4, [0, 0, 0]), rect);
+        let row_height = 10.0 * 3.0f32.sqrt() * 0.5;
+        let row = |i: usize| -> Vec<Vec2> {
+            let y = rect.top() - i as f32 * row_height;
+            dots.iter()
+                .map(|d| d.position)
+                .filter(|p| (p.y - y).abs() < 1e-3)
+                .collect()
+        };
+        assert_eq!(row(0).len(), 11);
+        assert_close(row(0)[0].x, -50.0, 1e-3);
+        //Every other row starts half a step in, so one dot less fits
+        assert_eq!(row(1).len(), 10);
+        assert_close(row(1)[0].x, -45.0, 1e-3);
+        assert_close(row(2)[0].x, -50.0, 1e-3);
+        let rows = (100.0 / row_height) as usize + 1;
+        assert_eq!(dots.len(), rows.div_ceil(2) * 11 + rows / 2 * 10);
+    }
+
+    #[test]
+    fn palette_of_two_colours() {
+        //70% red on the left, 30% blue on the right
+        let pixels = RgbaImage::from_fn(100, 100, |x, _| match x < 70 {
+            true => Rgba([255, 0, 0, 255]),
+            false => Rgba([0, 0, 255, 255]),
+        });
+        let image = Image::from_pixels(pixels).unwrap();
+        let mut rng = SketchRng::from_seed(5);
+        let colors = palette(&image, 2, 4000, &mut rng);
+        assert_eq!(colors.len(), 2);
+        assert_close(colors[0].share, 0.7, 0.03);
+        assert_close(colors[1].share, 0.3, 0.03);
+        assert_close(colors.iter().map(|c| c.share).sum(), 1.0, 1e-4);
+
+        let lab = |rgb: LinSrgb| -> Lab { rgb.into() };
+        let distance = |a: Lab, b: Lab| {
+            ((a.l - b.l).powi(2) + (a.a - b.a).powi(2) + (a.b - b.b).powi(2)).sqrt()
+        };
+        let red: Lab = colors[0].color.into();
+        let blue: Lab = colors[1].color.into();
+        assert!(
+            distance(red, lab(LinSrgb::new(1.0, 0.0, 0.0))) < 5.0,
+            "{:?}",
+            red
+        );
+        assert!(
+            distance(blue, lab(LinSrgb::new(0.0, 0.0, 1.0))) < 5.0,
+            "{:?}",
+            blue
+        );
+    }
+
+    #[test]
+    fn palette_of_one_colour() {
+        let mut rng = SketchRng::from_seed(1);
+        let colors = palette(&flat(8, 8, [10, 200, 30]), 4, 100, &mut rng);
+        assert_eq!(colors.len(), 1);
+        assert_close(colors[0].share, 1.0, 1e-6);
+    }
+}
@@ -0,0 +1,480 @@
+//! Named layers that are drawn separately and blended together in the window.
+//!
+//! Every sketch draws straight into the window, so a trail that fades out, like in `p2_e`, fades
+//! out everything else as well.
+//! With layers, the trail is drawn into a layer of its own that is never cleared, and the crisp
+//! foreground into another layer that is cleared every frame.
+//! The layers are then blended together in the window, from the lowest `order` to the highest.
+//!
+//! A layer is either an `offscreen::Offscreen` texture drawn with a `Draw`, or an image where
+//! each pixel is set on the CPU.
+//! The colours in both are _premultiplied_, i.e. the red, green and blue are already multiplied
+//! by the alpha. Drawing onto a transparent background gives premultiplied colours by itself.
+//! A layer that isn't fully opaque is faded into a texture of its own before it is blended,
+//! which multiplies all four channels by the opacity, so it stays premultiplied.
+
+use nannou::color::{IntoLinSrgba, LinSrgba};
+use nannou::image::RgbaImage;
+use nannou::prelude::*;
+use nannou::wgpu;
+
+use crate::offscreen::Offscreen;
+
+/// How a layer is blended with the layers below it.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum BlendMode {
+    /// The layer covers what is below, where it isn't transparent.
+    Normal,
+    /// The colours are added, so it only gets brighter. Good for light and glow.
+    Add,
+    /// The colours are multiplied, so it only gets darker. Good for shadows and vignettes.
+    Multiply,
+    /// The opposite of multiply: it only gets brighter, but never brighter than white.
+    Screen,
+}
+
+impl BlendMode {
+    /// How the GPU blends the colours, with a premultiplied layer on top.
+    pub fn color_blend(self) -> wgpu::BlendComponent {
+        let (src_factor, dst_factor) = match self {
+            BlendMode::Normal => (wgpu::BlendFactor::One, wgpu::BlendFactor::OneMinusSrcAlpha),
+            BlendMode::Add => (wgpu::BlendFactor::One, wgpu::BlendFactor::One),
+            BlendMode::Multiply => (wgpu::BlendFactor::Dst, wgpu::BlendFactor::OneMinusSrcAlpha),
+            BlendMode::Screen => (wgpu::BlendFactor::One, wgpu::BlendFactor::OneMinusSrc),
+        };
+        wgpu::BlendComponent {
+            src_factor,
+            dst_factor,
+            operation: wgpu::BlendOperation::Add,
+        }
+    }
+
+    /// How the GPU blends the alpha. The same for every mode.
+    pub fn alpha_blend(self) -> wgpu::BlendComponent {
+        wgpu::BlendComponent {
+            src_factor: wgpu::BlendFactor::One,
+            dst_factor: wgpu::BlendFactor::OneMinusSrcAlpha,
+            operation: wgpu::BlendOperation::Add,
+        }
+    }
+
+    /// Blend the premultiplied colour `src` onto `dst` on the CPU, with `src` faded by
+    /// `opacity`. This is the same sum the GPU does with `color_blend` and `alpha_blend`.
+    pub fn blend(self, dst: LinSrgba, src: LinSrgba, opacity: f32) -> LinSrgba {
+        let o = opacity.clamp(0.0, 1.0);
+        let (s, sa) = ([src.red * o, src.green * o, src.blue * o], src.alpha * o);
+        let d = [dst.red, dst.green, dst.blue];
+        let rgb = [0, 1, 2].map(|i| match self {
+            BlendMode::Normal => s[i] + d[i] * (1.0 - sa),
+            BlendMode::Add => s[i] + d[i],
+            BlendMode::Multiply => s[i] * d[i] + d[i] * (1.0 - sa),
+            BlendMode::Screen => s[i] + d[i] * (1.0 - s[i]),
+        });
+        LinSrgba::new(rgb[0], rgb[1], rgb[2], sa + dst.alpha * (1.0 - sa))
+    }
+}
+
+/// Blend the pixels of a stack of layers on the CPU, from the bottom up, like `Layers::draw`
+/// does on the GPU. Each layer is a blend mode, an opacity and a premultiplied colour.
+pub fn composite(background: LinSrgba, layers: &[(BlendMode, f32, LinSrgba)]) -> LinSrgba {
+    layers
+        .iter()
+        .fold(background, |dst, &(mode, opacity, src)| {
+            mode.blend(dst, src, opacity)
+        })
+}
+
+/// Darken everything in a layer towards transparent by `amount`, from `0.0` for nothing to
+/// `1.0` for clearing it. Use it in `Layer::render_over` for trails that fade out.
+pub fn fade(draw: &Draw, rect: Rect, amount: f32) {
+    let erase = wgpu::BlendComponent {
+        src_factor: wgpu::BlendFactor::Zero,
+        dst_factor: wgpu::BlendFactor::OneMinusSrcAlpha,
+        operation: wgpu::BlendOperation::Add,
+    };
+    draw.color_blend(erase)
+        .alpha_blend(erase)
+        .rect()
+        .xy(rect.xy())
+        .wh(rect.wh())
+        .color(rgba(0.0, 0.0, 0.0, amount.clamp(0.0, 1.0)));
+}
+
+/// What a layer is drawn into.
+pub enum Surface {
+    /// A texture, drawn into with a `Draw`.
+    Texture(Box<Offscreen>),
+    /// An image where each pixel is set on the CPU, and copied to the GPU when it has changed.
+    Image {
+        image: RgbaImage,
+        texture: wgpu::Texture,
+        changed: bool,
+    },
+}
+
+/// One layer.
+pub struct Layer {
+    pub name: String,
+    pub surface: Surface,
+    pub blend: BlendMode,
+    pub opacity: f32,
+    /// Layers with a higher order are drawn on top.
+    pub order: i32,
+    pub visible: bool,
+    //Pixels per point of texture layers, so they can be drawn in window coordinates
+    scale_factor: f32,
+    //The surface faded by the opacity, when it is below 1
+    faded: Option<Offscreen>,
+}
+
+impl Layer {
+    /// A layer drawn with a `Draw`, with a pixel for every pixel of `window`. It is drawn in
+    /// the same coordinates as the window.
+    pub fn texture(window: &Window, name: impl Into<String>) -> Self {
+        let (w, h) = window.inner_size_pixels();
+        let mut layer = Layer::new(name, Surface::Texture(Box::new(Offscreen::new(window, w, h))));
+        layer.scale_factor = window.scale_factor();
+        layer
+    }
+
+    /// A layer where the pixels of `image` are set on the CPU. The colours in the image are
+    /// not premultiplied; that is done when it is copied to the GPU.
+    pub fn image(window: &Window, name: impl Into<String>, image: RgbaImage) -> Self {
+        let texture = wgpu::TextureBuilder::new()
+            .size([image.width().max(1), image.height().max(1)])
+            .usage(wgpu::TextureUsages::COPY_DST | wgpu::TextureUsages::TEXTURE_BINDING)
+            .format(wgpu::TextureFormat::Rgba8UnormSrgb)
+            .build(window.device());
+        Layer::new(
+            name,
+            Surface::Image {
+                image,
+                texture,
+                changed: true,
+            },
+        )
+    }
+
+    fn new(name: impl Into<String>, surface: Surface) -> Self {
+        Layer {
+            name: name.into(),
+            surface,
+            blend: BlendMode::Normal,
+            opacity: 1.0,
+            order: 0,
+            visible: true,
+            scale_factor: 1.0,
+            faded: None,
+        }
+    }
+
+    pub fn blend(mut self, blend: BlendMode) -> Self {
+        self.blend = blend;
+        self
+    }
+
+    pub fn opacity(mut self, opacity: f32) -> Self {
+        self.opacity = opacity.clamp(0.0, 1.0);
+        self
+    }
+
+    pub fn order(mut self, order: i32) -> Self {
+        self.order = order;
+        self
+    }
+
+    /// Clear the layer and draw into it. Does nothing for image layers.
+    pub fn render<F>(&mut self, window: &Window, view: F)
+    where
+        F: FnOnce(&Draw),
+    {
+        let scale = self.scale_factor;
+        if let Surface::Texture(offscreen) = &mut self.surface {
+            offscreen.render(window, |draw| view(&draw.scale(scale)));
+        }
+    }
+
+    /// Draw into the layer on top of what is there already. Does nothing for image layers.
+    pub fn render_over<F>(&mut self, window: &Window, view: F)
+    where
+        F: FnOnce(&Draw),
+    {
+        let scale = self.scale_factor;
+        if let Surface::Texture(offscreen) = &mut self.surface {
+            offscreen.render_over(window, |draw| view(&draw.scale(scale)));
+        }
+    }
+
+    /// The pixels of an image layer, to change them. They are copied to the GPU before the
+    /// layer is drawn next time.
+    pub fn image_mut(&mut self) -> Option<&mut RgbaImage> {
+        match &mut self.surface {
+            Surface::Image { image, changed, .. } => {
+                *changed = true;
+                Some(image)
+            }
+            Surface::Texture(_) => None,
+        }
+    }
+
+    /// Copy a changed image to the GPU.
+    fn upload(&mut self, window: &Window) {
+        if let Surface::Image {
+            image,
+            texture,
+            changed,
+        } = &mut self.surface
+        {
+            if !*changed {
+                return;
+            }
+            let pixels: Vec<u8> = image.pixels().flat_map(|p| premultiply(p.0)).collect();
+            let device = window.device();
+            let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("layer image"),
+            });
+            texture.upload_data(device, &mut encoder, &pixels);
+            window.queue().submit(Some(encoder.finish()));
+            *changed = false;
+        }
+    }
+
+    //Copy the surface into `faded` with all four channels multiplied by the opacity: the
+    // premultiplied colour of the layer at that opacity
+    fn apply_opacity(&mut self, window: &Window) {
+        if self.opacity >= 1.0 {
+            self.faded = None;
+            return;
+        }
+        let source = surface_texture(&self.surface);
+        let [w, h] = source.size();
+        if self.faded.as_ref().map(Offscreen::size) != Some([w, h]) {
+            self.faded = Some(Offscreen::new(window, w, h));
+        }
+        let opacity = self.opacity;
+        if let Some(faded) = self.faded.as_mut() {
+            faded.render(window, |draw| {
+                let rect = Rect::from_w_h(w as f32, h as f32);
+                draw.color_blend(wgpu::BlendComponent::REPLACE)
+                    .alpha_blend(wgpu::BlendComponent::REPLACE)
+                    .mesh()
+                    .indexed_textured(source, quad(rect), QUAD_INDICES);
+                fade(draw, rect, 1.0 - opacity);
+            });
+        }
+    }
+
+    //What `Layers::draw` blends into the window
+    fn wgpu_texture(&self) -> &wgpu::Texture {
+        match &self.faded {
+            Some(faded) => faded.texture(),
+            None => surface_texture(&self.surface),
+        }
+    }
+}
+
+fn surface_texture(surface: &Surface) -> &wgpu::Texture {
+    match surface {
+        Surface::Texture(offscreen) => offscreen.texture(),
+        Surface::Image { texture, .. } => texture,
+    }
+}
+
+//The corners of `rect` with the texture coordinates that stretch a texture over it
+fn quad(rect: Rect) -> impl Iterator<Item = (Vec3, Vec2)> {
+    [
+        (rect.top_left(), vec2(0.0, 0.0)),
+        (rect.top_right(), vec2(1.0, 0.0)),
+        (rect.bottom_right(), vec2(1.0, 1.0)),
+        (rect.bottom_left(), vec2(0.0, 1.0)),
+    ]
+    .into_iter()
+    .map(|(p, uv)| (p.extend(0.0), uv))
+}
+
+const QUAD_INDICES: [usize; 6] = [0, 1, 2, 0, 2, 3];
+
+//Premultiply in linear colour, since that is how the GPU blends
+fn premultiply([r, g, b, a]: [u8; 4]) -> [u8; 4] {
+    let color = Srgba::new(r, g, b, a).into_format::<f32, f32>().into_linear();
+    let color = LinSrgba::new(
+        color.red * color.alpha,
+        color.green * color.alpha,
+        color.blue * color.alpha,
+        color.alpha,
+    );
+    let color: Srgba<u8> = Srgba::from_linear(color).into_format();
+    [color.red, color.green, color.blue, a]
+}
+
+/// All the layers of a sketch.
+#[derive(Default)]
+pub struct Layers {
+    layers: Vec<Layer>,
+}
+
+impl Layers {
+    pub fn new() -> Self {
+        Layers::default()
+    }
+
+    /// Add a layer. Layers with the same order are drawn in the order they were added.
+    pub fn add(&mut self, layer: Layer) {
+        self.layers.push(layer);
+        self.layers.sort_by_key(|layer| layer.order);
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Layer> {
+        self.layers.iter().find(|layer| layer.name == name)
+    }
+
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut Layer> {
+        self.layers.iter_mut().find(|layer| layer.name == name)
+    }
+
+    /// Change the order of a layer, and sort the layers again.
+    pub fn set_order(&mut self, name: &str, order: i32) {
+        if let Some(layer) = self.get_mut(name) {
+            layer.order = order;
+        }
+        self.layers.sort_by_key(|layer| layer.order);
+    }
+
+    /// The layers from the bottom up.
+    pub fn iter(&self) -> impl Iterator<Item = &Layer> {
+        self.layers.iter()
+    }
+
+    /// Copy the image layers that have changed to the GPU, and fade the layers that aren't
+    /// fully opaque. Call this at the end of `update`, after rendering into the layers.
+    pub fn upload(&mut self, window: &Window) {
+        for layer in self.layers.iter_mut() {
+            layer.upload(window);
+            layer.apply_opacity(window);
+        }
+    }
+
+    /// Blend all the visible layers into `draw`, stretched over `rect`, e.g. the window rect.
+    /// Call this in `view`.
+    pub fn draw(&self, draw: &Draw, rect: Rect) {
+        let shown = self
+            .layers
+            .iter()
+            .filter(|layer| layer.visible && layer.opacity > 0.0);
+        for layer in shown {
+            draw.color_blend(layer.blend.color_blend())
+                .alpha_blend(layer.blend.alpha_blend())
+                .mesh()
+                .indexed_textured(layer.wgpu_texture(), quad(rect), QUAD_INDICES);
+        }
+    }
+}
+
+/// A layer colour as a premultiplied linear colour, for `composite`.
+pub fn premultiplied<C: IntoLinSrgba<f32>>(color: C) -> LinSrgba {
+    let c = color.into_lin_srgba();
+    LinSrgba::new(c.red * c.alpha, c.green * c.alpha, c.blue * c.alpha, c.alpha)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MODES: [BlendMode; 4] = [
+        BlendMode::Normal,
+        BlendMode::Add,
+        BlendMode::Multiply,
+        BlendMode::Screen,
+    ];
+
+    fn close(a: LinSrgba, b: LinSrgba) -> bool {
+        let near = |x: f32, y: f32| (x - y).abs() < 1e-5;
+        near(a.red, b.red)
+            && near(a.green, b.green)
+            && near(a.blue, b.blue)
+            && near(a.alpha, b.alpha)
+    }
+
+    //The colours to blend: a half transparent orange on top of a dark blue
+    fn dst() -> LinSrgba {
+        LinSrgba::new(0.1, 0.2, 0.4, 1.0)
+    }
+
+    fn src() -> LinSrgba {
+        premultiplied(LinSrgba::new(1.0, 0.5, 0.0, 0.5))
+    }
+
+    //What `fade` does on the GPU: the source is multiplied by zero, and what is there by one
+    // minus the alpha of the rect
+    fn gpu_fade(color: LinSrgba, amount: f32) -> LinSrgba {
+        let keep = 1.0 - amount;
+        LinSrgba::new(color.red * keep, color.green * keep, color.blue * keep, color.alpha * keep)
+    }
+
+    #[test]
+    fn fading_first_is_the_same_as_blending_with_opacity() {
+        //`Layers::draw` blends the faded copy at full opacity, `composite` blends with the
+        // opacity, and both have to give the same colour
+        for mode in MODES {
+            for opacity in [0.0, 0.25, 0.6, 1.0] {
+                let faded = gpu_fade(src(), 1.0 - opacity);
+                let gpu = mode.blend(dst(), faded, 1.0);
+                let cpu = composite(dst(), &[(mode, opacity, src())]);
+                assert!(close(gpu, cpu), "{:?} at {}: {:?} {:?}", mode, opacity, gpu, cpu);
+            }
+        }
+    }
+
+    #[test]
+    fn nothing_changes_at_zero_opacity() {
+        for mode in MODES {
+            assert!(close(mode.blend(dst(), src(), 0.0), dst()), "{:?}", mode);
+        }
+    }
+
+    #[test]
+    fn neutral_colours() {
+        let clear = LinSrgba::new(0.0, 0.0, 0.0, 0.0);
+        let white = LinSrgba::new(1.0, 1.0, 1.0, 1.0);
+        assert!(close(BlendMode::Normal.blend(dst(), clear, 1.0), dst()));
+        assert!(close(BlendMode::Add.blend(dst(), clear, 1.0), dst()));
+        assert!(close(BlendMode::Multiply.blend(dst(), white, 1.0), dst()));
+        assert!(close(BlendMode::Screen.blend(dst(), clear, 1.0), dst()));
+        //An opaque layer covers everything
+        assert!(close(BlendMode::Normal.blend(dst(), white, 1.0), white));
+    }
+
+    #[test]
+    fn blend_modes() {
+        let (d, s) = (dst(), src());
+        let normal = BlendMode::Normal.blend(d, s, 1.0);
+        assert!(close(normal, LinSrgba::new(0.55, 0.35, 0.2, 1.0)));
+        let add = BlendMode::Add.blend(d, s, 1.0);
+        assert!(close(add, LinSrgba::new(0.6, 0.45, 0.4, 1.0)));
+        let multiply = BlendMode::Multiply.blend(d, s, 1.0);
+        assert!(close(multiply, LinSrgba::new(0.1, 0.15, 0.2, 1.0)));
+        let screen = BlendMode::Screen.blend(d, s, 1.0);
+        assert!(close(screen, LinSrgba::new(0.55, 0.4, 0.4, 1.0)));
+    }
+
+    #[test]
+    fn composite_goes_from_the_bottom_up() {
+        let red = premultiplied(LinSrgba::new(1.0, 0.0, 0.0, 1.0));
+        let blue = premultiplied(LinSrgba::new(0.0, 0.0, 1.0, 1.0));
+        let black = LinSrgba::new(0.0, 0.0, 0.0, 1.0);
+        let layers = [(BlendMode::Normal, 1.0, red), (BlendMode::Normal, 0.5, blue)];
+        assert!(close(composite(black, &layers), LinSrgba::new(0.5, 0.0, 0.5, 1.0)));
+        let layers = [(BlendMode::Normal, 0.5, blue), (BlendMode::Normal, 1.0, red)];
+        assert!(close(composite(black, &layers), red));
+        assert!(close(composite(black, &[]), black));
+    }
+
+    #[test]
+    fn images_are_premultiplied() {
+        assert_eq!(premultiply([200, 100, 50, 255]), [200, 100, 50, 255]);
+        assert_eq!(premultiply([200, 100, 50, 0]), [0, 0, 0, 0]);
+        let [r, g, b, a] = premultiply([255, 255, 255, 128]);
+        assert_eq!(a, 128);
+        assert!(r == g && g == b && r < 255 && r > 128);
+    }
+}
@@ -0,0 +1,376 @@
+//! Placing things relative to the window.
+//!
+//! `Rect` already has a lot of helpers, like the `mid_bottom_of` we use for the building in
+//! `p0_g`.
+//! This module adds the things we kept doing by hand: grids, margins, anchoring, and fitting a
+//! sketch designed for one window size into a window of another size.
+
+use nannou::prelude::*;
+
+/// Nine points on a rectangle that things can be anchored to.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Anchor {
+    TopLeft,
+    Top,
+    TopRight,
+    Left,
+    Center,
+    Right,
+    BottomLeft,
+    Bottom,
+    BottomRight,
+}
+
+impl Anchor {
+    /// Where the anchor is, from `-1.0` to `1.0` along each axis.
+    pub fn unit(&self) -> Vec2 {
+        match self {
+            Anchor::TopLeft => vec2(-1.0, 1.0),
+            Anchor::Top => vec2(0.0, 1.0),
+            Anchor::TopRight => vec2(1.0, 1.0),
+            Anchor::Left => vec2(-1.0, 0.0),
+            Anchor::Center => vec2(0.0, 0.0),
+            Anchor::Right => vec2(1.0, 0.0),
+            Anchor::BottomLeft => vec2(-1.0, -1.0),
+            Anchor::Bottom => vec2(0.0, -1.0),
+            Anchor::BottomRight => vec2(1.0, -1.0),
+        }
+    }
+
+    /// The anchor point on `rect`.
+    pub fn point(&self, rect: Rect) -> Vec2 {
+        map_unit(rect, self.unit())
+    }
+}
+
+/// A rect of size `wh` with its anchor at the same anchor of `within`, moved by `offset`.
+/// E.g. `place(vec2(100.0, 200.0), Anchor::Bottom, win, Vec2::ZERO)` does the same as
+/// `Rect::from_w_h(100.0, 200.0).mid_bottom_of(win)`.
+pub fn place(wh: Vec2, anchor: Anchor, within: Rect, offset: Vec2) -> Rect {
+    let center = anchor.point(within) - anchor.unit() * wh * 0.5 + offset;
+    Rect::from_x_y_w_h(center.x, center.y, wh.x, wh.y)
+}
+
+/// Maps a position from `-1.0..=1.0` on each axis to a position within `rect`.
+/// `map_unit(win.pad(radius), vec2(t.sin(), t.cos()))` keeps a circle of `radius` inside the
+/// window, like the `map_range` calls in `p1`.
+pub fn map_unit(rect: Rect, unit: Vec2) -> Vec2 {
+    rect.xy() + unit * rect.wh() * 0.5
+}
+
+/// Shrink a rect by a margin on each side. Negative margins grows it.
+pub fn inset(rect: Rect, left: f32, right: f32, bottom: f32, top: f32) -> Rect {
+    let w = (rect.w() - left - right).max(0.0);
+    let h = (rect.h() - bottom - top).max(0.0);
+    Rect::from_x_y_w_h(
+        rect.left() + left + w * 0.5,
+        rect.bottom() + bottom + h * 0.5,
+        w,
+        h,
+    )
+}
+
+/// The part of the rect that is safe from being cut off by e.g. a projector, where `fraction` of
+/// the width and height is left as margin on each side. TV uses 0.05 for "title safe".
+pub fn safe_area(rect: Rect, fraction: f32) -> Rect {
+    let dx = rect.w() * fraction;
+    let dy = rect.h() * fraction;
+    inset(rect, dx, dx, dy, dy)
+}
+
+/// Rows and columns of cells within a rect, with gutters between them.
+///
+/// A grid always has at least one row and one column, also when `rows` or `columns` is set to 0.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Grid {
+    pub rect: Rect,
+    pub rows: usize,
+    pub columns: usize,
+    /// Space between the cells, in pixels.
+    pub gutter: Vec2,
+}
+
+impl Grid {
+    pub fn new(rect: Rect, rows: usize, columns: usize) -> Self {
+        Grid {
+            rect,
+            rows: rows.max(1),
+            columns: columns.max(1),
+            gutter: Vec2::ZERO,
+        }
+    }
+
+    pub fn gutter(mut self, gutter: f32) -> Self {
+        self.gutter = vec2(gutter, gutter);
+        self
+    }
+
+    pub fn gutter_xy(mut self, gutter: Vec2) -> Self {
+        self.gutter = gutter;
+        self
+    }
+
+    //The fields are public, so they can be 0 even though `new` doesn't allow it
+    fn rows_columns(&self) -> (usize, usize) {
+        (self.rows.max(1), self.columns.max(1))
+    }
+
+    /// The size of each cell.
+    pub fn cell_wh(&self) -> Vec2 {
+        let (rows, columns) = self.rows_columns();
+        let w = (self.rect.w() - self.gutter.x * (columns - 1) as f32) / columns as f32;
+        let h = (self.rect.h() - self.gutter.y * (rows - 1) as f32) / rows as f32;
+        vec2(w.max(0.0), h.max(0.0))
+    }
+
+    /// The cell in `row` and `column`, counting rows from the top and columns from the left.
+    pub fn cell(&self, row: usize, column: usize) -> Rect {
+        let wh = self.cell_wh();
+        let x = self.rect.left() + (wh.x + self.gutter.x) * column as f32 + wh.x * 0.5;
+        let y = self.rect.top() - (wh.y + self.gutter.y) * row as f32 - wh.y * 0.5;
+        Rect::from_x_y_w_h(x, y, wh.x, wh.y)
+    }
+
+    /// The cell number `index`, counting along the rows like reading a page.
+    pub fn nth(&self, index: usize) -> Rect {
+        let (_, columns) = self.rows_columns();
+        self.cell(index / columns, index % columns)
+    }
+
+    /// All the cells, row by row.
+    pub fn cells(&self) -> impl Iterator<Item = Rect> + '_ {
+        (0..self.len()).map(move |i| self.nth(i))
+    }
+
+    pub fn len(&self) -> usize {
+        let (rows, columns) = self.rows_columns();
+        rows * columns
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// How a design is fitted into a window with a different shape.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Fit {
+    /// Show the whole design, with bars at the sides or at the top and bottom.
+    Contain,
+    /// Fill the whole window, cutting off the parts of the design that don't fit.
+    Cover,
+    /// Fill the whole window by stretching the design.
+    Stretch,
+}
+
+/// A sketch designed for a fixed size, e.g. 1024x720, fitted into the actual window.
+///
+/// Draw everything in design coordinates on the `Draw` from `apply`, and the sketch looks the
+/// same when the window is resized, or when shown at 1920x1080 on a projector.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct DesignSpace {
+    pub size: Vec2,
+    pub fit: Fit,
+}
+
+impl DesignSpace {
+    pub fn new(w: f32, h: f32) -> Self {
+        DesignSpace {
+            size: vec2(w, h),
+            fit: Fit::Contain,
+        }
+    }
+
+    pub fn fit(mut self, fit: Fit) -> Self {
+        self.fit = fit;
+        self
+    }
+
+    /// The design as a rect centered on the origin. Use this instead of `app.window_rect()`.
+    pub fn rect(&self) -> Rect {
+        Rect::from_w_h(self.size.x, self.size.y)
+    }
+
+    /// How much design units are scaled along each axis to fit into `window`.
+    pub fn scale(&self, window: Rect) -> Vec2 {
+        let sx = window.w() / self.size.x;
+        let sy = window.h() / self.size.y;
+        match self.fit {
+            Fit::Contain => Vec2::splat(sx.min(sy)),
+            Fit::Cover => Vec2::splat(sx.max(sy)),
+            Fit::Stretch => vec2(sx, sy),
+        }
+    }
+
+    /// A draw context where positions and sizes are in design units.
+    pub fn apply(&self, draw: &Draw, window: Rect) -> Draw {
+        let scale = self.scale(window);
+        draw.xy(window.xy()).scale_axes(scale.extend(1.0))
+    }
+
+    /// The part of the window that the design is drawn within, in window coordinates.
+    pub fn viewport(&self, window: Rect) -> Rect {
+        let wh = self.size * self.scale(window);
+        Rect::from_x_y_w_h(window.x(), window.y(), wh.x, wh.y)
+    }
+
+    /// Convert a position in the window, e.g. `app.mouse.position()`, to design units.
+    /// When the window has no size, e.g. when it is minimised, this gives the center of the design.
+    pub fn to_design(&self, window: Rect, p: Vec2) -> Vec2 {
+        let scale = self.scale(window);
+        let d = p - window.xy();
+        let axis = |d: f32, scale: f32| if scale > 0.0 { d / scale } else { 0.0 };
+        vec2(axis(d.x, scale.x), axis(d.y, scale.y))
+    }
+
+    /// Convert a position in design units to the window.
+    pub fn to_window(&self, window: Rect, p: Vec2) -> Vec2 {
+        p * self.scale(window) + window.xy()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: Vec2, b: Vec2) {
+        assert!(a.distance(b) < 1e-3, "{:?} is not {:?}", a, b);
+    }
+
+    fn assert_rect(rect: Rect, x: f32, y: f32, w: f32, h: f32) {
+        assert_close(rect.xy(), vec2(x, y));
+        assert_close(rect.wh(), vec2(w, h));
+    }
+
+    #[test]
+    fn grid_cells_with_gutters() {
+        //Three columns of 30 and two rows of 30, with 10 between them
+        let grid = Grid::new(Rect::from_w_h(110.0, 70.0), 2, 3).gutter(10.0);
+        assert_close(grid.cell_wh(), vec2(30.0, 30.0));
+        assert_rect(grid.cell(0, 0), -40.0, 20.0, 30.0, 30.0);
+        assert_rect(grid.cell(1, 2), 40.0, -20.0, 30.0, 30.0);
+        assert_eq!(grid.nth(5), grid.cell(1, 2));
+        assert_eq!(grid.nth(3), grid.cell(1, 0));
+        assert_eq!(grid.len(), 6);
+
+        let cells: Vec<Rect> = grid.cells().collect();
+        assert_eq!(cells.len(), 6);
+        //The outer cells touch the edges of the grid
+        assert!((cells[0].left() - grid.rect.left()).abs() < 1e-3);
+        assert!((cells[0].top() - grid.rect.top()).abs() < 1e-3);
+        assert!((cells[5].right() - grid.rect.right()).abs() < 1e-3);
+        assert!((cells[5].bottom() - grid.rect.bottom()).abs() < 1e-3);
+        //With a gutter between neighbours
+        assert!((cells[1].left() - cells[0].right() - 10.0).abs() < 1e-3);
+        assert!((cells[0].bottom() - cells[3].top() - 10.0).abs() < 1e-3);
+
+        let grid = Grid::new(Rect::from_w_h(110.0, 70.0), 2, 3).gutter_xy(vec2(10.0, 0.0));
+        assert_close(grid.cell_wh(), vec2(30.0, 35.0));
+    }
+
+    #[test]
+    fn grid_has_at_least_one_cell() {
+        let rect = Rect::from_w_h(100.0, 50.0);
+        assert_eq!(Grid::new(rect, 0, 0).len(), 1);
+
+        //Setting the public fields to 0 works like 1
+        let mut grid = Grid::new(rect, 2, 2).gutter(10.0);
+        grid.rows = 0;
+        grid.columns = 0;
+        assert_close(grid.cell_wh(), vec2(100.0, 50.0));
+        assert_eq!(grid.nth(0), Rect::from_w_h(100.0, 50.0));
+        assert_eq!(grid.cells().count(), 1);
+
+        //Gutters wider than the rect give empty cells
+        let grid = Grid::new(rect, 1, 3).gutter(80.0);
+        assert_eq!(grid.cell_wh().x, 0.0);
+    }
+
+    #[test]
+    fn margins_and_safe_area() {
+        let win = Rect::from_w_h(1024.0, 720.0);
+        let inner = inset(win, 10.0, 20.0, 30.0, 40.0);
+        assert_eq!(inner.left(), -502.0);
+        assert_eq!(inner.right(), 492.0);
+        assert_eq!(inner.bottom(), -330.0);
+        assert_eq!(inner.top(), 320.0);
+        //Negative margins grow the rect, and a rect can't shrink below nothing
+        assert_rect(inset(win, -10.0, -10.0, 0.0, 0.0), 0.0, 0.0, 1044.0, 720.0);
+        assert_close(inset(win, 600.0, 600.0, 0.0, 0.0).wh(), vec2(0.0, 720.0));
+
+        let safe = safe_area(Rect::from_w_h(1000.0, 500.0), 0.05);
+        assert_rect(safe, 0.0, 0.0, 900.0, 450.0);
+    }
+
+    #[test]
+    fn anchoring() {
+        let win = Rect::from_w_h(1024.0, 720.0);
+        let building = place(vec2(100.0, 200.0), Anchor::Bottom, win, Vec2::ZERO);
+        assert_eq!(building, Rect::from_w_h(100.0, 200.0).mid_bottom_of(win));
+
+        let corner = place(vec2(100.0, 50.0), Anchor::TopRight, win, vec2(-10.0, -10.0));
+        assert_eq!(corner.right(), 502.0);
+        assert_eq!(corner.top(), 350.0);
+        assert_rect(place(vec2(10.0, 10.0), Anchor::Center, win, Vec2::ZERO), 0.0, 0.0, 10.0, 10.0);
+
+        assert_close(Anchor::BottomLeft.point(win), vec2(-512.0, -360.0));
+        assert_close(Anchor::Right.point(win), vec2(512.0, 0.0));
+        assert_close(map_unit(win.pad(50.0), vec2(1.0, -1.0)), vec2(462.0, -310.0));
+    }
+
+    //Every fit, between a design and a window of each size
+    fn fits() -> Vec<(DesignSpace, Rect)> {
+        let small = Rect::from_w_h(1024.0, 720.0);
+        let large = Rect::from_w_h(1920.0, 1080.0);
+        let mut fits = Vec::new();
+        for fit in [Fit::Contain, Fit::Cover, Fit::Stretch] {
+            fits.push((DesignSpace::new(1024.0, 720.0).fit(fit), large));
+            fits.push((DesignSpace::new(1920.0, 1080.0).fit(fit), small));
+            //A window that is not centered on the origin
+            fits.push((DesignSpace::new(1024.0, 720.0).fit(fit), large.shift_x(100.0)));
+        }
+        fits
+    }
+
+    #[test]
+    fn design_fits_the_window() {
+        let design = DesignSpace::new(1024.0, 720.0);
+        let large = Rect::from_w_h(1920.0, 1080.0);
+        //1080 / 720 is less than 1920 / 1024, so the height decides when containing
+        assert_eq!(design.scale(large), Vec2::splat(1.5));
+        assert_rect(design.viewport(large), 0.0, 0.0, 1536.0, 1080.0);
+        let cover = design.fit(Fit::Cover);
+        assert_eq!(cover.scale(large), Vec2::splat(1.875));
+        assert_rect(cover.viewport(large), 0.0, 0.0, 1920.0, 1350.0);
+        let stretch = design.fit(Fit::Stretch);
+        assert_eq!(stretch.scale(large), vec2(1.875, 1.5));
+        assert_rect(stretch.viewport(large), 0.0, 0.0, 1920.0, 1080.0);
+
+        //The corners of the design end up at the corners of the viewport
+        for (design, window) in fits() {
+            let viewport = design.viewport(window);
+            let top_left = design.to_window(window, design.rect().top_left());
+            assert_close(top_left, viewport.top_left());
+        }
+    }
+
+    #[test]
+    fn design_round_trips() {
+        for (design, window) in fits() {
+            for p in [vec2(0.0, 0.0), vec2(-512.0, 360.0), vec2(300.0, -123.0)] {
+                assert_close(design.to_design(window, design.to_window(window, p)), p);
+                assert_close(design.to_window(window, design.to_design(window, p)), p);
+            }
+        }
+    }
+
+    #[test]
+    fn minimised_window() {
+        let design = DesignSpace::new(1024.0, 720.0);
+        let minimised = Rect::from_w_h(0.0, 0.0);
+        assert_eq!(design.to_design(minimised, vec2(10.0, 10.0)), Vec2::ZERO);
+        let flat = Rect::from_w_h(800.0, 0.0);
+        let p = design.fit(Fit::Stretch).to_design(flat, vec2(100.0, 10.0));
+        assert!(p.is_finite());
+    }
+}
@@ -0,0 +1,34 @@
+//! Shared building blocks for the workshop sketches.
+//!
+//! The sketches in `p0`, `p1` and `p2` are kept self-contained on purpose, so that each file can
+//! be read from top to bottom during the workshop.
+//! The stuff that we want to reuse between sketches, e.g. randomness, lives in this crate instead.
+//! Each module is described in `workshop/README.md`.
+
+pub mod batch;
+pub mod bezier;
+pub mod capture;
+pub mod check;
+pub mod curves;
+pub mod export;
+pub mod geometry;
+pub mod history;
+pub mod image;
+pub mod layers;
+pub mod layout;
+pub mod lsystem;
+pub mod motion;
+pub mod noise;
+pub mod offscreen;
+pub mod particles;
+pub mod perf;
+pub mod postfx;
+pub mod projection;
+pub mod record;
+pub mod rng;
+pub mod sketch;
+pub mod snapshot;
+pub mod spline;
+pub mod stroke;
+pub mod text;
+pub mod video;
@@ -0,0 +1,453 @@
+//! L-systems, and a turtle that turns them into paths.
+//!
+//! An L-system starts with a string of symbols, the _axiom_, and replaces every symbol using its
+//! _rules_ a number of times.
+//! The result is handed to a `Turtle`, which walks around and draws a line wherever it goes.
+//! The turtle adds its moves to a lyon `Builder`, the same kind of path builder that we use for the
+//! bezier lines in `p2`, so the result can be drawn with `draw.path()` like any other path.
+//!
+//! Symbols can have parameters, written like `F(10.0)` or `A(1,0.5)`.
+//! The turtle understands these symbols, all other symbols are ignored by it:
+//!
+//! | Symbol | Meaning |
+//! |--------|---------|
+//! | `F`, `G` | Move forward and draw. `F(l)` moves `l` pixels. |
+//! | `f` | Move forward without drawing. |
+//! | `+`, `-` | Turn left/right. `+(a)` turns `a` degrees. |
+//! | `\|` | Turn around. |
+//! | `[`, `]` | Remember the position and heading, and go back to it. |
+//! | `>`, `<` | Multiply/divide the step length by the step scale. |
+
+use std::collections::HashMap;
+use std::fmt;
+
+use nannou::lyon::path::path::Builder;
+use nannou::lyon::path::Path;
+use nannou::lyon::math::point;
+use nannou::prelude::*;
+
+use crate::rng::SketchRng;
+
+/// A symbol with optional parameters.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Module {
+    pub symbol: char,
+    pub params: Vec<f32>,
+}
+
+impl Module {
+    pub fn new(symbol: char) -> Self {
+        Module {
+            symbol,
+            params: Vec::new(),
+        }
+    }
+
+    pub fn with_params(symbol: char, params: Vec<f32>) -> Self {
+        Module { symbol, params }
+    }
+
+    /// The first parameter, if there is one.
+    pub fn param(&self) -> Option<f32> {
+        self.params.first().copied()
+    }
+}
+
+impl fmt::Display for Module {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.symbol)?;
+        if !self.params.is_empty() {
+            let params: Vec<String> = self.params.iter().map(|p| p.to_string()).collect();
+            write!(f, "({})", params.join(","))?;
+        }
+        Ok(())
+    }
+}
+
+/// Parse a string like `"F(10)[+F]-F"` into modules. Whitespace is ignored.
+pub fn parse(s: &str) -> Vec<Module> {
+    let mut modules = Vec::new();
+    let mut chars = s.chars().filter(|c| !c.is_whitespace()).peekable();
+    while let Some(symbol) = chars.next() {
+        let mut module = Module::new(symbol);
+        if chars.peek() == Some(&'(') {
+            chars.next();
+            let mut inner = String::new();
+            for c in chars.by_ref() {
+                if c == ')' {
+                    break;
+                }
+                inner.push(c);
+            }
+            module.params = inner
+                .split(',')
+                .filter_map(|p| p.trim().parse().ok())
+                .collect();
+        }
+        modules.push(module);
+    }
+    modules
+}
+
+/// Turn modules back into a string, e.g. for printing.
+pub fn to_string(modules: &[Module]) -> String {
+    modules.iter().map(|m| m.to_string()).collect()
+}
+
+//A parametric successor gets the parameters of the module it replaces, and returns the
+// replacement string, or `None` if the rule doesn't apply to these parameters.
+type ParametricFn = Box<dyn Fn(&[f32]) -> Option<String>>;
+
+/// An axiom and a set of rules.
+pub struct LSystem {
+    axiom: Vec<Module>,
+    //The successors of each symbol, with their weights
+    rules: HashMap<char, Vec<(f32, Vec<Module>)>>,
+    //Tried in the order they were added, before the rules above
+    parametric_rules: HashMap<char, Vec<ParametricFn>>,
+}
+
+impl LSystem {
+    pub fn new(axiom: &str) -> Self {
+        LSystem {
+            axiom: parse(axiom),
+            rules: HashMap::new(),
+            parametric_rules: HashMap::new(),
+        }
+    }
+
+    /// Replace `predecessor` with `successor` every iteration.
+    pub fn rule(self, predecessor: char, successor: &str) -> Self {
+        self.stochastic_rule(predecessor, 1.0, successor)
+    }
+
+    /// When a symbol has several stochastic rules, one of them is picked at random, with a
+    /// probability given by its weight.
+    pub fn stochastic_rule(mut self, predecessor: char, weight: f32, successor: &str) -> Self {
+        self.rules
+            .entry(predecessor)
+            .or_default()
+            .push((weight, parse(successor)));
+        self
+    }
+
+    /// A rule that uses the parameters of the symbol, e.g.
+    /// `.parametric_rule('A', |p| Some(format!("F({0})[+A({1})][-A({1})]", p[0], p[0] * 0.7)))`.
+    /// Return `None` when the rule doesn't apply to these parameters, which works as a
+    /// condition.
+    ///
+    /// The parametric rules of a symbol are tried in the order they were added, and the first
+    /// one that applies is used. When none applies, the other rules of the symbol are used, and
+    /// without those the symbol stays as it is.
+    pub fn parametric_rule<F>(mut self, predecessor: char, successor: F) -> Self
+    where
+        F: Fn(&[f32]) -> Option<String> + 'static,
+    {
+        self.parametric_rules
+            .entry(predecessor)
+            .or_default()
+            .push(Box::new(successor));
+        self
+    }
+
+    /// Apply the rules `iterations` times. The `rng` is only used by stochastic rules.
+    pub fn generate(&self, iterations: u32, rng: &mut SketchRng) -> Vec<Module> {
+        let mut modules = self.axiom.clone();
+        for _ in 0..iterations {
+            let mut next = Vec::with_capacity(modules.len() * 2);
+            for module in &modules {
+                if let Some(successor) = self.apply_parametric(module) {
+                    next.extend(successor);
+                } else if let Some(successor) = self.pick_rule(module, rng) {
+                    next.extend(successor.iter().cloned());
+                } else {
+                    next.push(module.clone());
+                }
+            }
+            modules = next;
+        }
+        modules
+    }
+
+    fn apply_parametric(&self, module: &Module) -> Option<Vec<Module>> {
+        self.parametric_rules
+            .get(&module.symbol)?
+            .iter()
+            .find_map(|f| f(&module.params))
+            .map(|successor| parse(&successor))
+    }
+
+    fn pick_rule(&self, module: &Module, rng: &mut SketchRng) -> Option<&[Module]> {
+        let rules = self.rules.get(&module.symbol)?;
+        if rules.len() == 1 {
+            return Some(&rules[0].1);
+        }
+        let total: f32 = rules.iter().map(|(weight, _)| weight).sum();
+        let mut choice = rng.f32() * total;
+        for (weight, successor) in rules {
+            if choice < *weight {
+                return Some(successor);
+            }
+            choice -= weight;
+        }
+        rules.last().map(|(_, successor)| successor.as_slice())
+    }
+}
+
+/// Walks through the modules and draws a path.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Turtle {
+    /// Length of a step in pixels.
+    pub step: f32,
+    /// The angle turned by `+` and `-`, in degrees.
+    pub angle: f32,
+    /// What `>` and `<` multiplies and divides the step with.
+    pub step_scale: f32,
+    /// Where the turtle starts.
+    pub start: Vec2,
+    /// The direction the turtle starts in, in degrees. 90.0 is up.
+    pub heading: f32,
+}
+
+#[derive(Copy, Clone)]
+struct TurtleState {
+    position: Vec2,
+    heading: f32,
+    step: f32,
+}
+
+/// The path drawn by a turtle, and the rectangle it fits within.
+#[derive(Clone, Debug)]
+pub struct TurtleDrawing {
+    pub path: Path,
+    pub bounds: Rect,
+}
+
+impl TurtleDrawing {
+    /// Scale needed to fit the drawing within `rect`, e.g. `app.window_rect()`.
+    pub fn scale_to_fit(&self, rect: Rect) -> f32 {
+        let w = self.bounds.w().max(f32::EPSILON);
+        let h = self.bounds.h().max(f32::EPSILON);
+        (rect.w() / w).min(rect.h() / h)
+    }
+}
+
+impl Turtle {
+    pub fn new(step: f32, angle: f32) -> Self {
+        Turtle {
+            step,
+            angle,
+            step_scale: 0.7,
+            start: Vec2::ZERO,
+            heading: 90.0,
+        }
+    }
+
+    pub fn start(mut self, start: Vec2) -> Self {
+        self.start = start;
+        self
+    }
+
+    pub fn heading(mut self, heading: f32) -> Self {
+        self.heading = heading;
+        self
+    }
+
+    pub fn step_scale(mut self, step_scale: f32) -> Self {
+        self.step_scale = step_scale;
+        self
+    }
+
+    /// Add the moves of the turtle to a path builder, and return the bounds of the drawing.
+    pub fn walk(&self, modules: &[Module], builder: &mut Builder) -> Rect {
+        let mut state = TurtleState {
+            position: self.start,
+            heading: self.heading.to_radians(),
+            step: self.step,
+        };
+        let mut stack = Vec::new();
+        let mut drawing = false;
+        let mut min = self.start;
+        let mut max = self.start;
+
+        for module in modules {
+            match module.symbol {
+                'F' | 'G' | 'f' => {
+                    let length = module.param().unwrap_or(state.step);
+                    let from = state.position;
+                    state.position += vec2(state.heading.cos(), state.heading.sin()) * length;
+                    if module.symbol == 'f' {
+                        if drawing {
+                            builder.end(false);
+                            drawing = false;
+                        }
+                    } else {
+                        if !drawing {
+                            builder.begin(point(from.x, from.y));
+                            drawing = true;
+                        }
+                        builder.line_to(point(state.position.x, state.position.y));
+                        min = min.min(state.position);
+                        max = max.max(state.position);
+                    }
+                }
+                '+' => state.heading += module.param().unwrap_or(self.angle).to_radians(),
+                '-' => state.heading -= module.param().unwrap_or(self.angle).to_radians(),
+                '|' => state.heading += PI,
+                '>' => state.step *= self.step_scale,
+                '<' => state.step /= self.step_scale,
+                '[' => stack.push(state),
+                ']' => {
+                    if let Some(saved) = stack.pop() {
+                        //Jumping back means lifting the pen
+                        if drawing {
+                            builder.end(false);
+                            drawing = false;
+                        }
+                        state = saved;
+                    }
+                }
+                _ => {}
+            }
+        }
+        if drawing {
+            builder.end(false);
+        }
+        let center = (min + max) * 0.5;
+        let size = max - min;
+        Rect::from_x_y_w_h(center.x, center.y, size.x, size.y)
+    }
+
+    /// Walk through the modules and build a path.
+    pub fn draw(&self, modules: &[Module]) -> TurtleDrawing {
+        let mut builder = Builder::new();
+        let bounds = self.walk(modules, &mut builder);
+        TurtleDrawing {
+            path: builder.build(),
+            bounds,
+        }
+    }
+}
+
+/// The Koch curve. Use with `Turtle::new(step, 60.0).heading(0.0)`.
+pub fn koch_curve() -> LSystem {
+    LSystem::new("F").rule('F', "F+F--F+F")
+}
+
+/// The Hilbert curve, which fills a square. Use with `Turtle::new(step, 90.0)`.
+pub fn hilbert_curve() -> LSystem {
+    LSystem::new("A")
+        .rule('A', "+BF-AFA-FB+")
+        .rule('B', "-AF+BFB+FA-")
+}
+
+/// A fractal plant. Use with `Turtle::new(step, 25.0)`.
+pub fn fractal_plant() -> LSystem {
+    LSystem::new("X")
+        .rule('X', "F+[[X]-X]-F[-FX]+X")
+        .rule('F', "FF")
+}
+
+/// A bush where each branch grows a little differently. Use with `Turtle::new(step, 25.7)`.
+pub fn stochastic_bush() -> LSystem {
+    LSystem::new("F")
+        .stochastic_rule('F', 0.33, "F[+F]F[-F]F")
+        .stochastic_rule('F', 0.33, "F[+F]F")
+        .stochastic_rule('F', 0.34, "F[-F]F")
+}
+
+/// A tree where each branch is shorter than the one it grows from, until the branches get
+/// shorter than `min_length`. Use with `Turtle::new(step, 30.0)`.
+pub fn parametric_tree(trunk_length: f32, min_length: f32) -> LSystem {
+    LSystem::new(&format!("A({})", trunk_length)).parametric_rule('A', move |p| {
+        let length = p.first().copied().unwrap_or(0.0);
+        if length < min_length {
+            return None;
+        }
+        Some(format!(
+            "F({0})[+A({1})][-A({1})]",
+            length,
+            length * 0.7
+        ))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn count(modules: &[Module], symbol: char) -> usize {
+        modules.iter().filter(|m| m.symbol == symbol).count()
+    }
+
+    #[test]
+    fn koch_curve_expansion_lengths() {
+        let mut rng = SketchRng::from_seed(0);
+        for n in 0..5 {
+            let modules = koch_curve().generate(n, &mut rng);
+            let segments = 4usize.pow(n);
+            //Every F adds four turns when it is replaced
+            let turns = (segments - 1) * 4 / 3;
+            assert_eq!(count(&modules, 'F'), segments);
+            assert_eq!(modules.len(), segments + turns);
+        }
+        let modules = koch_curve().generate(1, &mut rng);
+        assert_eq!(to_string(&modules), "F+F--F+F");
+    }
+
+    #[test]
+    fn hilbert_curve_expansion_lengths() {
+        let mut rng = SketchRng::from_seed(0);
+        for n in 1..5 {
+            let modules = hilbert_curve().generate(n, &mut rng);
+            let cells = 4usize.pow(n);
+            assert_eq!(count(&modules, 'A') + count(&modules, 'B'), cells);
+            //A curve through all the cells has one step less than there are cells
+            assert_eq!(count(&modules, 'F'), cells - 1);
+            assert_eq!(modules.len(), cells + (cells - 1) + (cells - 1) * 4 / 3);
+
+            //With a step of 1, it fills a square with 2^n cells on each side
+            let drawing = Turtle::new(1.0, 90.0).draw(&modules);
+            let side = 2f32.powi(n as i32) - 1.0;
+            assert!((drawing.bounds.w() - side).abs() < 1e-3);
+            assert!((drawing.bounds.h() - side).abs() < 1e-3);
+        }
+    }
+
+    #[test]
+    fn stochastic_rules_are_deterministic_per_seed() {
+        let generate = |seed| stochastic_bush().generate(4, &mut SketchRng::from_seed(seed));
+        assert_eq!(generate(1), generate(1));
+        assert_eq!(generate(2), generate(2));
+        assert_ne!(generate(1), generate(2));
+    }
+
+    #[test]
+    fn parametric_rules_are_tried_in_order() {
+        let system = LSystem::new("A(0.5)A(2)A(5)")
+            .parametric_rule('A', |p| if p[0] < 1.0 { Some("X".into()) } else { None })
+            .parametric_rule('A', |p| if p[0] < 3.0 { Some("Y".into()) } else { None });
+        for seed in 0..16 {
+            let modules = system.generate(1, &mut SketchRng::from_seed(seed));
+            assert_eq!(to_string(&modules), "XYA(5)");
+        }
+    }
+
+    #[test]
+    fn parametric_rules_come_before_fixed_rules() {
+        let system = LSystem::new("A(0.5)A(2)")
+            .rule('A', "Z")
+            .parametric_rule('A', |p| if p[0] < 1.0 { Some("X".into()) } else { None });
+        let modules = system.generate(1, &mut SketchRng::from_seed(0));
+        assert_eq!(to_string(&modules), "XZ");
+    }
+
+    #[test]
+    fn parse_and_print_round_trip() {
+        let modules = parse("F(10)[+(25.5)A(1,0.5)]-F");
+        assert_eq!(modules.len(), 7);
+        assert_eq!(modules[0], Module::with_params('F', vec![10.0]));
+        assert_eq!(modules[3].params, vec![1.0, 0.5]);
+        assert_eq!(to_string(&modules), "F(10)[+(25.5)A(1,0.5)]-F");
+    }
+}
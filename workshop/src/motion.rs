@@ -0,0 +1,336 @@
+//! Motion in video frames, to make sketches react to what moves in front of the camera.
+//!
+//! A `Motion` looks at a small grid of brightness values for each frame, e.g. 64 by 48, instead
+//! of every pixel, so it is quick enough to run every frame.
+//! From two frames in a row it finds:
+//!
+//! * the _difference_: how much the brightness changed in each cell, which is high wherever
+//!   something moves;
+//! * the _flow_: which way things moved in each cell, found with the Lucas-Kanade method.
+//!
+//! `Motion::push` turns the flow into a push for something round, like the `Circle`s in `p1`,
+//! and `Motion::field` makes it a `particles::VectorField`.
+
+use nannou::prelude::*;
+
+use crate::image::Image;
+use crate::particles::VectorField;
+
+/// Changes in brightness smaller than this are treated as noise in the video.
+pub const DEFAULT_THRESHOLD: f32 = 0.05;
+
+/// Motion between the last two frames given to `update`.
+#[derive(Clone, Debug)]
+pub struct Motion {
+    columns: usize,
+    rows: usize,
+    threshold: f32,
+    previous: Vec<f32>,
+    current: Vec<f32>,
+    difference: Vec<f32>,
+    //In cells per frame, with y pointing up
+    flow: Vec<Vec2>,
+}
+
+impl Motion {
+    pub fn new(columns: usize, rows: usize) -> Self {
+        let (columns, rows) = (columns.max(3), rows.max(3));
+        let cells = columns * rows;
+        Motion {
+            columns,
+            rows,
+            threshold: DEFAULT_THRESHOLD,
+            previous: Vec::new(),
+            current: Vec::new(),
+            difference: vec![0.0; cells],
+            flow: vec![Vec2::ZERO; cells],
+        }
+    }
+
+    /// Ignore brightness changes below `threshold`, from `0.0` to `1.0`.
+    pub fn threshold(mut self, threshold: f32) -> Self {
+        self.threshold = threshold.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Compare `frame` to the frame given last time. Call this when the video frame changes.
+    pub fn update(&mut self, frame: &Image) {
+        std::mem::swap(&mut self.previous, &mut self.current);
+        let (columns, rows) = (self.columns, self.rows);
+        self.current = (0..rows)
+            .flat_map(|row| (0..columns).map(move |column| (column, row)))
+            .map(|(column, row)| {
+                let uv = vec2(
+                    (column as f32 + 0.5) / columns as f32,
+                    (row as f32 + 0.5) / rows as f32,
+                );
+                frame.brightness(uv)
+            })
+            .collect();
+        if self.previous.len() != self.current.len() {
+            //The first frame, so nothing moved yet
+            self.previous = self.current.clone();
+        }
+
+        for ((difference, current), previous) in self
+            .difference
+            .iter_mut()
+            .zip(self.current.iter())
+            .zip(self.previous.iter())
+        {
+            let d = (current - previous).abs();
+            *difference = if d < self.threshold { 0.0 } else { d };
+        }
+
+        for row in 0..rows {
+            for column in 0..columns {
+                let flow = self.lucas_kanade(column, row);
+                self.flow[row * columns + column] = flow;
+            }
+        }
+    }
+
+    //Lucas-Kanade: assume everything in the 3x3 cells around a cell moved the same way, and
+    // find the movement that best explains the change in brightness of all nine
+    fn lucas_kanade(&self, column: usize, row: usize) -> Vec2 {
+        if self.difference[row * self.columns + column] == 0.0 {
+            return Vec2::ZERO;
+        }
+        let (mut xx, mut xy, mut yy, mut xt, mut yt) = (0.0, 0.0, 0.0, 0.0, 0.0);
+        for dy in -1..=1 {
+            for dx in -1..=1 {
+                let (c, r) = (column as i64 + dx, row as i64 + dy);
+                //Brightness gradient, with y pointing up like in nannou
+                let ix = (self.at(c + 1, r) - self.at(c - 1, r)) * 0.5;
+                let iy = (self.at(c, r - 1) - self.at(c, r + 1)) * 0.5;
+                let it = self.at(c, r) - self.previous_at(c, r);
+                xx += ix * ix;
+                xy += ix * iy;
+                yy += iy * iy;
+                xt += ix * it;
+                yt += iy * it;
+            }
+        }
+        let determinant = xx * yy - xy * xy;
+        if determinant.abs() < 1e-6 {
+            //Flat or a straight edge, where the direction can't be told
+            return Vec2::ZERO;
+        }
+        let flow = vec2(yy * -xt + xy * yt, xy * xt - xx * yt) / determinant;
+        //Flow of more than a few cells per frame is not something this can measure
+        flow.clamp_length_max(2.0)
+    }
+
+    fn at(&self, column: i64, row: i64) -> f32 {
+        self.current[self.clamped(column, row)]
+    }
+
+    fn previous_at(&self, column: i64, row: i64) -> f32 {
+        self.previous[self.clamped(column, row)]
+    }
+
+    fn clamped(&self, column: i64, row: i64) -> usize {
+        let column = column.clamp(0, self.columns as i64 - 1) as usize;
+        let row = row.clamp(0, self.rows as i64 - 1) as usize;
+        row * self.columns + column
+    }
+
+    //The cell under `p`, for a frame drawn at `rect`
+    fn cell_at(&self, rect: Rect, p: Vec2) -> Option<usize> {
+        if !rect.contains(p) {
+            return None;
+        }
+        let uv = Image::uv_at(rect, p);
+        let column = ((uv.x * self.columns as f32) as i64).clamp(0, self.columns as i64 - 1);
+        let row = ((uv.y * self.rows as f32) as i64).clamp(0, self.rows as i64 - 1);
+        Some(row as usize * self.columns + column as usize)
+    }
+
+    //The size of a cell in pixels, for a frame drawn at `rect`
+    fn cell_size(&self, rect: Rect) -> Vec2 {
+        vec2(rect.w() / self.columns as f32, rect.h() / self.rows as f32)
+    }
+
+    fn cell_center(&self, rect: Rect, i: usize) -> Vec2 {
+        let (column, row) = (i % self.columns, i / self.columns);
+        let cell = self.cell_size(rect);
+        vec2(
+            rect.left() + (column as f32 + 0.5) * cell.x,
+            rect.top() - (row as f32 + 0.5) * cell.y,
+        )
+    }
+
+    /// How much the brightness changed at `p`, from `0.0` to `1.0`, for a frame drawn at `rect`.
+    pub fn difference_at(&self, rect: Rect, p: Vec2) -> f32 {
+        self.cell_at(rect, p).map_or(0.0, |i| self.difference[i])
+    }
+
+    /// Which way things moved at `p`, in pixels per frame, for a frame drawn at `rect`.
+    pub fn flow_at(&self, rect: Rect, p: Vec2) -> Vec2 {
+        let cell = self.cell_size(rect);
+        self.cell_at(rect, p).map_or(Vec2::ZERO, |i| self.flow[i] * cell)
+    }
+
+    /// The average flow under a circle at `position`, in pixels per frame, e.g. to add to the
+    /// velocity of a `Circle`. Only cells where something moved count, so a small movement isn't
+    /// averaged away by the still background around it.
+    pub fn push(&self, rect: Rect, position: Vec2, radius: f32) -> Vec2 {
+        let cell = self.cell_size(rect);
+        let mut sum = Vec2::ZERO;
+        let mut count = 0;
+        for (i, flow) in self.flow.iter().enumerate() {
+            if *flow == Vec2::ZERO {
+                continue;
+            }
+            let center = self.cell_center(rect, i);
+            if center.distance(position) <= radius + cell.max_element() * 0.5 {
+                sum += *flow * cell;
+                count += 1;
+            }
+        }
+        if count == 0 {
+            Vec2::ZERO
+        } else {
+            sum / count as f32
+        }
+    }
+
+    /// The flow as a vector field for a frame drawn at `rect`, e.g. for
+    /// `particles.update(&motion.field(rect, 60.0), dt, time)`.
+    /// Particles move with `scale` times the flow per second.
+    pub fn field(&self, rect: Rect, scale: f32) -> impl VectorField + '_ {
+        move |p: Vec2, _time: f32| self.flow_at(rect, p) * scale
+    }
+
+    /// How much moved in the whole frame, from `0.0` to `1.0`.
+    pub fn amount(&self) -> f32 {
+        self.difference.iter().sum::<f32>() / self.difference.len() as f32
+    }
+
+    /// The middle of everything that moved, for a frame drawn at `rect`, or `None` if nothing
+    /// moved.
+    pub fn center(&self, rect: Rect) -> Option<Vec2> {
+        let mut sum = Vec2::ZERO;
+        let mut weight = 0.0;
+        for (i, difference) in self.difference.iter().enumerate() {
+            let center = self.cell_center(rect, i);
+            sum += center * *difference;
+            weight += difference;
+        }
+        if weight > 0.0 {
+            Some(sum / weight)
+        } else {
+            None
+        }
+    }
+
+    /// The difference of each cell with the rect of the cell, e.g. to draw where things moved.
+    pub fn cells(&self, rect: Rect) -> impl Iterator<Item = (Rect, f32)> + '_ {
+        let cell = self.cell_size(rect);
+        self.difference.iter().enumerate().map(move |(i, difference)| {
+            let center = self.cell_center(rect, i);
+            (Rect::from_xy_wh(center, cell), *difference)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nannou::image::{Rgba, RgbaImage};
+
+    //A frame of 160 by 120 pixels, drawn at the same size around the origin
+    fn rect() -> Rect {
+        Rect::from_w_h(160.0, 120.0)
+    }
+
+    //A bright square on black, with soft edges so the brightness changes gradually, centred on
+    // `center` in the pixels of the image, with y pointing down
+    fn square(center: Vec2) -> Image {
+        let pixels = RgbaImage::from_fn(160, 120, |x, y| {
+            let d = (vec2(x as f32 + 0.5, y as f32 + 0.5) - center).abs().max_element();
+            let v = ((1.0 - (d - 15.0) / 15.0).clamp(0.0, 1.0) * 255.0) as u8;
+            Rgba([v, v, v, 255])
+        });
+        Image::from_pixels(pixels).unwrap()
+    }
+
+    fn moved(from: Vec2, to: Vec2) -> Motion {
+        let mut motion = Motion::new(16, 12);
+        motion.update(&square(from));
+        motion.update(&square(to));
+        motion
+    }
+
+    #[test]
+    fn nothing_moves_in_the_first_frame() {
+        let mut motion = Motion::new(16, 12);
+        motion.update(&square(vec2(80.0, 60.0)));
+        assert_eq!(motion.amount(), 0.0);
+        assert_eq!(motion.center(rect()), None);
+        assert_eq!(motion.push(rect(), Vec2::ZERO, 50.0), Vec2::ZERO);
+        //The same frame again doesn't move either
+        motion.update(&square(vec2(80.0, 60.0)));
+        assert_eq!(motion.amount(), 0.0);
+    }
+
+    #[test]
+    fn difference_where_the_square_moved() {
+        let motion = moved(vec2(60.0, 60.0), vec2(66.0, 60.0));
+        //On the soft left edge of the square
+        let edge = vec2(-45.0, 0.0);
+        assert!(motion.difference_at(rect(), edge) > 0.05);
+        //The corner of the frame and the middle of the square stay the same
+        assert_eq!(motion.difference_at(rect(), vec2(-75.0, 55.0)), 0.0);
+        assert_eq!(motion.difference_at(rect(), vec2(-20.0, 0.0)), 0.0);
+        //Outside the frame there is nothing
+        assert_eq!(motion.difference_at(rect(), vec2(500.0, 0.0)), 0.0);
+        assert!(motion.amount() > 0.0);
+    }
+
+    #[test]
+    fn flow_follows_the_square() {
+        let rect = rect();
+        //The squares are centred on a cell, so moving sideways doesn't look like moving up
+        let right = moved(vec2(55.0, 65.0), vec2(59.0, 65.0));
+        let left_edge = vec2(-50.0, -5.0);
+        let flow = right.flow_at(rect, left_edge);
+        assert!(flow.x > 1.0 && flow.x.abs() > flow.y.abs() * 2.0, "{:?}", flow);
+
+        let left = moved(vec2(59.0, 65.0), vec2(55.0, 65.0));
+        let flow = left.flow_at(rect, left_edge);
+        assert!(flow.x < -1.0 && flow.x.abs() > flow.y.abs() * 2.0, "{:?}", flow);
+
+        //Down in the image is down in nannou too, where y points up
+        let down = moved(vec2(85.0, 55.0), vec2(85.0, 59.0));
+        let top_edge = vec2(5.0, 30.0);
+        let flow = down.flow_at(rect, top_edge);
+        assert!(flow.y < -1.0 && flow.y.abs() > flow.x.abs() * 2.0, "{:?}", flow);
+    }
+
+    #[test]
+    fn push_is_the_flow_under_a_circle() {
+        let rect = rect();
+        let motion = moved(vec2(60.0, 60.0), vec2(66.0, 60.0));
+        let push = motion.push(rect, vec2(-20.0, 0.0), 40.0);
+        assert!(push.x > 1.0 && push.x.abs() > push.y.abs() * 2.0, "{:?}", push);
+        //Far from the square there is nothing to push
+        assert_eq!(motion.push(rect, vec2(70.0, 50.0), 5.0), Vec2::ZERO);
+        //The field gives the same flow, scaled
+        let field = motion.field(rect, 60.0);
+        let p = vec2(-45.0, 0.0);
+        assert_eq!(field.velocity(p, 0.0), motion.flow_at(rect, p) * 60.0);
+    }
+
+    #[test]
+    fn center_of_what_moved() {
+        let motion = moved(vec2(60.0, 60.0), vec2(66.0, 60.0));
+        let center = motion.center(rect()).unwrap();
+        //The square was around x = -20 and -14, and stayed in the middle in y
+        assert!(center.x > -30.0 && center.x < -5.0, "{:?}", center);
+        assert!(center.y.abs() < 3.0, "{:?}", center);
+        let cells: Vec<_> = motion.cells(rect()).collect();
+        assert_eq!(cells.len(), 16 * 12);
+        assert_eq!(cells[0].0, Rect::from_x_y_w_h(-75.0, 55.0, 10.0, 10.0));
+    }
+}
@@ -0,0 +1,508 @@
+//! Noise fields that can be sampled from `update` and `view`.
+//!
+//! Where `sin` and `cos` gives us smooth but very regular movement, noise gives us smooth movement
+//! that looks organic.
+//! All the fields implement the `NoiseField` trait, so they can be combined: an `Fbm` stacks
+//! octaves of any field, and a `Warp` bends the coordinates of one field with another one.
+//!
+//! Every field is made from a seed, so the same seed always gives the same noise.
+//! The values are roughly in the range `-1.0..=1.0`.
+
+use nannou::glam::Vec4;
+use nannou::noise::{MultiFractal, NoiseFn, Seedable};
+use nannou::prelude::*;
+
+/// A noise function that can be sampled in 1 to 4 dimensions.
+pub trait NoiseField {
+    fn sample2(&self, p: Vec2) -> f32;
+    fn sample3(&self, p: Vec3) -> f32;
+    fn sample4(&self, p: Vec4) -> f32;
+
+    //We sample a line through the 2D field, a bit off the integer grid where Perlin noise is
+    // always zero.
+    fn sample1(&self, x: f32) -> f32 {
+        self.sample2(vec2(x, 0.37))
+    }
+}
+
+/// Classic Perlin gradient noise.
+#[derive(Clone, Debug)]
+pub struct Perlin {
+    //noise 0.7 exports two structs called `Perlin`, so the name is ambiguous. An fBm with one
+    // octave is exactly the plain Perlin noise, as the first octave has weight 1 and the same seed.
+    inner: nannou::noise::Fbm,
+}
+
+impl Perlin {
+    pub fn new(seed: u64) -> Self {
+        Perlin {
+            inner: nannou::noise::Fbm::new()
+                .set_octaves(1)
+                .set_seed(fold_seed(seed)),
+        }
+    }
+}
+
+impl NoiseField for Perlin {
+    fn sample2(&self, p: Vec2) -> f32 {
+        self.inner.get([p.x as f64, p.y as f64]) as f32
+    }
+
+    fn sample3(&self, p: Vec3) -> f32 {
+        self.inner.get([p.x as f64, p.y as f64, p.z as f64]) as f32
+    }
+
+    fn sample4(&self, p: Vec4) -> f32 {
+        self.inner
+            .get([p.x as f64, p.y as f64, p.z as f64, p.w as f64]) as f32
+    }
+}
+
+/// OpenSimplex noise, which has fewer grid shaped artifacts than Perlin noise.
+#[derive(Clone, Debug)]
+pub struct Simplex {
+    inner: nannou::noise::OpenSimplex,
+}
+
+impl Simplex {
+    pub fn new(seed: u64) -> Self {
+        Simplex {
+            inner: nannou::noise::OpenSimplex::new().set_seed(fold_seed(seed)),
+        }
+    }
+}
+
+impl NoiseField for Simplex {
+    fn sample2(&self, p: Vec2) -> f32 {
+        self.inner.get([p.x as f64, p.y as f64]) as f32
+    }
+
+    fn sample3(&self, p: Vec3) -> f32 {
+        self.inner.get([p.x as f64, p.y as f64, p.z as f64]) as f32
+    }
+
+    fn sample4(&self, p: Vec4) -> f32 {
+        self.inner
+            .get([p.x as f64, p.y as f64, p.z as f64, p.w as f64]) as f32
+    }
+}
+
+/// What a `Worley` field returns.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum WorleyReturn {
+    /// Distance to the closest feature point, gives round cells.
+    F1,
+    /// Distance to the second closest minus the closest, gives thin lines between the cells.
+    F2MinusF1,
+}
+
+//The noise crate takes a `u32` seed, so fold the upper half of the seed into the lower half
+// instead of cutting it off
+fn fold_seed(seed: u64) -> u32 {
+    (seed ^ (seed >> 32)) as u32
+}
+
+/// Worley (cellular) noise: every unit cell has one random feature point, and the value is
+/// based on the distance to the closest ones.
+#[derive(Clone, Debug)]
+pub struct Worley {
+    seed: u64,
+    returns: WorleyReturn,
+}
+
+impl Worley {
+    pub fn new(seed: u64) -> Self {
+        Worley {
+            seed,
+            returns: WorleyReturn::F1,
+        }
+    }
+
+    pub fn returns(mut self, returns: WorleyReturn) -> Self {
+        self.returns = returns;
+        self
+    }
+
+    fn sample<const N: usize>(&self, p: [f32; N]) -> f32 {
+        let cell = p.map(|v| v.floor() as i32);
+        let mut f1 = f32::MAX;
+        let mut f2 = f32::MAX;
+        //Visit the cell the point is in, and all its neighbours, i.e. 3^N cells
+        for neighbour_index in 0..3usize.pow(N as u32) {
+            let mut neighbour = cell;
+            let mut k = neighbour_index;
+            for axis in neighbour.iter_mut() {
+                *axis += (k % 3) as i32 - 1;
+                k /= 3;
+            }
+            let mut distance_squared = 0.0;
+            for axis in 0..N {
+                let feature = neighbour[axis] as f32 + hash_unit(self.seed, &neighbour, axis);
+                let diff = feature - p[axis];
+                distance_squared += diff * diff;
+            }
+            if distance_squared < f1 {
+                f2 = f1;
+                f1 = distance_squared;
+            } else if distance_squared < f2 {
+                f2 = distance_squared;
+            }
+        }
+        let value = match self.returns {
+            WorleyReturn::F1 => f1.sqrt(),
+            WorleyReturn::F2MinusF1 => f2.sqrt() - f1.sqrt(),
+        };
+        //The distances are mostly within 0..1, so we stretch them to the same range as the
+        // other fields.
+        (value * 2.0 - 1.0).clamp(-1.0, 1.0)
+    }
+}
+
+impl NoiseField for Worley {
+    fn sample2(&self, p: Vec2) -> f32 {
+        self.sample(p.to_array())
+    }
+
+    fn sample3(&self, p: Vec3) -> f32 {
+        self.sample(p.to_array())
+    }
+
+    fn sample4(&self, p: Vec4) -> f32 {
+        self.sample(p.to_array())
+    }
+}
+
+/// Fractional Brownian motion: the sum of several octaves of a noise field, where each octave
+/// has a higher frequency and a lower amplitude than the one before.
+#[derive(Clone, Debug)]
+pub struct Fbm<N> {
+    source: N,
+    octaves: u32,
+    frequency: f32,
+    lacunarity: f32,
+    gain: f32,
+}
+
+impl<N: NoiseField> Fbm<N> {
+    pub fn new(source: N) -> Self {
+        Fbm {
+            source,
+            octaves: 5,
+            frequency: 1.0,
+            lacunarity: 2.0,
+            gain: 0.5,
+        }
+    }
+
+    /// The number of layers of noise that are added together.
+    pub fn octaves(mut self, octaves: u32) -> Self {
+        self.octaves = octaves.max(1);
+        self
+    }
+
+    /// The frequency of the first octave.
+    pub fn frequency(mut self, frequency: f32) -> Self {
+        self.frequency = frequency;
+        self
+    }
+
+    /// How much the frequency is multiplied with for each octave.
+    pub fn lacunarity(mut self, lacunarity: f32) -> Self {
+        self.lacunarity = lacunarity;
+        self
+    }
+
+    /// How much the amplitude is multiplied with for each octave.
+    pub fn gain(mut self, gain: f32) -> Self {
+        self.gain = gain;
+        self
+    }
+
+    //Sum the octaves and divide by the total amplitude, to stay within -1.0..=1.0.
+    // Each octave is shifted a bit, so that the octaves don't line up at the origin.
+    fn sum(&self, sample: impl Fn(f32, f32) -> f32) -> f32 {
+        let mut frequency = self.frequency;
+        let mut amplitude = 1.0;
+        let mut total = 0.0;
+        let mut total_amplitude = 0.0;
+        for octave in 0..self.octaves {
+            total += sample(frequency, octave as f32 * 17.31) * amplitude;
+            total_amplitude += amplitude;
+            frequency *= self.lacunarity;
+            amplitude *= self.gain;
+        }
+        total / total_amplitude
+    }
+}
+
+impl<N: NoiseField> NoiseField for Fbm<N> {
+    fn sample2(&self, p: Vec2) -> f32 {
+        self.sum(|frequency, offset| self.source.sample2(p * frequency + offset))
+    }
+
+    fn sample3(&self, p: Vec3) -> f32 {
+        self.sum(|frequency, offset| self.source.sample3(p * frequency + offset))
+    }
+
+    fn sample4(&self, p: Vec4) -> f32 {
+        self.sum(|frequency, offset| self.source.sample4(p * frequency + offset))
+    }
+}
+
+/// Domain warping: the coordinates are pushed around by the `warp` field before the `source`
+/// field is sampled, which gives swirly, marbled patterns.
+#[derive(Clone, Debug)]
+pub struct Warp<N, W> {
+    source: N,
+    warp: W,
+    strength: f32,
+}
+
+//Offsets used to sample the warp field once per axis, so that each axis moves independently.
+const WARP_OFFSETS: [f32; 4] = [0.0, 5.2, 1.3, 8.7];
+
+impl<N: NoiseField, W: NoiseField> Warp<N, W> {
+    pub fn new(source: N, warp: W) -> Self {
+        Warp {
+            source,
+            warp,
+            strength: 1.0,
+        }
+    }
+
+    /// How far the coordinates are pushed, in the units of the source field.
+    pub fn strength(mut self, strength: f32) -> Self {
+        self.strength = strength;
+        self
+    }
+
+    /// The offset the warp field gives the point `p`.
+    pub fn offset2(&self, p: Vec2) -> Vec2 {
+        vec2(
+            self.warp.sample2(p + WARP_OFFSETS[0]),
+            self.warp.sample2(p + WARP_OFFSETS[1]),
+        ) * self.strength
+    }
+
+    pub fn offset3(&self, p: Vec3) -> Vec3 {
+        vec3(
+            self.warp.sample3(p + WARP_OFFSETS[0]),
+            self.warp.sample3(p + WARP_OFFSETS[1]),
+            self.warp.sample3(p + WARP_OFFSETS[2]),
+        ) * self.strength
+    }
+
+    pub fn offset4(&self, p: Vec4) -> Vec4 {
+        Vec4::new(
+            self.warp.sample4(p + WARP_OFFSETS[0]),
+            self.warp.sample4(p + WARP_OFFSETS[1]),
+            self.warp.sample4(p + WARP_OFFSETS[2]),
+            self.warp.sample4(p + WARP_OFFSETS[3]),
+        ) * self.strength
+    }
+}
+
+impl<N: NoiseField, W: NoiseField> NoiseField for Warp<N, W> {
+    fn sample2(&self, p: Vec2) -> f32 {
+        self.source.sample2(p + self.offset2(p))
+    }
+
+    fn sample3(&self, p: Vec3) -> f32 {
+        self.source.sample3(p + self.offset3(p))
+    }
+
+    fn sample4(&self, p: Vec4) -> f32 {
+        self.source.sample4(p + self.offset4(p))
+    }
+}
+
+/// A point that wanders smoothly around the origin, with each axis in the range
+/// `-1.0..=1.0`.
+/// Handy for replacing `vec2(t.cos(), t.sin())` style movement.
+pub fn wander2(field: &impl NoiseField, t: f32, offset: f32) -> Vec2 {
+    vec2(
+        field.sample2(vec2(t, offset)),
+        field.sample2(vec2(offset + 31.7, t)),
+    )
+}
+
+//A number in 0.0..1.0 that is unique for the cell, axis and seed.
+fn hash_unit<const N: usize>(seed: u64, cell: &[i32; N], axis: usize) -> f32 {
+    let mut h = seed ^ (axis as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15);
+    for &c in cell.iter() {
+        h ^= c as u32 as u64;
+        h = h.wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        h ^= h >> 31;
+    }
+    h = h.wrapping_mul(0x94D0_49BB_1331_11EB);
+    h ^= h >> 29;
+    (h >> 40) as f32 / (1u64 << 24) as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn samples(field: &impl NoiseField) -> Vec<f32> {
+        (0..64)
+            .map(|i| field.sample2(vec2(i as f32 * 0.37, i as f32 * 0.21)))
+            .collect()
+    }
+
+    #[test]
+    fn same_seed_gives_same_noise() {
+        assert_eq!(samples(&Perlin::new(5)), samples(&Perlin::new(5)));
+        assert_eq!(samples(&Simplex::new(5)), samples(&Simplex::new(5)));
+        assert_eq!(samples(&Worley::new(5)), samples(&Worley::new(5)));
+        let fbm = |seed| Fbm::new(Simplex::new(seed)).octaves(4);
+        assert_eq!(samples(&fbm(5)), samples(&fbm(5)));
+        let warp = |seed| Warp::new(Perlin::new(seed), Simplex::new(seed + 1)).strength(2.0);
+        assert_eq!(samples(&warp(5)), samples(&warp(5)));
+    }
+
+    #[test]
+    fn different_seeds_give_different_noise() {
+        assert_ne!(samples(&Perlin::new(5)), samples(&Perlin::new(6)));
+        assert_ne!(samples(&Simplex::new(5)), samples(&Simplex::new(6)));
+        assert_ne!(samples(&Worley::new(5)), samples(&Worley::new(6)));
+    }
+
+    #[test]
+    fn upper_bits_of_the_seed_count() {
+        let low = 5;
+        let high = 5 | (1 << 40);
+        assert_ne!(fold_seed(low), fold_seed(high));
+        assert_ne!(samples(&Perlin::new(low)), samples(&Perlin::new(high)));
+        assert_ne!(samples(&Simplex::new(low)), samples(&Simplex::new(high)));
+    }
+
+    //Fields with known values, to check how `Fbm` and `Warp` combine their sources
+    struct Constant(f32);
+
+    impl NoiseField for Constant {
+        fn sample2(&self, _p: Vec2) -> f32 {
+            self.0
+        }
+        fn sample3(&self, _p: Vec3) -> f32 {
+            self.0
+        }
+        fn sample4(&self, _p: Vec4) -> f32 {
+            self.0
+        }
+    }
+
+    struct X;
+
+    impl NoiseField for X {
+        fn sample2(&self, p: Vec2) -> f32 {
+            p.x
+        }
+        fn sample3(&self, p: Vec3) -> f32 {
+            p.x
+        }
+        fn sample4(&self, p: Vec4) -> f32 {
+            p.x
+        }
+    }
+
+    fn assert_in_range(name: &str, field: &impl NoiseField) {
+        let mut min = f32::MAX;
+        let mut max = f32::MIN;
+        for i in 0..2000 {
+            let t = i as f32 * 0.173;
+            let values = [
+                field.sample2(vec2(t, t * 0.61 - 40.0)),
+                field.sample3(vec3(t * 0.37, -t, t * 0.11)),
+                field.sample4(Vec4::new(t, t * 0.29, -t * 0.53, 3.0)),
+            ];
+            for v in values {
+                assert!((-1.0..=1.0).contains(&v), "{} gave {}", name, v);
+                min = min.min(v);
+                max = max.max(v);
+            }
+        }
+        //Not stuck on one value
+        assert!(max - min > 0.5, "{} only spans {}..{}", name, min, max);
+    }
+
+    #[test]
+    fn fields_stay_in_range() {
+        assert_in_range("perlin", &Perlin::new(3));
+        assert_in_range("simplex", &Simplex::new(3));
+        assert_in_range("worley", &Worley::new(3));
+        assert_in_range("worley f2-f1", &Worley::new(3).returns(WorleyReturn::F2MinusF1));
+        assert_in_range("fbm", &Fbm::new(Perlin::new(3)).octaves(6));
+        assert_in_range("warp", &Warp::new(Simplex::new(3), Perlin::new(4)).strength(4.0));
+    }
+
+    #[test]
+    fn perlin_is_zero_on_the_grid() {
+        let perlin = Perlin::new(9);
+        for x in -3..3 {
+            for y in -3..3 {
+                assert!(perlin.sample2(vec2(x as f32, y as f32)).abs() < 1e-6);
+            }
+        }
+    }
+
+    #[test]
+    fn one_octave_is_the_source() {
+        let source = Simplex::new(2);
+        let fbm = Fbm::new(Simplex::new(2)).octaves(1).frequency(3.0);
+        for i in 0..20 {
+            let p = vec2(i as f32 * 0.3, 1.7);
+            assert_eq!(fbm.sample2(p), source.sample2(p * 3.0));
+        }
+    }
+
+    #[test]
+    fn octaves_are_weighted_by_gain() {
+        //At x = 1, `X` returns the frequency of each octave plus its offset
+        let p = vec2(1.0, 0.0);
+        let fbm = Fbm::new(X).octaves(2).lacunarity(2.0).gain(0.5);
+        let expected = (1.0 + 0.5 * (2.0 + 17.31)) / 1.5;
+        assert!((fbm.sample2(p) - expected).abs() < 1e-4);
+
+        //With no gain, only the first octave counts
+        let fbm = Fbm::new(X).octaves(8).frequency(4.0).gain(0.0);
+        assert_eq!(fbm.sample2(p), 4.0);
+
+        //The sum is normalised, so a constant field stays the same for any number of octaves
+        for octaves in 1..8 {
+            let fbm = Fbm::new(Constant(0.75)).octaves(octaves).gain(0.8);
+            assert!((fbm.sample3(vec3(1.0, 2.0, 3.0)) - 0.75).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn more_octaves_add_detail() {
+        //Neighbouring samples differ more when high frequencies are added, even though the sum
+        // is normalised, which makes the first octave weaker
+        let roughness = |octaves| {
+            let fbm = Fbm::new(Perlin::new(1)).octaves(octaves);
+            (0..500)
+                .map(|i| {
+                    let p = vec2(i as f32 * 0.01 + 0.05, 0.37);
+                    (fbm.sample2(p + vec2(0.01, 0.0)) - fbm.sample2(p)).abs()
+                })
+                .sum::<f32>()
+        };
+        assert!(roughness(6) > roughness(1) * 1.2);
+    }
+
+    #[test]
+    fn warp_moves_the_coordinates() {
+        let warp = Warp::new(X, Constant(0.25)).strength(2.0);
+        assert_eq!(warp.offset2(vec2(3.0, 4.0)), vec2(0.5, 0.5));
+        assert_eq!(warp.sample2(vec2(1.0, 0.0)), 1.5);
+        assert_eq!(warp.sample3(vec3(1.0, 0.0, 0.0)), 1.5);
+        assert_eq!(warp.sample4(Vec4::new(1.0, 0.0, 0.0, 0.0)), 1.5);
+
+        //Without strength the source is sampled where it is
+        let source = Perlin::new(7);
+        let still = Warp::new(Perlin::new(7), Simplex::new(8)).strength(0.0);
+        assert_eq!(samples(&still), samples(&source));
+        let warped = Warp::new(Perlin::new(7), Simplex::new(8)).strength(1.0);
+        assert_ne!(samples(&warped), samples(&source));
+    }
+}
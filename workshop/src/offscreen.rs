@@ -0,0 +1,87 @@
+//! Drawing into a texture instead of a window.
+//!
+//! The texture can then be drawn in one or more windows, e.g. warped onto a projector with
+//! `projection`, or blended with other textures.
+
+use nannou::prelude::*;
+use nannou::wgpu;
+
+/// The format of offscreen textures. 16 bit floats leave room for colours brighter than white,
+/// and for blending many layers without banding.
+pub const TEXTURE_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
+
+/// A texture that can be drawn into with a `Draw`, and then drawn in a window.
+pub struct Offscreen {
+    texture: wgpu::Texture,
+    renderer: nannou::draw::Renderer,
+    draw: Draw,
+}
+
+impl Offscreen {
+    /// A `w` by `h` pixel texture, made on the GPU of `window`.
+    pub fn new(window: &Window, w: u32, h: u32) -> Self {
+        let device = window.device();
+        let texture = wgpu::TextureBuilder::new()
+            .size([w.max(1), h.max(1)])
+            .usage(wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING)
+            .sample_count(1)
+            .format(TEXTURE_FORMAT)
+            .build(device);
+        let renderer = nannou::draw::RendererBuilder::new()
+            .build_from_texture_descriptor(device, texture.descriptor());
+        Offscreen {
+            texture,
+            renderer,
+            draw: Draw::new(),
+        }
+    }
+
+    /// Draw into the texture. The origin is in the middle of the texture, like in a window,
+    /// and the background is transparent unless `view` draws one.
+    ///
+    /// Call this from `update`, so the texture is ready when the windows draw it.
+    pub fn render<F>(&mut self, window: &Window, view: F)
+    where
+        F: FnOnce(&Draw),
+    {
+        self.draw.reset();
+        self.draw.background().color(rgba(0.0, 0.0, 0.0, 0.0));
+        view(&self.draw);
+        self.submit(window);
+    }
+
+    /// Like `render`, but keeps what was drawn before, for trails and feedback effects.
+    pub fn render_over<F>(&mut self, window: &Window, view: F)
+    where
+        F: FnOnce(&Draw),
+    {
+        self.draw.reset();
+        view(&self.draw);
+        self.submit(window);
+    }
+
+    fn submit(&mut self, window: &Window) {
+        let device = window.device();
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("offscreen"),
+        });
+        self.renderer
+            .render_to_texture(device, &mut encoder, &self.draw, &self.texture);
+        window.queue().submit(Some(encoder.finish()));
+    }
+
+    pub fn texture(&self) -> &wgpu::Texture {
+        &self.texture
+    }
+
+    /// The size of the texture in pixels.
+    pub fn size(&self) -> [u32; 2] {
+        self.texture.size()
+    }
+
+    /// The texture as a rect centered on the origin, in pixels.
+    pub fn rect(&self) -> Rect {
+        let [w, h] = self.size();
+        Rect::from_w_h(w as f32, h as f32)
+    }
+}
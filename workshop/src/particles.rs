@@ -0,0 +1,624 @@
+//! Particles that flow through a vector field.
+//!
+//! Where the `Circle` in `p1` is one struct per circle, a `ParticleSystem` keeps one `Vec` per
+//! property instead (positions, velocities, ages, ...).
+//! This is called _structure of arrays_, and makes it cheap to update a very large number of
+//! particles every frame.
+
+use nannou::color::LinSrgba;
+use nannou::prelude::*;
+
+use crate::batch::{Instance, ShapeBatch};
+use crate::noise::NoiseField;
+use crate::rng::SketchRng;
+
+//The dots are only a few pixels wide, so they don't need as many segments as a `ShapeBatch`
+// normally uses
+const DOT_RESOLUTION: usize = 8;
+
+/// Gives the velocity particles should have at a position and time.
+///
+/// Any closure `Fn(Vec2, f32) -> Vec2` is a vector field, e.g.
+/// `|p: Vec2, _t: f32| vec2(-p.y, p.x)` makes particles swirl around the center.
+pub trait VectorField {
+    fn velocity(&self, position: Vec2, time: f32) -> Vec2;
+}
+
+impl<F> VectorField for F
+where
+    F: Fn(Vec2, f32) -> Vec2,
+{
+    fn velocity(&self, position: Vec2, time: f32) -> Vec2 {
+        self(position, time)
+    }
+}
+
+/// How a `NoiseFlow` turns noise values into directions.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum FlowMode {
+    /// The noise value is used as an angle. Particles tend to gather in streams.
+    Angle,
+    /// The curl of the noise. Particles never gather in one spot, but swirl around.
+    Curl,
+}
+
+/// A vector field made from a noise field that evolves over time.
+#[derive(Clone, Debug)]
+pub struct NoiseFlow<N> {
+    field: N,
+    mode: FlowMode,
+    scale: f32,
+    speed: f32,
+    evolution: f32,
+}
+
+impl<N: NoiseField> NoiseFlow<N> {
+    pub fn new(field: N) -> Self {
+        NoiseFlow {
+            field,
+            mode: FlowMode::Angle,
+            scale: 0.005,
+            speed: 100.0,
+            evolution: 0.1,
+        }
+    }
+
+    pub fn mode(mut self, mode: FlowMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// How much pixel coordinates are scaled before sampling the noise. Smaller values gives
+    /// larger swirls.
+    pub fn scale(mut self, scale: f32) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    /// The speed of the flow, in pixels per second.
+    pub fn speed(mut self, speed: f32) -> Self {
+        self.speed = speed;
+        self
+    }
+
+    /// How fast the field itself changes over time.
+    pub fn evolution(mut self, evolution: f32) -> Self {
+        self.evolution = evolution;
+        self
+    }
+}
+
+impl<N: NoiseField> VectorField for NoiseFlow<N> {
+    fn velocity(&self, position: Vec2, time: f32) -> Vec2 {
+        let p = (position * self.scale).extend(time * self.evolution);
+        match self.mode {
+            FlowMode::Angle => {
+                let angle = self.field.sample3(p) * TAU;
+                vec2(angle.cos(), angle.sin()) * self.speed
+            }
+            FlowMode::Curl => {
+                let e = 0.01;
+                let dx = self.field.sample3(p + vec3(e, 0.0, 0.0))
+                    - self.field.sample3(p - vec3(e, 0.0, 0.0));
+                let dy = self.field.sample3(p + vec3(0.0, e, 0.0))
+                    - self.field.sample3(p - vec3(0.0, e, 0.0));
+                //The curl of a 2D field is its gradient rotated a quarter turn
+                vec2(dy, -dx).normalize_or_zero() * self.speed
+            }
+        }
+    }
+}
+
+/// Values that can be blended, so they can be used in a `Curve`.
+pub trait Lerp: Copy {
+    fn lerp(self, other: Self, t: f32) -> Self;
+}
+
+impl Lerp for f32 {
+    fn lerp(self, other: Self, t: f32) -> Self {
+        self + (other - self) * t
+    }
+}
+
+impl Lerp for Vec2 {
+    fn lerp(self, other: Self, t: f32) -> Self {
+        Vec2::lerp(self, other, t)
+    }
+}
+
+impl Lerp for LinSrgba {
+    fn lerp(self, other: Self, t: f32) -> Self {
+        LinSrgba::new(
+            Lerp::lerp(self.red, other.red, t),
+            Lerp::lerp(self.green, other.green, t),
+            Lerp::lerp(self.blue, other.blue, t),
+            Lerp::lerp(self.alpha, other.alpha, t),
+        )
+    }
+}
+
+/// A value that changes over the life of a particle, given as keys at positions from `0.0`
+/// (born) to `1.0` (dead).
+#[derive(Clone, Debug)]
+pub struct Curve<T> {
+    keys: Vec<(f32, T)>,
+}
+
+impl<T: Lerp> Curve<T> {
+    /// The keys must be sorted by their position.
+    /// A curve without any keys is always `T::default()`, e.g. transparent black for colours.
+    pub fn new(keys: Vec<(f32, T)>) -> Self
+    where
+        T: Default,
+    {
+        if keys.is_empty() {
+            return Curve::constant(T::default());
+        }
+        Curve { keys }
+    }
+
+    pub fn constant(value: T) -> Self {
+        Curve {
+            keys: vec![(0.0, value)],
+        }
+    }
+
+    /// A curve going straight from `from` to `to`.
+    pub fn linear(from: T, to: T) -> Self {
+        Curve {
+            keys: vec![(0.0, from), (1.0, to)],
+        }
+    }
+
+    pub fn sample(&self, t: f32) -> T {
+        let first = self.keys[0];
+        if t <= first.0 {
+            return first.1;
+        }
+        for pair in self.keys.windows(2) {
+            let (t0, a) = pair[0];
+            let (t1, b) = pair[1];
+            if t <= t1 {
+                let local = if t1 > t0 { (t - t0) / (t1 - t0) } else { 1.0 };
+                return a.lerp(b, local);
+            }
+        }
+        self.keys[self.keys.len() - 1].1
+    }
+}
+
+/// Spawns particles at a position.
+#[derive(Clone, Debug)]
+pub struct Emitter {
+    pub position: Vec2,
+    /// Particles per second.
+    pub rate: f32,
+    /// The shortest and longest lifetime in seconds. Each particle gets a random lifetime in
+    /// between.
+    pub lifetime: (f32, f32),
+    /// Particles are spawned within this radius around the position.
+    pub spread: f32,
+    /// The velocity a particle is born with.
+    pub velocity: Vec2,
+    //Fraction of a particle carried over from last update, so that low rates still work
+    accumulator: f32,
+}
+
+impl Emitter {
+    pub fn new(position: Vec2, rate: f32) -> Self {
+        Emitter {
+            position,
+            rate,
+            lifetime: (2.0, 4.0),
+            spread: 5.0,
+            velocity: Vec2::ZERO,
+            accumulator: 0.0,
+        }
+    }
+
+    pub fn lifetime(mut self, min: f32, max: f32) -> Self {
+        self.lifetime = (min, max);
+        self
+    }
+
+    pub fn spread(mut self, spread: f32) -> Self {
+        self.spread = spread;
+        self
+    }
+
+    pub fn velocity(mut self, velocity: Vec2) -> Self {
+        self.velocity = velocity;
+        self
+    }
+}
+
+/// The state of a single particle, as given by `ParticleSystem::iter`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Particle {
+    pub position: Vec2,
+    pub velocity: Vec2,
+    /// How far the particle is through its life, from `0.0` to `1.0`.
+    pub life: f32,
+}
+
+/// A particle system with emitters, stored as structure of arrays.
+#[derive(Clone, Debug)]
+pub struct ParticleSystem {
+    pub emitters: Vec<Emitter>,
+    pub color_over_life: Curve<LinSrgba>,
+    pub size_over_life: Curve<f32>,
+    /// How quickly particles take on the velocity of the field, per second.
+    pub steering: f32,
+    /// No new particles are spawned while there are this many.
+    pub max_particles: usize,
+    positions: Vec<Vec2>,
+    velocities: Vec<Vec2>,
+    ages: Vec<f32>,
+    lifetimes: Vec<f32>,
+    //The last `trail_length` positions of each particle, one block after the other.
+    // `trail_head` is where the newest position goes in each block.
+    trails: Vec<Vec2>,
+    trail_length: usize,
+    trail_head: usize,
+    rng: SketchRng,
+}
+
+impl ParticleSystem {
+    pub fn new(rng: SketchRng) -> Self {
+        ParticleSystem {
+            emitters: Vec::new(),
+            color_over_life: Curve::linear(
+                lin_srgba(1.0, 1.0, 1.0, 1.0),
+                lin_srgba(1.0, 1.0, 1.0, 0.0),
+            ),
+            size_over_life: Curve::constant(2.0),
+            steering: 4.0,
+            max_particles: 100_000,
+            positions: Vec::new(),
+            velocities: Vec::new(),
+            ages: Vec::new(),
+            lifetimes: Vec::new(),
+            trails: Vec::new(),
+            trail_length: 0,
+            trail_head: 0,
+            rng,
+        }
+    }
+
+    pub fn emitter(mut self, emitter: Emitter) -> Self {
+        self.emitters.push(emitter);
+        self
+    }
+
+    pub fn color_over_life(mut self, curve: Curve<LinSrgba>) -> Self {
+        self.color_over_life = curve;
+        self
+    }
+
+    pub fn size_over_life(mut self, curve: Curve<f32>) -> Self {
+        self.size_over_life = curve;
+        self
+    }
+
+    pub fn max_particles(mut self, max_particles: usize) -> Self {
+        self.max_particles = max_particles;
+        self
+    }
+
+    /// Keep the last `length` positions of every particle, to draw trails. `0` turns trails off.
+    pub fn trail_length(mut self, length: usize) -> Self {
+        self.trail_length = length;
+        self.trail_head = 0;
+        self.trails = self
+            .positions
+            .iter()
+            .flat_map(|&p| std::iter::repeat_n(p, length))
+            .collect();
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.positions.clear();
+        self.velocities.clear();
+        self.ages.clear();
+        self.lifetimes.clear();
+        self.trails.clear();
+    }
+
+    /// Spawn a single particle.
+    pub fn spawn(&mut self, position: Vec2, velocity: Vec2, lifetime: f32) {
+        if self.len() >= self.max_particles {
+            return;
+        }
+        self.positions.push(position);
+        self.velocities.push(velocity);
+        self.ages.push(0.0);
+        self.lifetimes.push(lifetime.max(f32::EPSILON));
+        self.trails
+            .extend(std::iter::repeat_n(position, self.trail_length));
+    }
+
+    /// Spawn from the emitters, move all the particles through the field and remove the dead
+    /// ones.
+    /// Call it from `update` with `update.since_last.as_secs_f32()` as `dt`, and `app.time` as
+    /// `time`.
+    pub fn update(&mut self, field: &impl VectorField, dt: f32, time: f32) {
+        self.emit(dt);
+
+        let steering = (self.steering * dt).min(1.0);
+        for i in 0..self.positions.len() {
+            let target = field.velocity(self.positions[i], time);
+            self.velocities[i] = self.velocities[i].lerp(target, steering);
+            self.positions[i] += self.velocities[i] * dt;
+            self.ages[i] += dt;
+        }
+
+        self.remove_dead();
+
+        if self.trail_length > 0 {
+            self.trail_head = (self.trail_head + 1) % self.trail_length;
+            for (i, &position) in self.positions.iter().enumerate() {
+                self.trails[i * self.trail_length + self.trail_head] = position;
+            }
+        }
+    }
+
+    fn emit(&mut self, dt: f32) {
+        for e in 0..self.emitters.len() {
+            let emitter = &mut self.emitters[e];
+            emitter.accumulator += emitter.rate * dt;
+            let count = emitter.accumulator.floor();
+            emitter.accumulator -= count;
+            let emitter = emitter.clone();
+            for _ in 0..count as usize {
+                let angle = self.rng.range(0.0, TAU);
+                let distance = self.rng.f32().sqrt() * emitter.spread;
+                let position = emitter.position + vec2(angle.cos(), angle.sin()) * distance;
+                let lifetime = self.rng.range(emitter.lifetime.0, emitter.lifetime.1);
+                self.spawn(position, emitter.velocity, lifetime);
+            }
+        }
+    }
+
+    //Swap the dead particle with the last one and shrink the arrays, so we never have to
+    // shift the whole arrays.
+    fn remove_dead(&mut self) {
+        let mut i = 0;
+        while i < self.positions.len() {
+            if self.ages[i] < self.lifetimes[i] {
+                i += 1;
+                continue;
+            }
+            let last = self.positions.len() - 1;
+            self.positions.swap_remove(i);
+            self.velocities.swap_remove(i);
+            self.ages.swap_remove(i);
+            self.lifetimes.swap_remove(i);
+            if self.trail_length > 0 {
+                let len = self.trail_length;
+                if i != last {
+                    self.trails.copy_within(last * len..(last + 1) * len, i * len);
+                }
+                self.trails.truncate(last * len);
+            }
+        }
+    }
+
+    pub fn positions(&self) -> &[Vec2] {
+        &self.positions
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = Particle> + '_ {
+        (0..self.len()).map(move |i| Particle {
+            position: self.positions[i],
+            velocity: self.velocities[i],
+            life: self.ages[i] / self.lifetimes[i],
+        })
+    }
+
+    /// The trail of particle `index`, from the oldest to the newest position.
+    pub fn trail(&self, index: usize) -> impl Iterator<Item = Vec2> + '_ {
+        let len = self.trail_length;
+        let block = &self.trails[index * len..(index + 1) * len];
+        let head = self.trail_head;
+        (1..=len).map(move |k| block[(head + k) % len])
+    }
+
+    /// Draw the particles as dots, and their trails if the trails are turned on.
+    ///
+    /// All the dots are drawn as one `ShapeBatch`, and all the trail segments as another one,
+    /// so there are only two meshes no matter how many particles there are.
+    pub fn draw(&self, draw: &Draw) {
+        let mut dots = ShapeBatch::circles(DOT_RESOLUTION).with_capacity(self.len());
+        let mut segments = ShapeBatch::rects();
+        if self.trail_length > 1 {
+            segments = segments.with_capacity(self.len() * (self.trail_length - 1));
+        }
+        let len = self.trail_length as f32;
+        for (i, particle) in self.iter().enumerate() {
+            let color = self.color_over_life.sample(particle.life);
+            let size = self.size_over_life.sample(particle.life);
+            if self.trail_length > 1 {
+                let trail: Vec<Vec2> = self.trail(i).collect();
+                for (k, pair) in trail.windows(2).enumerate() {
+                    let d = pair[1] - pair[0];
+                    let length = d.length();
+                    //New particles have all their trail points in the same place
+                    if length < f32::EPSILON {
+                        continue;
+                    }
+                    let mut c = color;
+                    c.alpha *= (k + 2) as f32 / len;
+                    segments.push(Instance {
+                        position: (pair[0] + pair[1]) * 0.5,
+                        scale: vec2(length * 0.5, size * 0.25),
+                        rotation: d.y.atan2(d.x),
+                        color: c,
+                    });
+                }
+            }
+            dots.push_circle(particle.position, size * 0.5, color);
+        }
+        segments.draw(draw);
+        dots.draw(draw);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const UP: fn(Vec2, f32) -> Vec2 = |_, _| vec2(0.0, 1.0);
+
+    //Particles that move straight up one unit per update, keeping the last 3 positions.
+    // With `dt` 1.0 the steering is complete, so the velocity is the field's right away.
+    fn system(lifetimes: &[f32]) -> ParticleSystem {
+        let mut particles = ParticleSystem::new(SketchRng::from_seed(1)).trail_length(3);
+        for (i, &lifetime) in lifetimes.iter().enumerate() {
+            particles.spawn(vec2(i as f32 * 10.0, 0.0), Vec2::ZERO, lifetime);
+        }
+        particles
+    }
+
+    fn trail(particles: &ParticleSystem, index: usize) -> Vec<Vec2> {
+        particles.trail(index).collect()
+    }
+
+    #[test]
+    fn dead_particles_are_swapped_with_the_last() {
+        let mut particles = system(&[10.0, 1.5, 10.0]);
+        particles.update(&UP, 1.0, 0.0);
+        assert_eq!(particles.len(), 3);
+        particles.update(&UP, 1.0, 0.0);
+
+        //The second particle died, and the third one took its place, with its trail
+        assert_eq!(particles.positions(), &[vec2(0.0, 2.0), vec2(20.0, 2.0)]);
+        assert_eq!(particles.trails.len(), 2 * 3);
+        assert_eq!(
+            trail(&particles, 0),
+            [vec2(0.0, 0.0), vec2(0.0, 1.0), vec2(0.0, 2.0)]
+        );
+        assert_eq!(
+            trail(&particles, 1),
+            [vec2(20.0, 0.0), vec2(20.0, 1.0), vec2(20.0, 2.0)]
+        );
+    }
+
+    #[test]
+    fn a_dead_particle_swapped_in_is_removed_too() {
+        let mut particles = system(&[10.0, 0.5, 10.0, 0.5]);
+        particles.update(&UP, 1.0, 0.0);
+        assert_eq!(particles.positions(), &[vec2(0.0, 1.0), vec2(20.0, 1.0)]);
+        assert_eq!(particles.trails.len(), 2 * 3);
+        assert_eq!(trail(&particles, 1)[0], vec2(20.0, 0.0));
+        let lives: Vec<f32> = particles.iter().map(|p| p.life).collect();
+        assert_eq!(lives, [0.1, 0.1]);
+    }
+
+    #[test]
+    fn the_last_particle_can_die() {
+        let mut particles = system(&[10.0, 10.0, 0.5]);
+        particles.update(&UP, 1.0, 0.0);
+        assert_eq!(particles.positions(), &[vec2(0.0, 1.0), vec2(10.0, 1.0)]);
+        assert_eq!(particles.trails.len(), 2 * 3);
+        assert_eq!(trail(&particles, 1)[2], vec2(10.0, 1.0));
+    }
+
+    #[test]
+    fn trails_keep_the_newest_positions_in_order() {
+        let mut particles = system(&[100.0]);
+        //A new particle has its spawn position all along the trail
+        assert_eq!(trail(&particles, 0), [Vec2::ZERO; 3]);
+        for step in 1..=5 {
+            particles.update(&UP, 1.0, 0.0);
+            let newest = step as f32;
+            let expected: Vec<Vec2> = (0..3)
+                .map(|k| vec2(0.0, (newest - 2.0 + k as f32).max(0.0)))
+                .collect();
+            assert_eq!(trail(&particles, 0), expected, "after {} steps", step);
+        }
+    }
+
+    #[test]
+    fn trail_length_can_be_changed() {
+        let particles = system(&[1.0, 1.0]).trail_length(5);
+        assert_eq!(particles.trails.len(), 2 * 5);
+        assert_eq!(trail(&particles, 1), [vec2(10.0, 0.0); 5]);
+        let particles = particles.trail_length(0);
+        assert!(particles.trails.is_empty());
+    }
+
+    #[test]
+    fn emitters_carry_fractions_over() {
+        let still = |_: Vec2, _: f32| Vec2::ZERO;
+        let emitter = Emitter::new(Vec2::ZERO, 1.5).lifetime(100.0, 100.0);
+        let mut particles = ParticleSystem::new(SketchRng::from_seed(3)).emitter(emitter);
+        let mut counts = Vec::new();
+        for _ in 0..4 {
+            particles.update(&still, 1.0, 0.0);
+            counts.push(particles.len());
+        }
+        assert_eq!(counts, [1, 3, 4, 6]);
+
+        //A rate below one particle per update still spawns now and then
+        let emitter = Emitter::new(Vec2::ZERO, 15.0).lifetime(100.0, 100.0);
+        let mut particles = ParticleSystem::new(SketchRng::from_seed(3)).emitter(emitter);
+        for _ in 0..60 {
+            particles.update(&still, 1.0 / 60.0, 0.0);
+        }
+        assert_eq!(particles.len(), 15);
+    }
+
+    #[test]
+    fn emitters_stop_at_the_maximum() {
+        let emitter = Emitter::new(Vec2::ZERO, 100.0).lifetime(100.0, 100.0);
+        let mut particles = ParticleSystem::new(SketchRng::from_seed(3))
+            .emitter(emitter)
+            .max_particles(30);
+        particles.update(&|_: Vec2, _: f32| Vec2::ZERO, 1.0, 0.0);
+        assert_eq!(particles.len(), 30);
+    }
+
+    #[test]
+    fn curves_hold_the_ends() {
+        let curve = Curve::new(vec![(0.2, 1.0), (0.5, 3.0), (0.9, 2.0)]);
+        assert_eq!(curve.sample(-1.0), 1.0);
+        assert_eq!(curve.sample(0.0), 1.0);
+        assert_eq!(curve.sample(0.2), 1.0);
+        assert_eq!(curve.sample(0.9), 2.0);
+        assert_eq!(curve.sample(1.0), 2.0);
+        assert_eq!(curve.sample(5.0), 2.0);
+    }
+
+    #[test]
+    fn curves_blend_between_keys() {
+        let curve = Curve::new(vec![(0.2, 1.0), (0.5, 3.0), (0.9, 2.0)]);
+        assert!((curve.sample(0.35) - 2.0).abs() < 1e-6);
+        assert!((curve.sample(0.5) - 3.0).abs() < 1e-6);
+        assert!((curve.sample(0.8) - 2.25).abs() < 1e-6);
+
+        let color = Curve::linear(lin_srgba(0.0, 0.0, 0.0, 1.0), lin_srgba(1.0, 0.5, 0.0, 0.0));
+        assert_eq!(color.sample(0.5), lin_srgba(0.5, 0.25, 0.0, 0.5));
+
+        //Two keys at the same position give a step
+        let step = Curve::new(vec![(0.0, 0.0), (0.5, 0.0), (0.5, 1.0), (1.0, 1.0)]);
+        assert_eq!(step.sample(0.49), 0.0);
+        assert_eq!(step.sample(0.51), 1.0);
+    }
+
+    #[test]
+    fn curves_without_keys_are_constant() {
+        let curve: Curve<f32> = Curve::new(Vec::new());
+        assert_eq!(curve.sample(0.0), 0.0);
+        assert_eq!(curve.sample(1.0), 0.0);
+        assert_eq!(Curve::constant(vec2(1.0, 2.0)).sample(0.7), vec2(1.0, 2.0));
+    }
+}
@@ -0,0 +1,389 @@
+//! How long frames take, shown on top of the sketch and saved as CSV.
+//!
+//! Keep a `PerfHud` in the model and:
+//!
+//! * call `hud.frame(app, &update)` at the start of `update`,
+//! * time `update` and `view` with `let _timer = hud.time_update();` and
+//!   `let _timer = hud.time_view();`, which measure until the end of the function,
+//! * call `hud.draw(&draw, app.window_rect())` at the end of `view`,
+//! * count the primitives after the view timer with
+//!   `hud.count_recording(&record::record(|draw| ...))`, and
+//! * call `hud.key_pressed(key)` from `key_pressed` to show and hide it with `H`.
+//!
+//! `view` only gets `&Model`, so the hud keeps its numbers in a `RefCell` to be able to update
+//! them from there.
+
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use nannou::prelude::*;
+
+use crate::record::Recording;
+
+/// The key that shows and hides the hud.
+pub const HUD_KEY: Key = Key::H;
+
+/// The key that saves the CSV file, when one is given with `--perf-csv`.
+pub const CSV_KEY: Key = Key::C;
+
+//How many frames are shown in the histogram
+const HISTORY: usize = 120;
+
+/// The numbers for one frame.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct FrameStats {
+    pub frame: u64,
+    pub time: f32,
+    /// Time since the last frame, in milliseconds.
+    pub frame_ms: f32,
+    pub update_ms: f32,
+    pub view_ms: f32,
+    pub primitives: usize,
+    /// Vertices of the triangles the primitives are tessellated into.
+    pub vertices: usize,
+}
+
+#[derive(Clone, Debug, Default)]
+struct Stats {
+    //Whether `current` is a frame, i.e. `frame` was called
+    started: bool,
+    current: FrameStats,
+    history: VecDeque<FrameStats>,
+    //Every frame since the start, when saving to CSV
+    rows: Option<Vec<FrameStats>>,
+}
+
+/// Frame timing, drawn as an overlay.
+#[derive(Debug)]
+pub struct PerfHud {
+    pub visible: bool,
+    csv_path: Option<PathBuf>,
+    stats: RefCell<Stats>,
+}
+
+impl Default for PerfHud {
+    fn default() -> Self {
+        PerfHud::new()
+    }
+}
+
+impl PerfHud {
+    pub fn new() -> Self {
+        PerfHud {
+            visible: true,
+            csv_path: None,
+            stats: RefCell::new(Stats::default()),
+        }
+    }
+
+    /// A hud that keeps every frame and saves them to the file given with
+    /// `--perf-csv <path>`, if there is one.
+    pub fn from_args() -> Self {
+        let args: Vec<String> = std::env::args().collect();
+        let path = args
+            .iter()
+            .position(|arg| arg == "--perf-csv")
+            .and_then(|i| args.get(i + 1))
+            .map(PathBuf::from);
+        match path {
+            Some(path) => PerfHud::new().csv(path),
+            None => PerfHud::new(),
+        }
+    }
+
+    /// Keep every frame, to be saved to `path` with `save_csv`, the `C` key, or when the hud is
+    /// dropped.
+    pub fn csv(mut self, path: impl Into<PathBuf>) -> Self {
+        self.csv_path = Some(path.into());
+        self.stats.get_mut().rows = Some(Vec::new());
+        self
+    }
+
+    pub fn hidden(mut self) -> Self {
+        self.visible = false;
+        self
+    }
+
+    /// Start a new frame. Call this first thing in `update`.
+    pub fn frame(&self, app: &App, update: &Update) {
+        let frame_ms = update.since_last.as_secs_f32() * 1000.0;
+        self.start_frame(app.elapsed_frames(), app.time, frame_ms);
+    }
+
+    fn start_frame(&self, frame: u64, time: f32, frame_ms: f32) {
+        let mut stats = self.stats.borrow_mut();
+        let finished = stats.current;
+        if stats.started {
+            stats.history.push_back(finished);
+            while stats.history.len() > HISTORY {
+                stats.history.pop_front();
+            }
+            if let Some(rows) = stats.rows.as_mut() {
+                rows.push(finished);
+            }
+        }
+        stats.started = true;
+        stats.current = FrameStats {
+            frame,
+            time,
+            frame_ms,
+            ..FrameStats::default()
+        };
+    }
+
+    /// Measures until the returned timer is dropped, and adds it to the update time.
+    pub fn time_update(&self) -> Timer<'_> {
+        Timer {
+            hud: self,
+            start: Instant::now(),
+            view: false,
+        }
+    }
+
+    /// Measures until the returned timer is dropped, and adds it to the view time.
+    pub fn time_view(&self) -> Timer<'_> {
+        Timer {
+            hud: self,
+            start: Instant::now(),
+            view: true,
+        }
+    }
+
+    /// Set how many primitives and vertices were drawn this frame.
+    pub fn count(&self, primitives: usize, vertices: usize) {
+        let mut stats = self.stats.borrow_mut();
+        stats.current.primitives = primitives;
+        stats.current.vertices = vertices;
+    }
+
+    /// Count the primitives in a `record::Recording`, and the vertices nannou tessellates them
+    /// into.
+    pub fn count_recording(&self, recording: &Recording) {
+        self.count(recording.primitives.len(), recording.vertices());
+    }
+
+    /// The frames in the histogram, oldest first.
+    pub fn history(&self) -> Vec<FrameStats> {
+        self.stats.borrow().history.iter().copied().collect()
+    }
+
+    /// Frames per second, averaged over the histogram.
+    pub fn fps(&self) -> f32 {
+        let stats = self.stats.borrow();
+        let total: f32 = stats.history.iter().map(|s| s.frame_ms).sum();
+        if total > 0.0 {
+            stats.history.len() as f32 * 1000.0 / total
+        } else {
+            0.0
+        }
+    }
+
+    /// Call this from `key_pressed`. `H` shows and hides the hud, and `C` saves the CSV file.
+    pub fn key_pressed(&mut self, key: Key) {
+        if key == HUD_KEY {
+            self.visible = !self.visible;
+        }
+        if key == CSV_KEY && self.csv_path.is_some() {
+            if let Err(e) = self.save_csv() {
+                eprintln!("Could not save the CSV file: {}", e);
+            }
+        }
+    }
+
+    /// Save all the frames so far to the CSV file, if there is one.
+    pub fn save_csv(&self) -> io::Result<()> {
+        let path = match self.csv_path.as_ref() {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+        let stats = self.stats.borrow();
+        write_csv(path, stats.rows.as_deref().unwrap_or(&[]))?;
+        println!("Saved frame timing to {}", path.display());
+        Ok(())
+    }
+
+    /// Draw the overlay in the top left corner of `win`.
+    pub fn draw(&self, draw: &Draw, win: Rect) {
+        if !self.visible {
+            return;
+        }
+        let history = self.history();
+        let last = history.last().copied().unwrap_or_default();
+        let panel = Rect::from_w_h(260.0, 150.0)
+            .top_left_of(win)
+            .shift(vec2(10.0, -10.0));
+        draw.rect()
+            .xy(panel.xy())
+            .wh(panel.wh())
+            .color(rgba(0.0, 0.0, 0.0, 0.7));
+
+        let text = format!(
+            "{:.1} fps   frame {}\nframe {:.2} ms\nupdate {:.2} ms   view {:.2} ms\n{} primitives   {} vertices",
+            self.fps(),
+            last.frame,
+            last.frame_ms,
+            last.update_ms,
+            last.view_ms,
+            last.primitives,
+            last.vertices,
+        );
+        let text_rect = Rect::from_w_h(panel.w() - 16.0, 70.0).mid_top_of(panel).shift_y(-8.0);
+        draw.text(&text)
+            .xy(text_rect.xy())
+            .wh(text_rect.wh())
+            .left_justify()
+            .align_text_top()
+            .font_size(12)
+            .color(WHITE);
+
+        //One bar per frame. The line marks 60 fps, i.e. 16.7 ms
+        let graph = Rect::from_w_h(panel.w() - 16.0, 56.0).mid_bottom_of(panel).shift_y(8.0);
+        let max_ms = history
+            .iter()
+            .map(|s| s.frame_ms)
+            .fold(33.3_f32, f32::max);
+        let bar_w = graph.w() / HISTORY as f32;
+        for (i, s) in history.iter().enumerate() {
+            let h = (s.frame_ms / max_ms).min(1.0) * graph.h();
+            let color = if s.frame_ms > 1000.0 / 55.0 { ORANGE } else { LIMEGREEN };
+            draw.rect()
+                .x_y(graph.left() + bar_w * (i as f32 + 0.5), graph.bottom() + h * 0.5)
+                .w_h(bar_w, h)
+                .color(color);
+        }
+        let target_y = graph.bottom() + (1000.0 / 60.0) / max_ms * graph.h();
+        draw.line()
+            .start(vec2(graph.left(), target_y))
+            .end(vec2(graph.right(), target_y))
+            .weight(1.0)
+            .color(WHITE);
+    }
+}
+
+impl Drop for PerfHud {
+    fn drop(&mut self) {
+        if self.csv_path.is_some() {
+            let _ = self.save_csv();
+        }
+    }
+}
+
+/// Measures from when it is made until it is dropped. See `PerfHud::time_update`.
+pub struct Timer<'a> {
+    hud: &'a PerfHud,
+    start: Instant,
+    view: bool,
+}
+
+impl Drop for Timer<'_> {
+    fn drop(&mut self) {
+        let ms = self.start.elapsed().as_secs_f32() * 1000.0;
+        let mut stats = self.hud.stats.borrow_mut();
+        if self.view {
+            stats.current.view_ms += ms;
+        } else {
+            stats.current.update_ms += ms;
+        }
+    }
+}
+
+/// Write frames as CSV, with a header row.
+pub fn write_csv(path: &Path, frames: &[FrameStats]) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut file = io::BufWriter::new(fs::File::create(path)?);
+    writeln!(file, "frame,time,frame_ms,update_ms,view_ms,primitives,vertices")?;
+    for s in frames {
+        writeln!(
+            file,
+            "{},{},{},{},{},{},{}",
+            s.frame, s.time, s.frame_ms, s.update_ms, s.view_ms, s.primitives, s.vertices
+        )?;
+    }
+    file.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::record::record;
+
+    fn current(hud: &PerfHud) -> FrameStats {
+        hud.stats.borrow().current
+    }
+
+    #[test]
+    fn counts_the_recorded_primitives() {
+        let recording = record(|draw| {
+            draw.ellipse().radius(20.0);
+            draw.ellipse().radius(20.0).no_fill().stroke_weight(2.0);
+            //Two triangles
+            draw.rect().w_h(10.0, 20.0);
+            draw.line().start(vec2(0.0, 0.0)).end(vec2(10.0, 0.0));
+            draw.polyline()
+                .points([vec2(0.0, 0.0), vec2(10.0, 0.0), vec2(10.0, 10.0)]);
+        });
+        let hud = PerfHud::new();
+        hud.count_recording(&recording);
+        let stats = current(&hud);
+        assert_eq!(stats.primitives, 5);
+        assert_eq!(stats.vertices, recording.vertices());
+        assert!(stats.vertices > 4 + 4 + 4);
+    }
+
+    #[test]
+    fn every_frame_is_kept() {
+        let mut hud = PerfHud::new().csv(std::env::temp_dir().join("workshop-perf-frames.csv"));
+        //The first frame is frame 0, and nothing came before it to take time
+        hud.start_frame(0, 0.0, 0.0);
+        hud.count(3, 12);
+        hud.start_frame(1, 0.016, 16.0);
+        hud.start_frame(2, 0.032, 16.0);
+        let history = hud.history();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].frame, 0);
+        assert_eq!(history[0].primitives, 3);
+        assert_eq!(history[1].frame, 1);
+        assert_eq!(hud.stats.borrow().rows.as_ref().unwrap().len(), 2);
+        //Don't save the file when the hud is dropped
+        hud.csv_path = None;
+    }
+
+    #[test]
+    fn history_is_limited() {
+        let hud = PerfHud::new();
+        for frame in 0..=HISTORY as u64 + 10 {
+            hud.start_frame(frame, 0.0, 20.0);
+        }
+        let history = hud.history();
+        assert_eq!(history.len(), HISTORY);
+        assert_eq!(history[0].frame, 10);
+        assert!((hud.fps() - 50.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn csv_has_a_row_per_frame() {
+        let path = std::env::temp_dir().join("workshop-perf-test.csv");
+        let frames = [
+            FrameStats {
+                frame: 1,
+                primitives: 24,
+                vertices: 960,
+                ..FrameStats::default()
+            },
+            FrameStats::default(),
+        ];
+        write_csv(&path, &frames).unwrap();
+        let text = fs::read_to_string(&path).unwrap();
+        let _ = fs::remove_file(&path);
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].ends_with("primitives,vertices"));
+        assert!(lines[1].starts_with("1,") && lines[1].ends_with(",24,960"));
+    }
+}
@@ -0,0 +1,873 @@
+//! Effects applied to the whole picture after the sketch is drawn: bloom, chromatic aberration,
+//! vignette, film grain, colour grading with a LUT and pixelation.
+//!
+//! The sketch is drawn into an `offscreen::Offscreen`, and a `PostRenderer` runs a
+//! `PostChain` of effects on the texture, one shader pass at a time, on the GPU.
+//! Every effect is also written out on the CPU in `Effect::apply`, working on `Pixels`.
+//! That is far too slow to run every frame, but shows what each effect does step by step, and
+//! can be checked without a window.
+//!
+//! The shaders are in `src/shaders/postfx_vert.wgsl` and `src/shaders/postfx_frag.wgsl`.
+
+use std::borrow::Cow;
+use std::fs;
+use std::io;
+use std::num::NonZeroU32;
+use std::path::Path;
+
+use nannou::image::{Rgba, RgbaImage};
+use nannou::prelude::*;
+use nannou::wgpu;
+
+use crate::offscreen::TEXTURE_FORMAT;
+
+//The modes of the fragment shader. 0 copies the picture as it is.
+const BLOOM_H: u32 = 1;
+const BLOOM_V: u32 = 2;
+const ABERRATION: u32 = 3;
+const VIGNETTE: u32 = 4;
+const GRAIN: u32 = 5;
+const LUT: u32 = 6;
+const PIXELATE: u32 = 7;
+
+//The bloom blur reads this many pixels on each side of the middle one
+const BLUR_TAPS: i32 = 8;
+
+/// One effect and its parameters.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Effect {
+    /// A glow around everything brighter than `threshold`, spread over `radius` pixels.
+    /// Thin white lines, like in `p2_b`, glow with a threshold of about `0.5`.
+    Bloom {
+        threshold: f32,
+        intensity: f32,
+        radius: f32,
+    },
+    /// Red and blue are pushed apart towards the edges, like in a cheap lens.
+    /// `amount` is how many pixels apart they are in the corners.
+    ChromaticAberration { amount: f32 },
+    /// Darker towards the corners. `radius` is where the darkening starts, from `0.0` in the
+    /// middle to `1.0` in the corners, and `softness` how far it takes to get to `strength`.
+    Vignette {
+        strength: f32,
+        radius: f32,
+        softness: f32,
+    },
+    /// Random noise, different every frame.
+    Grain { amount: f32 },
+    /// Colour grading with a 3D lookup table, mixed with the original by `amount`.
+    Lut { lut: Lut, amount: f32 },
+    /// Big square pixels, `size` pixels wide.
+    Pixelate { size: f32 },
+}
+
+impl Effect {
+    pub fn bloom() -> Self {
+        Effect::Bloom {
+            threshold: 0.5,
+            intensity: 2.0,
+            radius: 24.0,
+        }
+    }
+
+    pub fn chromatic_aberration() -> Self {
+        Effect::ChromaticAberration { amount: 6.0 }
+    }
+
+    pub fn vignette() -> Self {
+        Effect::Vignette {
+            strength: 0.8,
+            radius: 0.4,
+            softness: 0.6,
+        }
+    }
+
+    pub fn grain() -> Self {
+        Effect::Grain { amount: 0.08 }
+    }
+
+    pub fn lut(lut: Lut) -> Self {
+        Effect::Lut { lut, amount: 1.0 }
+    }
+
+    pub fn pixelate() -> Self {
+        Effect::Pixelate { size: 8.0 }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Effect::Bloom { .. } => "bloom",
+            Effect::ChromaticAberration { .. } => "chromatic aberration",
+            Effect::Vignette { .. } => "vignette",
+            Effect::Grain { .. } => "grain",
+            Effect::Lut { .. } => "lut",
+            Effect::Pixelate { .. } => "pixelate",
+        }
+    }
+
+    /// Apply the effect on the CPU. `seed` changes the grain, e.g. the frame number.
+    pub fn apply(&self, pixels: &Pixels, seed: u32) -> Pixels {
+        let size = pixels.size();
+        match *self {
+            Effect::Bloom {
+                threshold,
+                intensity,
+                radius,
+            } => {
+                let glow = blur(pixels, vec2(1.0, 0.0), threshold, radius);
+                let glow = blur(&glow, vec2(0.0, 1.0), 0.0, radius);
+                pixels.map(|x, y, [r, g, b, a]| {
+                    let [gr, gg, gb, _] = glow.get(x as i64, y as i64);
+                    [r + gr * intensity, g + gg * intensity, b + gb * intensity, a]
+                })
+            }
+            Effect::ChromaticAberration { amount } => pixels.map(|x, y, [_, g, _, a]| {
+                let uv = pixels.uv(x, y);
+                let offset = (uv * 2.0 - 1.0) * amount / size;
+                let r = pixels.sample(uv + offset)[0];
+                let b = pixels.sample(uv - offset)[2];
+                [r, g, b, a]
+            }),
+            Effect::Vignette {
+                strength,
+                radius,
+                softness,
+            } => pixels.map(|x, y, [r, g, b, a]| {
+                let d = (pixels.uv(x, y) * 2.0 - 1.0).length() / 2.0f32.sqrt();
+                let t = ((d - radius) / softness.max(0.0001)).clamp(0.0, 1.0);
+                let v = 1.0 - strength * t * t * (3.0 - 2.0 * t);
+                [r * v, g * v, b * v, a]
+            }),
+            Effect::Grain { amount } => pixels.map(|x, y, [r, g, b, a]| {
+                let n = (hash(x, y, seed) - 0.5) * amount;
+                [(r + n).max(0.0), (g + n).max(0.0), (b + n).max(0.0), a]
+            }),
+            Effect::Lut { ref lut, amount } => pixels.map(|_, _, [r, g, b, a]| {
+                let srgb = [r, g, b].map(|c| linear_to_srgb(c.clamp(0.0, 1.0)));
+                let graded = lut.sample(srgb);
+                let [r, g, b] =
+                    [0, 1, 2].map(|i| srgb_to_linear(mix(srgb[i], graded[i], amount)));
+                [r, g, b, a]
+            }),
+            Effect::Pixelate { size: block } => pixels.map(|x, y, _| {
+                let block = block.max(1.0);
+                let cell = (pixels.uv(x, y) * size / block).floor();
+                pixels.sample((cell + 0.5) * block / size)
+            }),
+        }
+    }
+
+    //The shader passes of the effect, as a mode and four parameters each
+    fn passes(&self) -> Vec<(u32, [f32; 4])> {
+        match *self {
+            Effect::Bloom {
+                threshold,
+                intensity,
+                radius,
+            } => vec![
+                (BLOOM_H, [threshold, intensity, radius, 0.0]),
+                (BLOOM_V, [threshold, intensity, radius, 0.0]),
+            ],
+            Effect::ChromaticAberration { amount } => vec![(ABERRATION, [amount, 0.0, 0.0, 0.0])],
+            Effect::Vignette {
+                strength,
+                radius,
+                softness,
+            } => vec![(VIGNETTE, [strength, radius, softness, 0.0])],
+            Effect::Grain { amount } => vec![(GRAIN, [amount, 0.0, 0.0, 0.0])],
+            Effect::Lut { ref lut, amount } => vec![(LUT, [amount, lut.size as f32, 0.0, 0.0])],
+            Effect::Pixelate { size } => vec![(PIXELATE, [size, 0.0, 0.0, 0.0])],
+        }
+    }
+}
+
+//A gaussian blur along `dir` of what is brighter than `threshold`, like `blur` in the shader
+fn blur(pixels: &Pixels, dir: Vec2, threshold: f32, radius: f32) -> Pixels {
+    let size = pixels.size();
+    pixels.map(|x, y, _| {
+        let uv = pixels.uv(x, y);
+        let mut sum = [0.0; 3];
+        let mut weights = 0.0;
+        for i in -BLUR_TAPS..=BLUR_TAPS {
+            let t = i as f32 / BLUR_TAPS as f32;
+            let w = (-4.0 * t * t).exp();
+            let c = pixels.sample(uv + dir * t * radius / size);
+            for (sum, c) in sum.iter_mut().zip(c) {
+                *sum += (c - threshold).max(0.0) * w;
+            }
+            weights += w;
+        }
+        [sum[0] / weights, sum[1] / weights, sum[2] / weights, 1.0]
+    })
+}
+
+//The same integer hash as in the shader, so the grain is the same on the CPU and the GPU
+fn hash(x: u32, y: u32, z: u32) -> f32 {
+    let mut h = x
+        .wrapping_mul(374761393)
+        .wrapping_add(y.wrapping_mul(668265263))
+        .wrapping_add(z.wrapping_mul(2246822519));
+    h = (h ^ (h >> 13)).wrapping_mul(1274126177);
+    h ^= h >> 16;
+    (h & 0xFF_FFFF) as f32 / 16777216.0
+}
+
+fn linear_to_srgb(c: f32) -> f32 {
+    if c <= 0.0031308 {
+        c * 12.92
+    } else {
+        1.055 * c.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+fn srgb_to_linear(c: f32) -> f32 {
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn mix(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * t
+}
+
+/// A 3D colour lookup table, as used for colour grading in video and photo editors.
+///
+/// Each sRGB colour is looked up in a cube of `size` x `size` x `size` colours, with red along
+/// the first axis, green along the second and blue along the third.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Lut {
+    pub size: usize,
+    /// Red changes fastest, then green, then blue, like in a `.cube` file.
+    pub colors: Vec<[f32; 3]>,
+}
+
+impl Lut {
+    /// A table that changes nothing.
+    pub fn identity(size: usize) -> Self {
+        Lut::from_fn(size, |rgb| rgb)
+    }
+
+    /// A table where each colour is `grade(colour)`, e.g. to make a grading in code.
+    pub fn from_fn<F>(size: usize, grade: F) -> Self
+    where
+        F: Fn([f32; 3]) -> [f32; 3],
+    {
+        let size = size.max(2);
+        let step = 1.0 / (size - 1) as f32;
+        let mut colors = Vec::with_capacity(size * size * size);
+        for b in 0..size {
+            for g in 0..size {
+                for r in 0..size {
+                    colors.push(grade([r as f32 * step, g as f32 * step, b as f32 * step]));
+                }
+            }
+        }
+        Lut { size, colors }
+    }
+
+    /// Read a `.cube` file.
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        Lut::parse(&fs::read_to_string(path)?)
+    }
+
+    /// Parse the text of a `.cube` file. Only 3D tables with the default domain of `0.0` to
+    /// `1.0` are supported.
+    pub fn parse(text: &str) -> io::Result<Self> {
+        let invalid = |message: String| io::Error::new(io::ErrorKind::InvalidData, message);
+        let mut size: Option<usize> = None;
+        let mut colors = Vec::new();
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut words = line.split_whitespace();
+            let first = words.next().unwrap_or_default();
+            match first {
+                "LUT_3D_SIZE" => {
+                    let n = words.next().and_then(|n| n.parse().ok());
+                    let n = n.ok_or_else(|| invalid(format!("bad size on line {}", number + 1)))?;
+                    size = Some(n);
+                }
+                "LUT_1D_SIZE" => return Err(invalid("1D tables are not supported".to_string())),
+                "TITLE" | "DOMAIN_MIN" | "DOMAIN_MAX" | "LUT_3D_INPUT_RANGE" => {}
+                _ => {
+                    let values: Vec<f32> = line
+                        .split_whitespace()
+                        .filter_map(|v| v.parse().ok())
+                        .collect();
+                    match values[..] {
+                        [r, g, b] => colors.push([r, g, b]),
+                        _ => return Err(invalid(format!("bad colour on line {}", number + 1))),
+                    }
+                }
+            }
+        }
+        let size = size.ok_or_else(|| invalid("no LUT_3D_SIZE".to_string()))?;
+        if size < 2 || colors.len() != size * size * size {
+            return Err(invalid(format!(
+                "expected {} colours, found {}",
+                size * size * size,
+                colors.len()
+            )));
+        }
+        Ok(Lut { size, colors })
+    }
+
+    fn get(&self, r: usize, g: usize, b: usize) -> [f32; 3] {
+        self.colors[r + g * self.size + b * self.size * self.size]
+    }
+
+    /// Look up an sRGB colour, blending between the eight nearest colours in the table.
+    pub fn sample(&self, rgb: [f32; 3]) -> [f32; 3] {
+        let max = (self.size - 1) as f32;
+        let p = rgb.map(|c| c.clamp(0.0, 1.0) * max);
+        let i0 = p.map(|c| (c.floor() as usize).min(self.size - 2));
+        let t = [0, 1, 2].map(|k| p[k] - i0[k] as f32);
+        let mut out = [0.0; 3];
+        for corner in 0..8 {
+            let d = [corner & 1, (corner >> 1) & 1, (corner >> 2) & 1];
+            let w = (0..3)
+                .map(|k| if d[k] == 1 { t[k] } else { 1.0 - t[k] })
+                .product::<f32>();
+            let c = self.get(i0[0] + d[0], i0[1] + d[1], i0[2] + d[2]);
+            for (out, c) in out.iter_mut().zip(c) {
+                *out += c * w;
+            }
+        }
+        out
+    }
+}
+
+/// Linear colours on the CPU, for `Effect::apply`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Pixels {
+    pub width: u32,
+    pub height: u32,
+    /// Row by row from the top left, as linear red, green, blue and alpha.
+    pub data: Vec<[f32; 4]>,
+}
+
+impl Pixels {
+    pub fn new(width: u32, height: u32) -> Self {
+        Pixels {
+            width,
+            height,
+            data: vec![[0.0, 0.0, 0.0, 1.0]; (width * height) as usize],
+        }
+    }
+
+    pub fn from_image(image: &RgbaImage) -> Self {
+        Pixels {
+            width: image.width(),
+            height: image.height(),
+            data: image
+                .pixels()
+                .map(|Rgba([r, g, b, a])| {
+                    let [r, g, b] = [r, g, b].map(|c| srgb_to_linear(*c as f32 / 255.0));
+                    [r, g, b, *a as f32 / 255.0]
+                })
+                .collect(),
+        }
+    }
+
+    pub fn to_image(&self) -> RgbaImage {
+        RgbaImage::from_fn(self.width, self.height, |x, y| {
+            let [r, g, b, a] = self.get(x as i64, y as i64);
+            let [r, g, b] =
+                [r, g, b].map(|c| (linear_to_srgb(c.clamp(0.0, 1.0)) * 255.0).round() as u8);
+            Rgba([r, g, b, (a.clamp(0.0, 1.0) * 255.0).round() as u8])
+        })
+    }
+
+    pub fn size(&self) -> Vec2 {
+        vec2(self.width as f32, self.height as f32)
+    }
+
+    /// The pixel, with coordinates outside the image clamped to the edge.
+    pub fn get(&self, x: i64, y: i64) -> [f32; 4] {
+        let x = x.clamp(0, self.width as i64 - 1) as u32;
+        let y = y.clamp(0, self.height as i64 - 1) as u32;
+        self.data[(y * self.width + x) as usize]
+    }
+
+    /// The texture coordinate of the middle of a pixel.
+    pub fn uv(&self, x: u32, y: u32) -> Vec2 {
+        (vec2(x as f32, y as f32) + 0.5) / self.size()
+    }
+
+    /// Blend the four pixels nearest to a texture coordinate, like the GPU does.
+    pub fn sample(&self, uv: Vec2) -> [f32; 4] {
+        let p = uv * self.size() - 0.5;
+        let (x, y) = (p.x.floor(), p.y.floor());
+        let (tx, ty) = (p.x - x, p.y - y);
+        let (x, y) = (x as i64, y as i64);
+        let [a, b, c, d] =
+            [(x, y), (x + 1, y), (x, y + 1), (x + 1, y + 1)].map(|(x, y)| self.get(x, y));
+        [0, 1, 2, 3].map(|i| mix(mix(a[i], b[i], tx), mix(c[i], d[i], tx), ty))
+    }
+
+    //A new image with `f(x, y, pixel)` for each pixel
+    fn map<F>(&self, f: F) -> Pixels
+    where
+        F: Fn(u32, u32, [f32; 4]) -> [f32; 4],
+    {
+        let mut data = Vec::with_capacity(self.data.len());
+        for y in 0..self.height {
+            for x in 0..self.width {
+                data.push(f(x, y, self.data[(y * self.width + x) as usize]));
+            }
+        }
+        Pixels {
+            width: self.width,
+            height: self.height,
+            data,
+        }
+    }
+}
+
+/// The effects to apply, in order.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PostChain {
+    pub effects: Vec<(Effect, bool)>,
+}
+
+impl PostChain {
+    pub fn new() -> Self {
+        PostChain::default()
+    }
+
+    /// Add an effect after the others.
+    pub fn with(mut self, effect: Effect) -> Self {
+        self.effects.push((effect, true));
+        self
+    }
+
+    /// Turn the effect at `index` on or off.
+    pub fn toggle(&mut self, index: usize) {
+        if let Some((_, on)) = self.effects.get_mut(index) {
+            *on = !*on;
+        }
+    }
+
+    /// The effects that are on.
+    pub fn enabled(&self) -> impl Iterator<Item = &Effect> {
+        self.effects.iter().filter(|(_, on)| *on).map(|(effect, _)| effect)
+    }
+
+    /// Apply all the effects that are on, on the CPU.
+    pub fn apply(&self, pixels: &Pixels, seed: u32) -> Pixels {
+        self.enabled()
+            .fold(pixels.clone(), |pixels, effect| effect.apply(&pixels, seed))
+    }
+}
+
+//The uniforms of the fragment shader
+#[repr(C)]
+#[derive(Copy, Clone)]
+struct Uniforms {
+    params: [f32; 4],
+    size: [f32; 2],
+    seed: u32,
+    mode: u32,
+}
+
+/// Runs a `PostChain` on the GPU.
+pub struct PostRenderer {
+    pipeline: wgpu::RenderPipeline,
+    bind_group_layout: wgpu::BindGroupLayout,
+    uniforms: wgpu::Buffer,
+    sampler: wgpu::Sampler,
+    //Two textures the passes take turns drawing into, and one for the bloom blur
+    targets: Vec<wgpu::Texture>,
+    //A LUT for each effect, uploaded when it changes
+    luts: Vec<Option<(Lut, wgpu::TextureViewHandle)>>,
+    identity_lut: wgpu::TextureViewHandle,
+    //The target with the result of the last `render`, or `None` if there were no effects
+    output: Option<usize>,
+}
+
+impl PostRenderer {
+    pub fn new(window: &Window) -> Self {
+        let device = window.device();
+        let vs = shader(device, "postfx vertex", include_str!("shaders/postfx_vert.wgsl"));
+        let fs = shader(device, "postfx fragment", include_str!("shaders/postfx_frag.wgsl"));
+
+        let filterable = wgpu::TextureSampleType::Float { filterable: true };
+        let bind_group_layout = wgpu::BindGroupLayoutBuilder::new()
+            .uniform_buffer(wgpu::ShaderStages::FRAGMENT, false)
+            .texture(
+                wgpu::ShaderStages::FRAGMENT,
+                false,
+                wgpu::TextureViewDimension::D2,
+                filterable,
+            )
+            .texture(
+                wgpu::ShaderStages::FRAGMENT,
+                false,
+                wgpu::TextureViewDimension::D2,
+                filterable,
+            )
+            .texture(
+                wgpu::ShaderStages::FRAGMENT,
+                false,
+                wgpu::TextureViewDimension::D3,
+                filterable,
+            )
+            .sampler(wgpu::ShaderStages::FRAGMENT, true)
+            .build(device);
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("postfx"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = wgpu::RenderPipelineBuilder::from_layout(&pipeline_layout, &vs)
+            .fragment_shader(&fs)
+            .color_format(TEXTURE_FORMAT)
+            .color_blend(wgpu::BlendComponent::REPLACE)
+            .alpha_blend(wgpu::BlendComponent::REPLACE)
+            .sample_count(1)
+            .build(device);
+
+        let uniforms = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("postfx uniforms"),
+            size: std::mem::size_of::<Uniforms>() as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let sampler = wgpu::SamplerBuilder::new()
+            .address_mode(wgpu::AddressMode::ClampToEdge)
+            .build(device);
+        let identity_lut = upload_lut(window, &Lut::identity(2));
+
+        PostRenderer {
+            pipeline,
+            bind_group_layout,
+            uniforms,
+            sampler,
+            targets: Vec::new(),
+            luts: Vec::new(),
+            identity_lut,
+            output: None,
+        }
+    }
+
+    /// Run the effects that are on in `chain` on `input`. Call this in `update`, after drawing
+    /// into `input`, and draw `output(input)` in `view` with `draw.texture()`.
+    ///
+    /// `seed` changes the grain, e.g. the frame number.
+    pub fn render(&mut self, window: &Window, chain: &PostChain, input: &wgpu::Texture, seed: u32) {
+        self.resize(window, input.size());
+        let effects: Vec<&Effect> = chain.enabled().collect();
+        self.luts.resize_with(effects.len(), || None);
+        for (i, effect) in effects.iter().enumerate() {
+            if let Effect::Lut { lut, .. } = effect {
+                let changed = !matches!(&self.luts[i], Some((uploaded, _)) if uploaded == lut);
+                if changed {
+                    self.luts[i] = Some((lut.clone(), upload_lut(window, lut)));
+                }
+            }
+        }
+
+        //`None` is the input, otherwise the index of a target
+        let mut current: Option<usize> = None;
+        for (i, effect) in effects.iter().enumerate() {
+            for (mode, params) in effect.passes() {
+                //The first bloom pass blurs into the third target. The second one reads the
+                // blur, and adds it to the picture from before the blur.
+                let (src, out) = match mode {
+                    BLOOM_H => (current, 2),
+                    BLOOM_V => (Some(2), next_target(current)),
+                    _ => (current, next_target(current)),
+                };
+                let aux = current;
+                let lut = match &self.luts[i] {
+                    Some((_, view)) => view,
+                    None => &self.identity_lut,
+                };
+                let texture = |t: Option<usize>| t.map_or(input, |t| &self.targets[t]);
+                let uniforms = Uniforms {
+                    params,
+                    size: texture(src).size().map(|s| s as f32),
+                    seed,
+                    mode,
+                };
+                self.pass(window, texture(src), texture(aux), lut, &self.targets[out], uniforms);
+                if mode != BLOOM_H {
+                    current = Some(out);
+                }
+            }
+        }
+        self.output = current;
+    }
+
+    /// The result of the last `render`, which is `input` itself when no effects are on.
+    pub fn output<'a>(&'a self, input: &'a wgpu::Texture) -> &'a wgpu::Texture {
+        match self.output {
+            Some(t) => &self.targets[t],
+            None => input,
+        }
+    }
+
+    //Make the targets the same size as the input
+    fn resize(&mut self, window: &Window, size: [u32; 2]) {
+        if self.targets.first().map(|t| t.size()) == Some(size) {
+            return;
+        }
+        self.targets = (0..3)
+            .map(|_| {
+                wgpu::TextureBuilder::new()
+                    .size(size)
+                    .usage(
+                        wgpu::TextureUsages::RENDER_ATTACHMENT
+                            | wgpu::TextureUsages::TEXTURE_BINDING,
+                    )
+                    .sample_count(1)
+                    .format(TEXTURE_FORMAT)
+                    .build(window.device())
+            })
+            .collect();
+    }
+
+    fn pass(
+        &self,
+        window: &Window,
+        src: &wgpu::Texture,
+        aux: &wgpu::Texture,
+        lut: &wgpu::TextureViewHandle,
+        target: &wgpu::Texture,
+        uniforms: Uniforms,
+    ) {
+        let device = window.device();
+        //Each pass is submitted on its own, so it sees its own uniforms
+        window
+            .queue()
+            .write_buffer(&self.uniforms, 0, unsafe { wgpu::bytes::from(&uniforms) });
+        let src_view = src.view().build();
+        let aux_view = aux.view().build();
+        let target_view = target.view().build();
+        let bind_group = wgpu::BindGroupBuilder::new()
+            .buffer::<Uniforms>(&self.uniforms, 0..1)
+            .texture_view(&src_view)
+            .texture_view(&aux_view)
+            .texture_view(lut)
+            .sampler(&self.sampler)
+            .build(device, &self.bind_group_layout);
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("postfx"),
+        });
+        {
+            let mut render_pass = wgpu::RenderPassBuilder::new()
+                .color_attachment(&target_view, |color| color)
+                .begin(&mut encoder);
+            render_pass.set_pipeline(&self.pipeline);
+            render_pass.set_bind_group(0, &bind_group, &[]);
+            render_pass.draw(0..3, 0..1);
+        }
+        window.queue().submit(Some(encoder.finish()));
+    }
+}
+
+//Take turns between the first two targets
+fn next_target(current: Option<usize>) -> usize {
+    match current {
+        Some(0) => 1,
+        _ => 0,
+    }
+}
+
+fn shader(device: &wgpu::Device, label: &str, source: &str) -> wgpu::ShaderModule {
+    device.create_shader_module(&wgpu::ShaderModuleDescriptor {
+        label: Some(label),
+        source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(source)),
+    })
+}
+
+//Copy a LUT into a 3D texture
+fn upload_lut(window: &Window, lut: &Lut) -> wgpu::TextureViewHandle {
+    let n = lut.size as u32;
+    let extent = wgpu::Extent3d {
+        width: n,
+        height: n,
+        depth_or_array_layers: n,
+    };
+    let texture = window.device().create_texture(&wgpu::TextureDescriptor {
+        label: Some("postfx lut"),
+        size: extent,
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D3,
+        format: wgpu::TextureFormat::Rgba8Unorm,
+        usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+    });
+    let data: Vec<u8> = lut
+        .colors
+        .iter()
+        .flat_map(|&[r, g, b]| {
+            let [r, g, b] = [r, g, b].map(|c| (c.clamp(0.0, 1.0) * 255.0).round() as u8);
+            [r, g, b, 255]
+        })
+        .collect();
+    window.queue().write_texture(
+        wgpu::ImageCopyTexture {
+            texture: &texture,
+            mip_level: 0,
+            origin: wgpu::Origin3d::ZERO,
+            aspect: wgpu::TextureAspect::All,
+        },
+        &data,
+        wgpu::ImageDataLayout {
+            offset: 0,
+            bytes_per_row: NonZeroU32::new(4 * n),
+            rows_per_image: NonZeroU32::new(n),
+        },
+        extent,
+    );
+    texture.create_view(&wgpu::TextureViewDescriptor {
+        dimension: Some(wgpu::TextureViewDimension::D3),
+        ..Default::default()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    //A gradient from dark in the top left to bright in the bottom right
+    fn gradient(width: u32, height: u32) -> Pixels {
+        let mut pixels = Pixels::new(width, height);
+        for y in 0..height {
+            for x in 0..width {
+                let v = (x + y) as f32 / (width + height) as f32;
+                pixels.data[(y * width + x) as usize] = [v, v * 0.5, 1.0 - v, 1.0];
+            }
+        }
+        pixels
+    }
+
+    fn close(a: [f32; 4], b: [f32; 4]) -> bool {
+        a.iter().zip(b.iter()).all(|(a, b)| (a - b).abs() < 1e-4)
+    }
+
+    #[test]
+    fn vignette_keeps_the_middle() {
+        let pixels = Pixels {
+            width: 9,
+            height: 9,
+            data: vec![[0.8, 0.6, 0.4, 1.0]; 81],
+        };
+        let out = Effect::vignette().apply(&pixels, 0);
+        assert_eq!(out.get(4, 4), pixels.get(4, 4));
+        assert_eq!(out.get(3, 4), pixels.get(3, 4));
+        //The corners are darker, by the same amount in each channel, and keep their alpha
+        let corner = out.get(0, 0);
+        assert!(corner[0] < 0.8 * 0.5);
+        assert!((corner[0] / 0.8 - corner[2] / 0.4).abs() < 1e-5);
+        assert_eq!(corner[3], 1.0);
+        assert!(close(out.get(0, 0), out.get(8, 8)));
+    }
+
+    #[test]
+    fn pixelate_makes_blocks() {
+        let pixels = gradient(8, 8);
+        let out = Effect::Pixelate { size: 4.0 }.apply(&pixels, 0);
+        for y in 0..8 {
+            for x in 0..8 {
+                let first = out.get(x / 4 * 4, y / 4 * 4);
+                assert_eq!(out.get(x, y), first, "{}, {}", x, y);
+            }
+        }
+        assert_ne!(out.get(0, 0), out.get(4, 0));
+        assert_ne!(out.get(0, 0), out.get(0, 4));
+    }
+
+    #[test]
+    fn identity_lut_changes_nothing() {
+        let lut = Lut::identity(17);
+        for rgb in [[0.0, 0.0, 0.0], [1.0, 1.0, 1.0], [0.2, 0.7, 0.33], [0.99, 0.01, 0.5]] {
+            let out = lut.sample(rgb);
+            for k in 0..3 {
+                assert!((out[k] - rgb[k]).abs() < 1e-5, "{:?} became {:?}", rgb, out);
+            }
+        }
+
+        let pixels = gradient(6, 4);
+        let out = Effect::lut(Lut::identity(2)).apply(&pixels, 0);
+        for (a, b) in out.data.iter().zip(pixels.data.iter()) {
+            assert!(close(*a, *b), "{:?} became {:?}", b, a);
+        }
+    }
+
+    #[test]
+    fn lut_from_fn() {
+        //Trilinear blending gives back any grading that is linear, like this one, exactly
+        let lut = Lut::from_fn(5, |[r, g, b]| [1.0 - r, b, g * 0.5]);
+        assert_eq!(lut.colors.len(), 125);
+        assert_eq!(lut.colors[0], [1.0, 0.0, 0.0]);
+        let out = lut.sample([0.3, 0.6, 0.9]);
+        let expected = [0.7, 0.9, 0.3];
+        for k in 0..3 {
+            assert!((out[k] - expected[k]).abs() < 1e-5, "{:?}", out);
+        }
+    }
+
+    #[test]
+    fn parse_cube() {
+        let text = "# A table that swaps red and blue
+TITLE \"swap\"
+LUT_3D_SIZE 2
+DOMAIN_MIN 0.0 0.0 0.0
+DOMAIN_MAX 1.0 1.0 1.0
+
+0 0 0
+0 0 1
+0 1 0
+0 1 1
+1 0 0
+1 0 1
+1 1 0
+1 1 1
+";
+        let lut = Lut::parse(text).unwrap();
+        assert_eq!(lut.size, 2);
+        assert_eq!(lut.colors[1], [0.0, 0.0, 1.0]);
+        let out = lut.sample([0.8, 0.5, 0.1]);
+        let expected = [0.1, 0.5, 0.8];
+        for k in 0..3 {
+            assert!((out[k] - expected[k]).abs() < 1e-5, "{:?}", out);
+        }
+    }
+
+    #[test]
+    fn parse_rejects_bad_cubes() {
+        assert!(Lut::parse("LUT_3D_SIZE 2\n0 0 0\n1 1 1\n").is_err());
+        assert!(Lut::parse("0 0 0\n").is_err());
+        assert!(Lut::parse("LUT_1D_SIZE 2\n0 0 0\n1 1 1\n").is_err());
+        assert!(Lut::parse("LUT_3D_SIZE 2\n0 0\n").is_err());
+    }
+
+    #[test]
+    fn grain_depends_on_the_seed() {
+        let pixels = gradient(8, 8);
+        let effect = Effect::grain();
+        assert_eq!(effect.apply(&pixels, 3), effect.apply(&pixels, 3));
+        assert_ne!(effect.apply(&pixels, 3), effect.apply(&pixels, 4));
+    }
+
+    #[test]
+    fn chain_skips_effects_that_are_off() {
+        let pixels = gradient(8, 8);
+        let mut chain = PostChain::new()
+            .with(Effect::vignette())
+            .with(Effect::Pixelate { size: 2.0 });
+        let both = chain.apply(&pixels, 0);
+        chain.toggle(0);
+        let pixelated = chain.apply(&pixels, 0);
+        assert_eq!(pixelated, Effect::Pixelate { size: 2.0 }.apply(&pixels, 0));
+        assert_ne!(both, pixelated);
+        chain.toggle(1);
+        assert_eq!(chain.apply(&pixels, 0), pixels);
+    }
+}
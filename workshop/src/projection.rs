@@ -0,0 +1,480 @@
+//! Projection mapping: warping the sketch to fit a surface, and blending overlapping projectors.
+//!
+//! The sketch is drawn once into an `offscreen::Offscreen` texture.
+//! Each output window shows a part of that texture, warped with a _corner pin_: the four
+//! corners of the picture are dragged to where they should be on the wall, and everything in
+//! between follows along in perspective.
+//! Where two projectors overlap, an _edge blend_ fades each of them out, so the overlap is not
+//! twice as bright.
+//!
+//! The maths, i.e. `CornerPin` and `EdgeBlend`, don't need a window or a GPU.
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use nannou::glam::{Mat3, Vec3};
+use nannou::prelude::*;
+use nannou::wgpu;
+use serde::{Deserialize, Serialize};
+
+/// How many squares the warped picture is divided into along each side. More squares follow
+/// the perspective more closely.
+pub const GRID_RESOLUTION: usize = 16;
+
+/// Maps the unit square to any four-sided shape, with perspective.
+///
+/// The square uses texture coordinates, i.e. `(0, 0)` is the top left corner and `(1, 1)` the
+/// bottom right.
+/// The corners are given in the output, from `-1.0` to `1.0` along each axis, with y up.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CornerPin {
+    /// Top left, top right, bottom right and bottom left.
+    pub corners: [[f32; 2]; 4],
+}
+
+impl Default for CornerPin {
+    /// The whole output, without any warping.
+    fn default() -> Self {
+        CornerPin {
+            corners: [[-1.0, 1.0], [1.0, 1.0], [1.0, -1.0], [-1.0, -1.0]],
+        }
+    }
+}
+
+impl CornerPin {
+    pub fn new(top_left: Vec2, top_right: Vec2, bottom_right: Vec2, bottom_left: Vec2) -> Self {
+        CornerPin {
+            corners: [
+                top_left.to_array(),
+                top_right.to_array(),
+                bottom_right.to_array(),
+                bottom_left.to_array(),
+            ],
+        }
+    }
+
+    pub fn corner(&self, i: usize) -> Vec2 {
+        Vec2::from(self.corners[i])
+    }
+
+    pub fn set_corner(&mut self, i: usize, p: Vec2) {
+        self.corners[i] = p.to_array();
+    }
+
+    /// The homography, i.e. the 3x3 matrix that maps `(u, v, 1)` to the output.
+    ///
+    /// This is the square-to-quad mapping from Heckbert's "Fundamentals of Texture Mapping and
+    /// Image Warping". Returns `None` when three corners are on a line.
+    pub fn homography(&self) -> Option<Mat3> {
+        let [p0, p1, p2, p3] = [0, 1, 2, 3].map(|i| self.corner(i));
+        let d1 = p1 - p2;
+        let d2 = p3 - p2;
+        let d3 = p0 - p1 + p2 - p3;
+        let den = d1.x * d2.y - d2.x * d1.y;
+        if den.abs() < 1e-9 {
+            return None;
+        }
+        let g = (d3.x * d2.y - d2.x * d3.y) / den;
+        let h = (d1.x * d3.y - d3.x * d1.y) / den;
+        let a = p1.x - p0.x + g * p1.x;
+        let b = p3.x - p0.x + h * p3.x;
+        let d = p1.y - p0.y + g * p1.y;
+        let e = p3.y - p0.y + h * p3.y;
+        Some(Mat3::from_cols(
+            Vec3::new(a, d, g),
+            Vec3::new(b, e, h),
+            Vec3::new(p0.x, p0.y, 1.0),
+        ))
+    }
+
+    /// Where the texture coordinate `uv` ends up in the output.
+    pub fn map(&self, uv: Vec2) -> Vec2 {
+        match self.homography() {
+            Some(m) => project(m, uv),
+            None => self.corner(0),
+        }
+    }
+
+    /// Which texture coordinate ends up at `p` in the output, e.g. to find what is under the
+    /// mouse. `None` if the corners are on a line.
+    pub fn unmap(&self, p: Vec2) -> Option<Vec2> {
+        let m = self.homography()?;
+        Some(project(m.inverse(), p))
+    }
+
+    /// The index of the corner closest to `p`.
+    pub fn nearest_corner(&self, p: Vec2) -> usize {
+        (0..4)
+            .min_by(|&a, &b| {
+                let da = self.corner(a).distance_squared(p);
+                let db = self.corner(b).distance_squared(p);
+                da.total_cmp(&db)
+            })
+            .unwrap_or(0)
+    }
+}
+
+fn project(m: Mat3, p: Vec2) -> Vec2 {
+    let q = m * Vec3::new(p.x, p.y, 1.0);
+    if q.z.abs() < 1e-9 {
+        return vec2(q.x, q.y);
+    }
+    vec2(q.x, q.y) / q.z
+}
+
+/// Fades out the edges of an output where it overlaps another projector.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct EdgeBlend {
+    /// How much of each side is faded, as a fraction of the width or height.
+    pub left: f32,
+    pub right: f32,
+    pub top: f32,
+    pub bottom: f32,
+    /// The shape of the fade. `1.0` is a straight line, higher values give an S-curve where the
+    /// two projectors meet more softly.
+    pub curve: f32,
+    /// Projectors don't give out light in a straight line with the pixel values, so the fade is
+    /// gamma corrected. Usually about `2.2`.
+    pub gamma: f32,
+}
+
+impl Default for EdgeBlend {
+    fn default() -> Self {
+        EdgeBlend {
+            left: 0.0,
+            right: 0.0,
+            top: 0.0,
+            bottom: 0.0,
+            curve: 2.0,
+            gamma: 2.2,
+        }
+    }
+}
+
+impl EdgeBlend {
+    /// How bright the output should be at the texture coordinate `uv`, from `0.0` to `1.0`.
+    pub fn brightness(&self, uv: Vec2) -> f32 {
+        let fade = |t: f32, width: f32| {
+            if width <= 0.0 {
+                1.0
+            } else {
+                self.ramp((t / width).clamp(0.0, 1.0))
+            }
+        };
+        let b = fade(uv.x, self.left)
+            * fade(1.0 - uv.x, self.right)
+            * fade(uv.y, self.top)
+            * fade(1.0 - uv.y, self.bottom);
+        b.powf(1.0 / self.gamma.max(0.01))
+    }
+
+    //From 0 to 1. The curves of two overlapping outputs add up to 1.
+    fn ramp(&self, t: f32) -> f32 {
+        let p = self.curve.max(0.01);
+        if t < 0.5 {
+            0.5 * (2.0 * t).powf(p)
+        } else {
+            1.0 - 0.5 * (2.0 * (1.0 - t)).powf(p)
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.left <= 0.0 && self.right <= 0.0 && self.top <= 0.0 && self.bottom <= 0.0
+    }
+}
+
+/// The settings for one output window.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Output {
+    pub name: String,
+    /// The part of the sketch this output shows, as texture coordinates: left, top, right and
+    /// bottom.
+    pub source: [f32; 4],
+    pub pin: CornerPin,
+    pub blend: EdgeBlend,
+}
+
+impl Output {
+    /// An output showing the whole sketch.
+    pub fn new(name: impl Into<String>) -> Self {
+        Output {
+            name: name.into(),
+            source: [0.0, 0.0, 1.0, 1.0],
+            pin: CornerPin::default(),
+            blend: EdgeBlend::default(),
+        }
+    }
+
+    /// Texture coordinates within the source part, turned into coordinates in the whole sketch.
+    pub fn source_uv(&self, uv: Vec2) -> Vec2 {
+        let [left, top, right, bottom] = self.source;
+        vec2(left + (right - left) * uv.x, top + (bottom - top) * uv.y)
+    }
+
+    /// The warped grid: for each point, where it goes in a window of size `win`, and its
+    /// texture coordinate in the whole sketch. Two triangles per square, as indices.
+    pub fn warp_mesh(&self, win: Rect) -> (Vec<(Vec2, Vec2)>, Vec<usize>) {
+        let n = GRID_RESOLUTION;
+        let half = win.wh() * 0.5;
+        let mut points = Vec::with_capacity((n + 1) * (n + 1));
+        for row in 0..=n {
+            for column in 0..=n {
+                let uv = vec2(column as f32 / n as f32, row as f32 / n as f32);
+                points.push((self.pin.map(uv) * half, self.source_uv(uv)));
+            }
+        }
+        (points, grid_indices(n))
+    }
+
+    /// Draw the warped part of `texture` into the window, with the edge blend on top.
+    pub fn draw(&self, draw: &Draw, win: Rect, texture: &wgpu::Texture) {
+        let (points, indices) = self.warp_mesh(win);
+        draw.mesh().indexed_textured(
+            texture,
+            points.iter().map(|(p, uv)| (p.extend(0.0), *uv)),
+            indices,
+        );
+        if !self.blend.is_empty() {
+            self.draw_blend(draw, win);
+        }
+    }
+
+    /// A black mask that darkens the edges, following the warp.
+    pub fn draw_blend(&self, draw: &Draw, win: Rect) {
+        let n = GRID_RESOLUTION * 4;
+        let half = win.wh() * 0.5;
+        let mut points = Vec::with_capacity((n + 1) * (n + 1));
+        for row in 0..=n {
+            for column in 0..=n {
+                let uv = vec2(column as f32 / n as f32, row as f32 / n as f32);
+                let alpha = 1.0 - self.blend.brightness(uv);
+                points.push((
+                    (self.pin.map(uv) * half).extend(0.0),
+                    rgba(0.0, 0.0, 0.0, alpha),
+                ));
+            }
+        }
+        draw.mesh().indexed_colored(points, grid_indices(n));
+    }
+}
+
+fn grid_indices(n: usize) -> Vec<usize> {
+    let mut indices = Vec::with_capacity(n * n * 6);
+    for row in 0..n {
+        for column in 0..n {
+            let i = row * (n + 1) + column;
+            indices.extend([i, i + 1, i + n + 2, i, i + n + 2, i + n + 1]);
+        }
+    }
+    indices
+}
+
+/// All the outputs, saved to and loaded from a RON file.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ProjectionConfig {
+    pub outputs: Vec<Output>,
+}
+
+impl Default for ProjectionConfig {
+    fn default() -> Self {
+        ProjectionConfig {
+            outputs: vec![Output::new("output")],
+        }
+    }
+}
+
+impl ProjectionConfig {
+    /// `n` outputs next to each other, e.g. one per projector, each showing its part of the
+    /// sketch. Neighbours overlap by `overlap`, as a fraction of the whole sketch, and are
+    /// blended there.
+    pub fn side_by_side(n: usize, overlap: f32) -> Self {
+        let n = n.max(1);
+        let overlap = if n > 1 { overlap.clamp(0.0, 0.5) } else { 0.0 };
+        //The outputs cover the sketch exactly: n * width - (n - 1) * overlap = 1
+        let width = (1.0 + (n - 1) as f32 * overlap) / n as f32;
+        let blend = overlap / width;
+        let outputs = (0..n)
+            .map(|i| {
+                let left = i as f32 * (width - overlap);
+                let mut output = Output::new(format!("output {}", i + 1));
+                output.source = [left, 0.0, left + width, 1.0];
+                output.blend.left = if i > 0 { blend } else { 0.0 };
+                output.blend.right = if i + 1 < n { blend } else { 0.0 };
+                output
+            })
+            .collect();
+        ProjectionConfig { outputs }
+    }
+
+    /// Load a config saved with `save`. A config without outputs is an error, since there
+    /// would be nothing to show the sketch on.
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let text = fs::read_to_string(path.as_ref())?;
+        let config: ProjectionConfig = ron::from_str(&text)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))?;
+        if config.outputs.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{} has no outputs", path.as_ref().display()),
+            ));
+        }
+        Ok(config)
+    }
+
+    /// Load the config, or use the default if there is no file yet.
+    pub fn load_or_default(path: impl AsRef<Path>) -> Self {
+        match ProjectionConfig::load(path.as_ref()) {
+            Ok(config) => config,
+            Err(e) => {
+                if e.kind() != io::ErrorKind::NotFound {
+                    eprintln!("Could not load {}: {}", path.as_ref().display(), e);
+                }
+                ProjectionConfig::default()
+            }
+        }
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let text = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))?;
+        fs::write(path.as_ref(), text)?;
+        println!("Saved {}", path.as_ref().display());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: Vec2, b: Vec2) -> bool {
+        a.distance(b) < 1e-4
+    }
+
+    fn warped() -> CornerPin {
+        CornerPin::new(
+            vec2(-0.8, 0.9),
+            vec2(0.7, 0.6),
+            vec2(0.9, -0.7),
+            vec2(-0.6, -0.9),
+        )
+    }
+
+    #[test]
+    fn default_pin_maps_to_the_whole_output() {
+        let pin = CornerPin::default();
+        for uv in [vec2(0.0, 0.0), vec2(0.5, 0.5), vec2(0.25, 0.75), vec2(1.0, 0.3)] {
+            //Texture coordinates go down, the output goes up
+            let expected = vec2(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0);
+            assert!(close(pin.map(uv), expected), "{:?}", uv);
+            assert!(close(pin.unmap(expected).unwrap(), uv), "{:?}", uv);
+        }
+    }
+
+    #[test]
+    fn corners_map_to_their_pins() {
+        let pin = warped();
+        let uvs = [vec2(0.0, 0.0), vec2(1.0, 0.0), vec2(1.0, 1.0), vec2(0.0, 1.0)];
+        for (i, uv) in uvs.iter().enumerate() {
+            assert!(close(pin.map(*uv), pin.corner(i)), "corner {}", i);
+        }
+    }
+
+    #[test]
+    fn unmap_undoes_map() {
+        let pin = warped();
+        for row in 0..=4 {
+            for column in 0..=4 {
+                let uv = vec2(column as f32, row as f32) / 4.0;
+                let back = pin.unmap(pin.map(uv)).unwrap();
+                assert!(close(back, uv), "{:?} came back as {:?}", uv, back);
+            }
+        }
+    }
+
+    #[test]
+    fn straight_lines_stay_straight() {
+        //A homography keeps lines straight, so the middle of the square ends up where the
+        // diagonals of the pinned corners cross
+        let pin = warped();
+        let [a, b, c, d] = [0, 1, 2, 3].map(|i| pin.corner(i));
+        let (r, s) = (c - a, d - b);
+        let t = ((b - a).x * s.y - (b - a).y * s.x) / (r.x * s.y - r.y * s.x);
+        assert!(close(pin.map(vec2(0.5, 0.5)), a + r * t));
+    }
+
+    #[test]
+    fn corners_on_a_line_have_no_homography() {
+        let pin = CornerPin::new(
+            vec2(-1.0, 0.0),
+            vec2(0.0, 0.0),
+            vec2(1.0, 0.0),
+            vec2(0.5, 0.0),
+        );
+        assert!(pin.homography().is_none());
+        assert!(pin.unmap(Vec2::ZERO).is_none());
+    }
+
+    #[test]
+    fn overlapping_blends_add_up_to_one() {
+        for curve in [1.0, 2.0, 3.5] {
+            let mut config = ProjectionConfig::side_by_side(3, 0.2);
+            for output in config.outputs.iter_mut() {
+                output.blend.curve = curve;
+            }
+            //The light of each projector, for a point in the sketch
+            let light = |output: &Output, x: f32| {
+                let [left, _, right, _] = output.source;
+                let u = (x - left) / (right - left);
+                if !(-1e-4..=1.0 + 1e-4).contains(&u) {
+                    return 0.0;
+                }
+                let uv = vec2(u.clamp(0.0, 1.0), 0.5);
+                output.blend.brightness(uv).powf(output.blend.gamma)
+            };
+            for i in 0..=100 {
+                let x = i as f32 / 100.0;
+                let total: f32 = config.outputs.iter().map(|o| light(o, x)).sum();
+                assert!((total - 1.0).abs() < 1e-3, "{} at {} with curve {}", total, x, curve);
+            }
+        }
+    }
+
+    #[test]
+    fn side_by_side_covers_the_sketch() {
+        let config = ProjectionConfig::side_by_side(3, 0.2);
+        assert_eq!(config.outputs.len(), 3);
+        assert_eq!(config.outputs[0].source[0], 0.0);
+        assert!((config.outputs[2].source[2] - 1.0).abs() < 1e-6);
+        assert_eq!(config.outputs[0].blend.left, 0.0);
+        assert_eq!(config.outputs[2].blend.right, 0.0);
+        //Neighbours overlap by a fifth of the sketch
+        let overlap = config.outputs[0].source[2] - config.outputs[1].source[0];
+        assert!((overlap - 0.2).abs() < 1e-6);
+    }
+
+    #[test]
+    fn load_what_was_saved() {
+        let path = std::env::temp_dir().join("workshop-projection-test.ron");
+        let mut config = ProjectionConfig::side_by_side(2, 0.1);
+        config.outputs[1].pin = warped();
+        config.save(&path).unwrap();
+        let loaded = ProjectionConfig::load(&path);
+        let _ = fs::remove_file(&path);
+        assert_eq!(loaded.unwrap(), config);
+    }
+
+    #[test]
+    fn load_rejects_configs_without_outputs() {
+        let path = std::env::temp_dir().join("workshop-projection-empty-test.ron");
+        ProjectionConfig { outputs: vec![] }.save(&path).unwrap();
+        let error = ProjectionConfig::load(&path).unwrap_err();
+        let _ = fs::remove_file(&path);
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+
+        let missing = std::env::temp_dir().join("workshop-projection-missing-test.ron");
+        let error = ProjectionConfig::load(&missing).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::NotFound);
+    }
+}
@@ -0,0 +1,684 @@
+//! Recording what a sketch draws, so it can be checked without looking at pixels.
+//!
+//! `Recording::from_draw(&draw)` takes the shapes out of a nannou `Draw`, and records each of
+//! them as a `Primitive`, with its world-space transform, colour and stroke weight.
+//! `record(|draw| ...)` gives a view function a `Draw` of its own and records that, so it works
+//! without a window.
+//!
+//! The `Recording` has assertions for checking the result, e.g. that `p2_d` draws "24 stroked
+//! paths rotated every 30°":
+//!
+//! ```ignore
+//! let recording = record::record(|draw| p2_d::draw_kaleidoscope(draw, win, app.time));
+//! recording.assert_count::<record::Path>(24);
+//! recording.assert_rotations::<record::Path>(&[0.0, 30.0, 60.0, /* ... */], 0.5);
+//! ```
+//!
+//! Recording takes the shapes out of the `Draw` like rendering does, so record a `Draw` that is
+//! not drawn to the frame, not the one from `app.draw()`.
+//! nannou keeps the background colour and the points of paths, polygons, meshes and text to
+//! itself, so those are not recorded.
+
+use nannou::color::{IntoLinSrgba, LinSrgba};
+use nannou::draw::primitive::polygon::{PolygonOptions, SetPolygon};
+use nannou::draw::primitive::Primitive as DrawPrimitive;
+use nannou::draw::properties::spatial::{dimension, orientation, position};
+use nannou::draw::properties::{SetColor, SetDimensions, SetOrientation, SetPosition, SetStroke};
+use nannou::draw::renderer::{GlyphCache, RenderContext, RenderPrimitive};
+use nannou::draw::theme::{self, Theme};
+use nannou::draw::DrawCommand;
+use nannou::lyon::tessellation::{FillTessellator, StrokeTessellator};
+use nannou::prelude::{vec2, Draw, Mat4, Vec2, Vec3};
+
+/// The kinds of shapes that can be recorded. `draw.polyline()` draws a `Path`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Kind {
+    Ellipse,
+    Rect,
+    Line,
+    Arrow,
+    Tri,
+    Quad,
+    Polygon,
+    Path,
+    Mesh,
+    Text,
+    Texture,
+}
+
+/// A marker type for each kind, used like `recording.count::<record::Ellipse>()`.
+pub trait Shape {
+    const KIND: Kind;
+}
+
+macro_rules! shapes {
+    ($($kind:ident),*) => {
+        $(
+            pub struct $kind;
+
+            impl Shape for $kind {
+                const KIND: Kind = Kind::$kind;
+            }
+        )*
+    };
+}
+
+shapes!(Ellipse, Rect, Line, Arrow, Tri, Quad, Polygon, Path, Mesh, Text, Texture);
+
+/// One recorded shape.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Primitive {
+    pub kind: Kind,
+    /// The transform of the draw context, followed by the position and orientation of the
+    /// shape itself.
+    pub transform: Mat4,
+    /// Width and height before the transform, or twice the radius for ellipses. Zero for shapes
+    /// that are made of points, like paths.
+    pub size: Vec2,
+    /// The fill colour, or the stroke colour for shapes that are only stroked.
+    pub color: LinSrgba,
+    /// `Some` for outlines, paths drawn with `.stroke()`, polylines, lines and arrows.
+    pub stroke_weight: Option<f32>,
+    pub filled: bool,
+    /// The start and end of lines, before the transform.
+    pub points: Vec<Vec2>,
+    /// How many vertices nannou tessellates the shape into, or `None` for the shapes with
+    /// points nannou keeps to itself.
+    pub vertices: Option<usize>,
+}
+
+impl Primitive {
+    fn new(kind: Kind, transform: Mat4) -> Self {
+        Primitive {
+            kind,
+            transform,
+            size: Vec2::ZERO,
+            color: LinSrgba::new(1.0, 1.0, 1.0, 1.0),
+            stroke_weight: None,
+            filled: true,
+            points: Vec::new(),
+            vertices: None,
+        }
+    }
+
+    /// Where the shape ends up in the window.
+    pub fn world_position(&self) -> Vec2 {
+        self.transform.transform_point3(Vec3::ZERO).truncate()
+    }
+
+    /// The radius in the window, for ellipses. Assumes that the shape is scaled the same
+    /// amount along both axes.
+    pub fn world_radius(&self) -> f32 {
+        self.size.x * 0.5 * self.transform.x_axis.truncate().length()
+    }
+
+    /// The points of the shape, transformed to the window.
+    pub fn world_points(&self) -> Vec<Vec2> {
+        self.points
+            .iter()
+            .map(|p| self.transform.transform_point3(p.extend(0.0)).truncate())
+            .collect()
+    }
+
+    /// How much the shape is rotated, in degrees from `0.0` to `360.0`.
+    /// This is measured from the y axis, so mirroring with `scale_x(-1.0)` doesn't change it.
+    pub fn rotation(&self) -> f32 {
+        let y = self.transform.y_axis;
+        (-y.x).atan2(y.y).to_degrees().rem_euclid(360.0)
+    }
+
+    /// Whether the shape is mirrored, e.g. with `scale_x(-1.0)`.
+    pub fn is_mirrored(&self) -> bool {
+        let (x, y) = (self.transform.x_axis, self.transform.y_axis);
+        x.x * y.y - x.y * y.x < 0.0
+    }
+
+    pub fn is_stroked(&self) -> bool {
+        self.stroke_weight.is_some()
+    }
+}
+
+/// Draw with `view` into a `Draw` of its own, and record it.
+pub fn record<F>(view: F) -> Recording
+where
+    F: FnOnce(&Draw),
+{
+    let draw = Draw::new();
+    view(&draw);
+    Recording::from_draw(&draw)
+}
+
+/// Everything drawn into a `Draw`, with assertions for checking it.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Recording {
+    pub primitives: Vec<Primitive>,
+}
+
+impl Recording {
+    /// Take everything drawn into `draw` so far, and record it. The `Draw` is empty afterwards.
+    pub fn from_draw(draw: &Draw) -> Self {
+        let mut tessellator = Tessellator::new();
+        let mut context = Mat4::IDENTITY;
+        let mut primitives = Vec::new();
+        for command in draw.drain_commands() {
+            match command {
+                DrawCommand::Context(ctx) => context = ctx.transform,
+                DrawCommand::Primitive(primitive) => {
+                    primitives.extend(tessellator.record(primitive, context));
+                }
+            }
+        }
+        Recording { primitives }
+    }
+
+    /// The primitives of one kind, e.g. `recording.of_kind::<record::Path>()`.
+    pub fn of_kind<S: Shape>(&self) -> impl Iterator<Item = &Primitive> {
+        self.primitives.iter().filter(|p| p.kind == S::KIND)
+    }
+
+    pub fn count<S: Shape>(&self) -> usize {
+        self.of_kind::<S>().count()
+    }
+
+    /// The vertices of all the primitives nannou lets us count them for.
+    pub fn vertices(&self) -> usize {
+        self.primitives.iter().filter_map(|p| p.vertices).sum()
+    }
+
+    /// The rotations of the primitives of one kind, in degrees, in the order they were drawn.
+    pub fn rotations<S: Shape>(&self) -> Vec<f32> {
+        self.of_kind::<S>().map(Primitive::rotation).collect()
+    }
+
+    /// The first ellipse at `at` with `radius` and `color`, where positions and radius can be
+    /// off by `tolerance` pixels.
+    pub fn find_ellipse(
+        &self,
+        at: Vec2,
+        radius: f32,
+        color: impl IntoLinSrgba<f32>,
+        tolerance: f32,
+    ) -> Option<&Primitive> {
+        let color = color.into_lin_srgba();
+        self.of_kind::<Ellipse>().find(|p| {
+            p.world_position().distance(at) <= tolerance
+                && (p.world_radius() - radius).abs() <= tolerance
+                && same_color(p.color, color)
+        })
+    }
+
+    /// Panics unless there is an ellipse like `find_ellipse` describes.
+    pub fn assert_has_ellipse(
+        &self,
+        at: Vec2,
+        radius: f32,
+        color: impl IntoLinSrgba<f32>,
+        tolerance: f32,
+    ) {
+        if self.find_ellipse(at, radius, color, tolerance).is_none() {
+            let found: Vec<String> = self
+                .of_kind::<Ellipse>()
+                .map(|p| format!("{:?} radius {}", p.world_position(), p.world_radius()))
+                .collect();
+            panic!(
+                "expected an ellipse at {:?} with radius {} (±{}), found: [{}]",
+                at,
+                radius,
+                tolerance,
+                found.join(", ")
+            );
+        }
+    }
+
+    /// Panics unless exactly `expected` primitives of the kind were drawn.
+    pub fn assert_count<S: Shape>(&self, expected: usize) {
+        let count = self.count::<S>();
+        assert_eq!(
+            count,
+            expected,
+            "expected {} {:?} primitives, found {}",
+            expected,
+            S::KIND,
+            count
+        );
+    }
+
+    /// Panics unless every primitive of the kind is rotated by one of `angles` (in degrees),
+    /// and every angle is used.
+    pub fn assert_rotations<S: Shape>(&self, angles: &[f32], tolerance: f32) {
+        let rotations = self.rotations::<S>();
+        let near = |a: f32, b: f32| {
+            let diff = (a - b).rem_euclid(360.0);
+            diff.min(360.0 - diff) <= tolerance
+        };
+        for r in rotations.iter() {
+            assert!(
+                angles.iter().any(|&a| near(*r, a)),
+                "a {:?} is rotated {}°, which is not one of {:?}",
+                S::KIND,
+                r,
+                angles
+            );
+        }
+        for a in angles.iter() {
+            assert!(
+                rotations.iter().any(|&r| near(r, *a)),
+                "no {:?} is rotated {}°, found {:?}",
+                S::KIND,
+                a,
+                rotations
+            );
+        }
+    }
+
+    /// Panics unless all the primitives of the kind are stroked.
+    pub fn assert_all_stroked<S: Shape>(&self) {
+        for p in self.of_kind::<S>() {
+            assert!(p.is_stroked(), "a {:?} is filled, not stroked", S::KIND);
+        }
+    }
+}
+
+fn same_color(a: LinSrgba, b: LinSrgba) -> bool {
+    let close = |x: f32, y: f32| (x - y).abs() <= 1.0 / 255.0;
+    close(a.red, b.red) && close(a.green, b.green) && close(a.blue, b.blue) && close(a.alpha, b.alpha)
+}
+
+//Turns nannou's primitives into recorded ones, and tessellates them the way nannou's renderer
+// does to count their vertices
+struct Tessellator {
+    theme: Theme,
+    glyph_cache: GlyphCache,
+    fill: FillTessellator,
+    stroke: StrokeTessellator,
+    //Only paths, polygons, meshes and text use the buffers of the `Draw`, and those aren't
+    // tessellated here, so these stay empty
+    intermediary_mesh: nannou::draw::Mesh,
+    mesh: nannou::draw::Mesh,
+}
+
+impl Tessellator {
+    fn new() -> Self {
+        Tessellator {
+            theme: Theme::default(),
+            glyph_cache: GlyphCache {
+                cache: nannou::text::GlyphCache::builder().dimensions(1, 1).build(),
+                pixel_buffer: vec![0],
+                requires_upload: false,
+            },
+            fill: FillTessellator::new(),
+            stroke: StrokeTessellator::new(),
+            intermediary_mesh: Default::default(),
+            mesh: Default::default(),
+        }
+    }
+
+    fn vertices<P: RenderPrimitive>(&mut self, primitive: P, context: &Mat4) -> usize {
+        self.mesh.clear();
+        let ctxt = RenderContext {
+            transform: context,
+            intermediary_mesh: &self.intermediary_mesh,
+            path_event_buffer: &[],
+            path_points_colored_buffer: &[],
+            path_points_textured_buffer: &[],
+            text_buffer: "",
+            theme: &self.theme,
+            glyph_cache: &mut self.glyph_cache,
+            fill_tessellator: &mut self.fill,
+            stroke_tessellator: &mut self.stroke,
+            output_attachment_size: Vec2::ONE,
+            output_attachment_scale_factor: 1.0,
+        };
+        primitive.render_primitive(ctxt, &mut self.mesh);
+        self.mesh.points().len()
+    }
+
+    //Shapes that are still being drawn, like a `draw.path().stroke()` without any points, aren't
+    // drawn by nannou either, so they give `None`
+    fn record(&mut self, primitive: DrawPrimitive, context: Mat4) -> Option<Primitive> {
+        let theme = self.theme.clone();
+        let recorded = match primitive {
+            DrawPrimitive::Ellipse(mut ellipse) => {
+                let size = size_or(SetDimensions::properties(&mut ellipse), 100.0);
+                let mut p = polygon(Kind::Ellipse, context, ellipse.polygon_options_mut(), &theme);
+                p.size = size;
+                p.vertices = Some(self.vertices(ellipse, &context));
+                p
+            }
+            DrawPrimitive::Rect(mut rect) => {
+                let size = size_or(SetDimensions::properties(&mut rect), 100.0);
+                let mut p = polygon(Kind::Rect, context, rect.polygon_options_mut(), &theme);
+                p.size = size;
+                p.vertices = Some(self.vertices(rect, &context));
+                p
+            }
+            DrawPrimitive::Tri(mut tri) => {
+                let size = size_or(SetDimensions::properties(&mut tri), 0.0);
+                let mut p = polygon(Kind::Tri, context, tri.polygon_options_mut(), &theme);
+                p.size = size;
+                p.vertices = Some(self.vertices(tri, &context));
+                p
+            }
+            DrawPrimitive::Quad(mut quad) => {
+                let size = size_or(SetDimensions::properties(&mut quad), 0.0);
+                let mut p = polygon(Kind::Quad, context, quad.polygon_options_mut(), &theme);
+                p.size = size;
+                p.vertices = Some(self.vertices(quad, &context));
+                p
+            }
+            DrawPrimitive::Line(mut line) => {
+                let mut p = Primitive::new(Kind::Line, context * local(&mut line));
+                p.points = vec![
+                    line.start.unwrap_or(Vec2::ZERO),
+                    line.end.unwrap_or(Vec2::ZERO),
+                ];
+                p.stroke_weight = Some(line.stroke_options_mut().line_width);
+                p.filled = false;
+                p.color = line
+                    .rgba_mut()
+                    .unwrap_or_else(|| theme.stroke_lin_srgba(&theme::Primitive::Line));
+                p.vertices = Some(self.vertices(line, &context));
+                p
+            }
+            DrawPrimitive::Arrow(mut arrow) => {
+                let mut p = Primitive::new(Kind::Arrow, context * local(&mut arrow));
+                p.stroke_weight = Some(arrow.stroke_options_mut().line_width);
+                p.color = arrow
+                    .rgba_mut()
+                    .unwrap_or_else(|| theme.stroke_lin_srgba(&theme::Primitive::Arrow));
+                p.vertices = Some(self.vertices(arrow, &context));
+                p
+            }
+            DrawPrimitive::Polygon(mut polygon) => {
+                let debug = format!("{:?}", polygon);
+                let mut p = Primitive::new(Kind::Polygon, context * local(&mut polygon));
+                p.stroke_weight = stroke_weight_from_debug(&debug);
+                p.filled = !debug.contains("no_fill: true");
+                p.color = polygon
+                    .rgba_mut()
+                    .unwrap_or_else(|| theme.fill_lin_srgba(&theme::Primitive::Polygon));
+                p
+            }
+            DrawPrimitive::Path(mut path) => {
+                let mut p = Primitive::new(Kind::Path, context * local(&mut path));
+                p.stroke_weight = stroke_weight_from_debug(&format!("{:?}", path));
+                p.filled = !p.is_stroked();
+                p.color = path.rgba_mut().unwrap_or_else(|| match p.stroke_weight {
+                    Some(_) => theme.stroke_lin_srgba(&theme::Primitive::Path),
+                    None => theme.fill_lin_srgba(&theme::Primitive::Path),
+                });
+                p
+            }
+            DrawPrimitive::Mesh(mut mesh) => {
+                let mut p = Primitive::new(Kind::Mesh, context * local(&mut mesh));
+                //Without a colour, each vertex has its own
+                if let Some(color) = *mesh.rgba_mut() {
+                    p.color = color;
+                }
+                p
+            }
+            DrawPrimitive::Text(mut text) => {
+                let size = size_or(SetDimensions::properties(&mut text), 200.0);
+                let mut p = Primitive::new(Kind::Text, context * local(&mut text));
+                p.size = size;
+                p.color = text
+                    .rgba_mut()
+                    .unwrap_or_else(|| theme.fill_lin_srgba(&theme::Primitive::Text));
+                p
+            }
+            DrawPrimitive::Texture(mut texture) => {
+                let size = size_or(SetDimensions::properties(&mut texture), 0.0);
+                let mut p = Primitive::new(Kind::Texture, context * local(&mut texture));
+                p.size = size;
+                p
+            }
+            DrawPrimitive::PathInit(_)
+            | DrawPrimitive::PathFill(_)
+            | DrawPrimitive::PathStroke(_)
+            | DrawPrimitive::PolygonInit(_)
+            | DrawPrimitive::MeshVertexless(_) => return None,
+        };
+        Some(recorded)
+    }
+}
+
+//Ellipses, rects, tris and quads keep their position, colours and stroke in `PolygonOptions`
+fn polygon(kind: Kind, context: Mat4, opts: &PolygonOptions, theme: &Theme) -> Primitive {
+    let theme_primitive = match kind {
+        Kind::Ellipse => theme::Primitive::Ellipse,
+        Kind::Rect => theme::Primitive::Rect,
+        Kind::Tri => theme::Primitive::Tri,
+        Kind::Quad => theme::Primitive::Quad,
+        _ => theme::Primitive::Polygon,
+    };
+    let local = opts.position.transform() * opts.orientation.transform();
+    let mut p = Primitive::new(kind, context * local);
+    p.filled = !opts.no_fill;
+    p.stroke_weight = opts.stroke.map(|stroke| stroke.line_width);
+    p.color = if p.filled {
+        opts.color
+            .unwrap_or_else(|| theme.fill_lin_srgba(&theme_primitive))
+    } else {
+        opts.stroke_color
+            .unwrap_or_else(|| theme.stroke_lin_srgba(&theme_primitive))
+    };
+    p
+}
+
+//The transform nannou applies to a shape before the one of the draw context
+fn local<T: SetPosition + SetOrientation>(shape: &mut T) -> Mat4 {
+    let position: position::Properties = *SetPosition::properties(shape);
+    let orientation: orientation::Properties = *SetOrientation::properties(shape);
+    position.transform() * orientation.transform()
+}
+
+//The size nannou draws a shape at, with `default` for the sides that weren't set
+fn size_or(dimensions: &dimension::Properties, default: f32) -> Vec2 {
+    vec2(
+        dimensions.x.map(f32::abs).unwrap_or(default),
+        dimensions.y.map(f32::abs).unwrap_or(default),
+    )
+}
+
+//nannou doesn't give access to the tessellation options of finished paths and polygons, but
+// they are in the `Debug` output: the `StrokeOptions` have a `line_width`, and the
+// `FillOptions` or a missing stroke don't
+fn stroke_weight_from_debug(debug: &str) -> Option<f32> {
+    let rest = debug.split_once("line_width: ")?.1;
+    let end = rest.find([',', ' ', '}']).unwrap_or(rest.len());
+    rest[..end].parse().ok()
+}
+
+#[cfg(test)]
+#[path = "../../p2/src/suggestion/p2_d.rs"]
+#[allow(dead_code)]
+mod p2_d;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nannou::prelude::{pt2, Rect as WindowRect, BLUE, MAGENTA, PI, WHITE};
+
+    const NUM_ARMS: usize = 12;
+
+    //Everything `p2_d` draws at `time`, in a window of 1024 by 768
+    fn p2_d(time: f32) -> Recording {
+        let win = WindowRect::from_w_h(1024.0, 768.0);
+        record(|draw| super::p2_d::draw_kaleidoscope(draw, win, time))
+    }
+
+    #[test]
+    fn p2_d_is_24_stroked_paths() {
+        for time in [0.0, 1.5, 20.0] {
+            let recording = p2_d(time);
+            recording.assert_count::<Path>(NUM_ARMS * 2);
+            recording.assert_count::<Ellipse>(0);
+            recording.assert_all_stroked::<Path>();
+            let angles: Vec<f32> = (0..NUM_ARMS).map(|i| i as f32 * 30.0).collect();
+            recording.assert_rotations::<Path>(&angles, 0.5);
+            for path in recording.of_kind::<Path>() {
+                assert_eq!(path.stroke_weight, Some(1.0));
+                assert!(same_color(path.color, WHITE.into_lin_srgba()));
+            }
+        }
+    }
+
+    #[test]
+    fn p2_d_fades_with_a_rect_over_the_window() {
+        let recording = p2_d(1.0);
+        recording.assert_count::<Rect>(1);
+        let rect = &recording.primitives[0];
+        assert_eq!(rect.kind, Kind::Rect);
+        assert_eq!(rect.size, vec2(1024.0, 768.0));
+        assert_eq!(rect.world_position(), Vec2::ZERO);
+        assert!(rect.filled && !rect.is_stroked());
+        assert!((rect.color.alpha - 0.01).abs() < 1e-6);
+        //A filled rect is two triangles
+        assert_eq!(rect.vertices, Some(4));
+    }
+
+    #[test]
+    fn p2_d_arms_are_mirrored_in_pairs() {
+        let recording = p2_d(1.0);
+        let paths: Vec<&Primitive> = recording.of_kind::<Path>().collect();
+        for pair in paths.chunks(2) {
+            assert!(!pair[0].is_mirrored());
+            assert!(pair[1].is_mirrored());
+            assert!((pair[0].rotation() - pair[1].rotation()).abs() < 1e-3);
+        }
+    }
+
+    #[test]
+    fn p2_d_arms_start_halfway_out() {
+        //Each arm is moved up by half the window height, in a context scaled by a half, so it
+        // starts a quarter of the height from the middle
+        let recording = p2_d(1.0);
+        for path in recording.of_kind::<Path>() {
+            let start = path.world_position();
+            assert!((start.length() - 768.0 * 0.25).abs() < 1e-2, "{:?}", start);
+            let angle = path.rotation().to_radians();
+            let expected = vec2(-angle.sin(), angle.cos()) * 768.0 * 0.25;
+            assert!(start.distance(expected) < 1e-2, "{:?} {:?}", start, expected);
+        }
+    }
+
+    #[test]
+    fn records_every_kind() {
+        let recording = record(|draw| {
+            draw.ellipse();
+            draw.rect();
+            draw.line().end(pt2(10.0, 0.0));
+            draw.arrow().end(pt2(10.0, 0.0));
+            draw.tri();
+            draw.quad();
+            draw.polygon()
+                .points([pt2(0.0, 0.0), pt2(10.0, 0.0), pt2(0.0, 10.0)]);
+            draw.polyline()
+                .points([pt2(0.0, 0.0), pt2(10.0, 0.0), pt2(0.0, 10.0)]);
+            draw.mesh().indexed_colored(
+                [(pt2(0.0, 0.0).extend(0.0), BLUE), (pt2(1.0, 0.0).extend(0.0), BLUE)],
+                [0, 1, 0],
+            );
+            draw.text("kaleidoscope");
+        });
+        let kinds: Vec<Kind> = recording.primitives.iter().map(|p| p.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                Kind::Ellipse,
+                Kind::Rect,
+                Kind::Line,
+                Kind::Arrow,
+                Kind::Tri,
+                Kind::Quad,
+                Kind::Polygon,
+                Kind::Path,
+                Kind::Mesh,
+                Kind::Text,
+            ]
+        );
+        //nannou's default sizes
+        assert_eq!(recording.primitives[0].size, vec2(100.0, 100.0));
+        assert_eq!(recording.primitives[9].size, vec2(200.0, 200.0));
+        //The shapes nannou lets us tessellate have vertices
+        for p in &recording.primitives[..6] {
+            assert!(p.vertices.unwrap() >= 3, "{:?}", p.kind);
+        }
+        assert_eq!(recording.primitives[7].vertices, None);
+        let sum: usize = recording.primitives[..6].iter().map(|p| p.vertices.unwrap()).sum();
+        assert_eq!(recording.vertices(), sum);
+    }
+
+    #[test]
+    fn records_colours_and_strokes() {
+        let recording = record(|draw| {
+            draw.xy(vec2(10.0, 0.0)).ellipse().radius(5.0).color(MAGENTA);
+            draw.ellipse()
+                .radius(20.0)
+                .no_fill()
+                .stroke(BLUE)
+                .stroke_weight(3.0);
+            draw.line().start(pt2(0.0, 0.0)).end(pt2(10.0, 0.0)).weight(4.0);
+            draw.path()
+                .fill()
+                .color(BLUE)
+                .points([pt2(0.0, 0.0), pt2(10.0, 0.0), pt2(0.0, 10.0)]);
+            draw.polygon()
+                .stroke_weight(2.0)
+                .points([pt2(0.0, 0.0), pt2(10.0, 0.0), pt2(0.0, 10.0)]);
+        });
+        recording.assert_has_ellipse(vec2(10.0, 0.0), 5.0, MAGENTA, 0.01);
+        assert!(recording.find_ellipse(vec2(10.0, 0.0), 5.0, WHITE, 0.01).is_none());
+        assert_eq!(recording.primitives[0].stroke_weight, None);
+
+        let outline = &recording.primitives[1];
+        assert!(!outline.filled);
+        assert_eq!(outline.stroke_weight, Some(3.0));
+        assert!(same_color(outline.color, BLUE.into_lin_srgba()));
+
+        let line = &recording.primitives[2];
+        assert_eq!(line.stroke_weight, Some(4.0));
+        assert_eq!(line.world_points(), vec![vec2(0.0, 0.0), vec2(10.0, 0.0)]);
+
+        let fill = &recording.primitives[3];
+        assert!(fill.filled && !fill.is_stroked());
+        assert!(same_color(fill.color, BLUE.into_lin_srgba()));
+
+        let polygon = &recording.primitives[4];
+        assert!(polygon.filled);
+        assert_eq!(polygon.stroke_weight, Some(2.0));
+    }
+
+    #[test]
+    fn transforms_apply_in_order() {
+        let recording = record(|draw| {
+            draw.rotate(PI * 0.5)
+                .x_y(10.0, 0.0)
+                .scale(2.0)
+                .ellipse()
+                .x_y(1.0, 0.0)
+                .radius(3.0);
+        });
+        let ellipse = recording.of_kind::<Ellipse>().next().unwrap();
+        assert!(ellipse.world_position().distance(vec2(0.0, 12.0)) < 1e-4);
+        assert!((ellipse.world_radius() - 6.0).abs() < 1e-4);
+        assert!((ellipse.rotation() - 90.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn recording_empties_the_draw() {
+        let draw = Draw::new();
+        draw.ellipse();
+        //Nothing is drawn for a path without points
+        draw.path().stroke();
+        assert_eq!(Recording::from_draw(&draw).primitives.len(), 1);
+        assert!(Recording::from_draw(&draw).primitives.is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "no Path is rotated 15")]
+    fn missing_rotation_panics() {
+        let angles: Vec<f32> = (0..=NUM_ARMS).map(|i| i as f32 * 30.0).chain([15.0]).collect();
+        p2_d(0.0).assert_rotations::<Path>(&angles, 0.5);
+    }
+}
@@ -0,0 +1,311 @@
+//! Seeded, reproducible randomness for sketches.
+//!
+//! nannou's `random_f32()` draws from a thread local generator that we can't seed, so a nice
+//! looking result can never be recreated.
+//! The `Randomness` service holds one explicit seed for the whole sketch, and hands out named
+//! _streams_ derived from that seed.
+//! Each subsystem (e.g. "buildings", "stars", "palette") gets its own stream, so adding a random
+//! call in one of them doesn't shift the numbers in all the others.
+
+use std::collections::BTreeMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use nannou::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// The key that picks a new random seed in `Randomness::key_pressed`.
+pub const RESEED_KEY: Key = Key::R;
+
+/// A small and fast pseudo random generator (SplitMix64).
+///
+/// We use our own generator instead of `nannou::rand::rngs::StdRng`, since `StdRng` makes no
+/// promise about giving the same numbers across versions of the `rand` crate.
+/// The whole state is one number, so it can be saved in a snapshot and picked up again.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SketchRng {
+    state: u64,
+}
+
+impl SketchRng {
+    pub fn from_seed(seed: u64) -> Self {
+        SketchRng { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        mix64(self.state)
+    }
+
+    /// A number in the range `0.0..1.0`.
+    pub fn f32(&mut self) -> f32 {
+        //Use the upper 24 bits, which is all the precision a f32 mantissa can hold
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    /// A number in the range `min..max`.
+    pub fn range(&mut self, min: f32, max: f32) -> f32 {
+        min + (max - min) * self.f32()
+    }
+
+    /// An index in the range `0..len`.
+    pub fn index(&mut self, len: usize) -> usize {
+        if len == 0 {
+            return 0;
+        }
+        (self.next_u64() % len as u64) as usize
+    }
+
+    /// Returns `true` with the probability `p`.
+    pub fn chance(&mut self, p: f32) -> bool {
+        self.f32() < p
+    }
+
+    /// A normal distributed number with mean 0.0 and standard deviation 1.0 (Box-Muller).
+    pub fn gaussian(&mut self) -> f32 {
+        let u1 = self.f32().max(f32::MIN_POSITIVE);
+        let u2 = self.f32();
+        (-2.0 * u1.ln()).sqrt() * (TAU * u2).cos()
+    }
+
+    /// A random position inside the rect, e.g. `app.window_rect()`.
+    pub fn in_rect(&mut self, rect: Rect) -> Vec2 {
+        vec2(
+            self.range(rect.left(), rect.right()),
+            self.range(rect.bottom(), rect.top()),
+        )
+    }
+
+    /// A random element from the slice, or `None` if it is empty.
+    pub fn pick<'a, T>(&mut self, items: &'a [T]) -> Option<&'a T> {
+        if items.is_empty() {
+            None
+        } else {
+            items.get(self.index(items.len()))
+        }
+    }
+}
+
+//Lets us use the generator with everything in the `rand` crate that takes an `Rng`,
+// e.g. `slice.shuffle(&mut rng)` or the distributions.
+impl nannou::rand::RngCore for SketchRng {
+    fn next_u32(&mut self) -> u32 {
+        (SketchRng::next_u64(self) >> 32) as u32
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        SketchRng::next_u64(self)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        for chunk in dest.chunks_mut(8) {
+            let bytes = SketchRng::next_u64(self).to_le_bytes();
+            chunk.copy_from_slice(&bytes[..chunk.len()]);
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), nannou::rand::Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+/// The sketch wide randomness service. Keep one of these in your `Model`.
+///
+/// It can be saved with serde, including how far each stream has come, so a sketch loaded from
+/// a `snapshot::Snapshot` gets the same random numbers it would have got without stopping.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Randomness {
+    seed: u64,
+    streams: BTreeMap<String, SketchRng>,
+}
+
+impl Randomness {
+    pub fn new(seed: u64) -> Self {
+        Randomness {
+            seed,
+            streams: BTreeMap::new(),
+        }
+    }
+
+    /// Use the seed given with `--seed <number>` on the command line, or in the `SKETCH_SEED`
+    /// environment variable.
+    /// Without any of them we make up a new seed from the clock.
+    /// The seed is printed, so that you can run the sketch again with the same seed.
+    ///
+    /// ```text
+    /// cargo run --bin seeded-skyline -- --seed 1234
+    /// ```
+    pub fn from_args() -> Self {
+        let seed = seed_from_args(std::env::args())
+            .or_else(|| std::env::var("SKETCH_SEED").ok().and_then(|s| s.parse().ok()))
+            .unwrap_or_else(clock_seed);
+        let randomness = Randomness::new(seed);
+        randomness.print_seed();
+        randomness
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Start over with a new seed. All streams restart from the beginning.
+    pub fn reseed(&mut self, seed: u64) {
+        self.seed = seed;
+        self.streams.clear();
+        self.print_seed();
+    }
+
+    /// Start over with a seed made up from the clock.
+    pub fn reseed_from_clock(&mut self) {
+        self.reseed(clock_seed());
+    }
+
+    /// Call this from your `key_pressed` function to reseed with the `R` key.
+    /// Returns `true` when the sketch was reseeded, so that you can rebuild your model.
+    pub fn key_pressed(&mut self, key: Key) -> bool {
+        if key == RESEED_KEY {
+            self.reseed_from_clock();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// The named stream, which keeps its position between calls.
+    /// Use this in `model` and `update`.
+    pub fn stream(&mut self, name: &str) -> &mut SketchRng {
+        let seed = self.seed;
+        self.streams
+            .entry(name.to_string())
+            .or_insert_with(|| SketchRng::from_seed(derive_seed(seed, name)))
+    }
+
+    /// A new generator for the named stream, starting from the beginning every time.
+    /// Since it only needs `&self`, it can be used in `view` to get the same numbers every frame.
+    pub fn fresh(&self, name: &str) -> SketchRng {
+        SketchRng::from_seed(derive_seed(self.seed, name))
+    }
+
+    /// A seed for the named subsystem, for things that take a seed instead of a generator,
+    /// e.g. the fields in `workshop::noise`.
+    pub fn derived_seed(&self, name: &str) -> u64 {
+        derive_seed(self.seed, name)
+    }
+
+    fn print_seed(&self) {
+        println!("Random seed: {0} (run again with `-- --seed {0}`)", self.seed);
+    }
+}
+
+/// Derive the seed for a named stream from the sketch seed.
+pub fn derive_seed(seed: u64, name: &str) -> u64 {
+    //FNV-1a hash of the name, mixed with the sketch seed
+    let mut hash: u64 = 0xCBF2_9CE4_8422_2325;
+    for byte in name.bytes() {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x0000_0100_0000_01B3);
+    }
+    mix64(seed ^ mix64(hash))
+}
+
+fn mix64(mut z: u64) -> u64 {
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+fn clock_seed() -> u64 {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos() as u64)
+        .unwrap_or(0);
+    //Keep the seed short enough to be typed in by hand
+    mix64(nanos) % 1_000_000
+}
+
+fn seed_from_args(args: impl Iterator<Item = String>) -> Option<u64> {
+    let args: Vec<String> = args.collect();
+    args.iter()
+        .position(|arg| arg == "--seed")
+        .and_then(|i| args.get(i + 1))
+        .and_then(|s| s.parse().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn draws(rng: &mut SketchRng, n: usize) -> Vec<u64> {
+        (0..n).map(|_| rng.next_u64()).collect()
+    }
+
+    #[test]
+    fn same_seed_gives_same_sequence() {
+        let a = draws(&mut SketchRng::from_seed(1234), 100);
+        let b = draws(&mut SketchRng::from_seed(1234), 100);
+        assert_eq!(a, b);
+        let c = draws(&mut SketchRng::from_seed(1235), 100);
+        assert_ne!(a, c);
+    }
+
+    #[test]
+    fn floats_stay_in_range() {
+        let mut rng = SketchRng::from_seed(7);
+        for _ in 0..10_000 {
+            let x = rng.f32();
+            assert!((0.0..1.0).contains(&x));
+            let y = rng.range(-5.0, 5.0);
+            assert!((-5.0..5.0).contains(&y));
+            assert!(rng.index(3) < 3);
+        }
+    }
+
+    #[test]
+    fn derived_seeds_differ_by_name_and_seed() {
+        assert_eq!(derive_seed(1, "stars"), derive_seed(1, "stars"));
+        assert_ne!(derive_seed(1, "stars"), derive_seed(1, "buildings"));
+        assert_ne!(derive_seed(1, "stars"), derive_seed(2, "stars"));
+    }
+
+    #[test]
+    fn streams_do_not_depend_on_draw_order() {
+        let mut a = Randomness::new(42);
+        let stars_first = draws(a.stream("stars"), 10);
+        let buildings_second = draws(a.stream("buildings"), 10);
+
+        //Draw from the other stream first, and add a stream that `a` doesn't have
+        let mut b = Randomness::new(42);
+        let buildings_first = draws(b.stream("buildings"), 10);
+        let _ = draws(b.stream("palette"), 3);
+        let stars_second = draws(b.stream("stars"), 10);
+
+        assert_eq!(stars_first, stars_second);
+        assert_eq!(buildings_second, buildings_first);
+        assert_eq!(stars_first, draws(&mut a.fresh("stars"), 10));
+    }
+
+    #[test]
+    fn streams_keep_their_position() {
+        let mut randomness = Randomness::new(42);
+        let first = draws(randomness.stream("stars"), 5);
+        let second = draws(randomness.stream("stars"), 5);
+        let all = draws(&mut randomness.fresh("stars"), 10);
+        assert_eq!([first, second].concat(), all);
+    }
+
+    #[test]
+    fn reseeding_restarts_the_streams() {
+        let mut randomness = Randomness::new(42);
+        let before = draws(randomness.stream("stars"), 5);
+        randomness.reseed(42);
+        assert_eq!(draws(randomness.stream("stars"), 5), before);
+    }
+
+    #[test]
+    fn seed_is_read_from_args() {
+        let args = ["sketch", "--seed", "99"].iter().map(|s| s.to_string());
+        assert_eq!(seed_from_args(args), Some(99));
+        let args = ["sketch"].iter().map(|s| s.to_string());
+        assert_eq!(seed_from_args(args), None);
+    }
+}
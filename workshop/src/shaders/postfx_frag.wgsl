@@ -0,0 +1,105 @@
+// All the effects in `postfx`, one per mode. Each pass runs one mode.
+// `Effect::apply` in `postfx.rs` does the same maths on the CPU, so keep the two in step.
+
+[[block]]
+struct Uniforms {
+    params: vec4<f32>;
+    size: vec2<f32>;
+    seed: u32;
+    mode: u32;
+};
+
+[[group(0), binding(0)]] var<uniform> u: Uniforms;
+[[group(0), binding(1)]] var src: texture_2d<f32>;
+[[group(0), binding(2)]] var aux: texture_2d<f32>;
+[[group(0), binding(3)]] var lut: texture_3d<f32>;
+[[group(0), binding(4)]] var samp: sampler;
+
+let COPY: u32 = 0u;
+let BLOOM_H: u32 = 1u;
+let BLOOM_V: u32 = 2u;
+let ABERRATION: u32 = 3u;
+let VIGNETTE: u32 = 4u;
+let GRAIN: u32 = 5u;
+let LUT: u32 = 6u;
+let PIXELATE: u32 = 7u;
+
+let BLUR_TAPS: i32 = 8;
+
+fn hash(x: u32, y: u32, z: u32) -> f32 {
+    var h = x * 374761393u + y * 668265263u + z * 2246822519u;
+    h = (h ^ (h >> 13u)) * 1274126177u;
+    h = h ^ (h >> 16u);
+    return f32(h & 16777215u) / 16777216.0;
+}
+
+fn linear_to_srgb(c: vec3<f32>) -> vec3<f32> {
+    let low = c * 12.92;
+    let high = 1.055 * pow(c, vec3<f32>(1.0 / 2.4)) - 0.055;
+    return select(high, low, c <= vec3<f32>(0.0031308));
+}
+
+fn srgb_to_linear(c: vec3<f32>) -> vec3<f32> {
+    let low = c / 12.92;
+    let high = pow((c + 0.055) / 1.055, vec3<f32>(2.4));
+    return select(high, low, c <= vec3<f32>(0.04045));
+}
+
+// A gaussian blur along `dir`, of what is brighter than `threshold`
+fn blur(uv: vec2<f32>, dir: vec2<f32>, threshold: f32) -> vec3<f32> {
+    var sum = vec3<f32>(0.0);
+    var weights = 0.0;
+    for (var i: i32 = -BLUR_TAPS; i <= BLUR_TAPS; i = i + 1) {
+        let t = f32(i) / f32(BLUR_TAPS);
+        let w = exp(-4.0 * t * t);
+        let offset = dir * t * u.params.z / u.size;
+        let c = textureSample(src, samp, uv + offset).rgb;
+        sum = sum + max(c - vec3<f32>(threshold), vec3<f32>(0.0)) * w;
+        weights = weights + w;
+    }
+    return sum / weights;
+}
+
+[[stage(fragment)]]
+fn main([[location(0)]] uv: vec2<f32>) -> [[location(0)]] vec4<f32> {
+    let c = textureSample(src, samp, uv);
+    let p = u.params;
+
+    if (u.mode == BLOOM_H) {
+        return vec4<f32>(blur(uv, vec2<f32>(1.0, 0.0), p.x), 1.0);
+    }
+    if (u.mode == BLOOM_V) {
+        let original = textureSample(aux, samp, uv);
+        return vec4<f32>(original.rgb + blur(uv, vec2<f32>(0.0, 1.0), 0.0) * p.y, original.a);
+    }
+    if (u.mode == ABERRATION) {
+        let offset = (uv * 2.0 - 1.0) * p.x / u.size;
+        let r = textureSample(src, samp, uv + offset).r;
+        let b = textureSample(src, samp, uv - offset).b;
+        return vec4<f32>(r, c.g, b, c.a);
+    }
+    if (u.mode == VIGNETTE) {
+        let d = length(uv * 2.0 - 1.0) / sqrt(2.0);
+        let t = clamp((d - p.y) / max(p.z, 0.0001), 0.0, 1.0);
+        let s = t * t * (3.0 - 2.0 * t);
+        return vec4<f32>(c.rgb * (1.0 - p.x * s), c.a);
+    }
+    if (u.mode == GRAIN) {
+        let pixel = vec2<u32>(uv * u.size);
+        let n = hash(pixel.x, pixel.y, u.seed) - 0.5;
+        return vec4<f32>(max(c.rgb + vec3<f32>(n * p.x), vec3<f32>(0.0)), c.a);
+    }
+    if (u.mode == LUT) {
+        let n = p.y;
+        let srgb = linear_to_srgb(clamp(c.rgb, vec3<f32>(0.0), vec3<f32>(1.0)));
+        let coord = srgb * (n - 1.0) / n + 0.5 / n;
+        let graded = textureSample(lut, samp, coord).rgb;
+        return vec4<f32>(srgb_to_linear(mix(srgb, graded, vec3<f32>(p.x))), c.a);
+    }
+    if (u.mode == PIXELATE) {
+        let size = max(p.x, 1.0);
+        let cell = floor(uv * u.size / size);
+        return textureSample(src, samp, (cell + 0.5) * size / u.size);
+    }
+    return c;
+}
@@ -0,0 +1,16 @@
+// One triangle that covers the whole target. The corners outside the target are clipped.
+
+struct VertexOutput {
+    [[builtin(position)]] position: vec4<f32>;
+    [[location(0)]] uv: vec2<f32>;
+};
+
+[[stage(vertex)]]
+fn main([[builtin(vertex_index)]] index: u32) -> VertexOutput {
+    let corner = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+    var out: VertexOutput;
+    out.position = vec4<f32>(corner * 2.0 - 1.0, 0.0, 1.0);
+    // Texture coordinates start in the top left corner
+    out.uv = vec2<f32>(corner.x, 1.0 - corner.y);
+    return out;
+}
@@ -0,0 +1,288 @@
+//! Sketches as types instead of a `model`, `update` and `view` function each.
+//!
+//! Every sketch in the workshop starts with
+//! `nannou::app(model).update(update).simple_window(view).run()`.
+//! Implementing `Sketch` for the model instead lets the same sketch be run in a window with
+//! `sketch::run::<MySketch>()`, stepped without a window with `Headless`, or rendered for print
+//! with `export::TiledExport`, without writing a `main` for each.
+//!
+//! The sketch gets a `Context` with the time, frame number, window rect and mouse position,
+//! instead of the `App`, so it can be driven without an `App` as well.
+
+use std::ops::RangeInclusive;
+
+use nannou::prelude::*;
+
+use crate::capture::{self, FrameMetadata};
+
+/// What a sketch knows about the world it runs in.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Context {
+    /// Seconds since the sketch started.
+    pub time: f32,
+    pub frame: u64,
+    pub window_rect: Rect,
+    pub mouse: Vec2,
+}
+
+impl Context {
+    /// A context at time zero, for a window of `w` by `h`.
+    pub fn new(w: f32, h: f32) -> Self {
+        Context {
+            time: 0.0,
+            frame: 0,
+            window_rect: Rect::from_w_h(w, h),
+            mouse: Vec2::ZERO,
+        }
+    }
+
+    pub fn from_app(app: &App) -> Self {
+        Context {
+            time: app.time,
+            frame: app.elapsed_frames(),
+            window_rect: app.window_rect(),
+            mouse: app.mouse.position(),
+        }
+    }
+}
+
+/// A number in a sketch that can be tweaked while it runs, e.g. from a GUI or a MIDI
+/// controller.
+#[derive(Debug)]
+pub struct Param<'a> {
+    pub name: &'static str,
+    pub value: &'a mut f32,
+    pub range: RangeInclusive<f32>,
+}
+
+impl<'a> Param<'a> {
+    pub fn new(name: &'static str, value: &'a mut f32, range: RangeInclusive<f32>) -> Self {
+        Param { name, value, range }
+    }
+
+    /// Set the value, kept within the range.
+    pub fn set(&mut self, value: f32) {
+        *self.value = value.clamp(*self.range.start(), *self.range.end());
+    }
+
+    /// Set the value from `0.0` at the start of the range to `1.0` at the end.
+    pub fn set_normalized(&mut self, t: f32) {
+        let (start, end) = (*self.range.start(), *self.range.end());
+        self.set(start + (end - start) * t.clamp(0.0, 1.0));
+    }
+}
+
+/// A sketch.
+pub trait Sketch: Sized + 'static {
+    /// The window title.
+    const TITLE: &'static str = "sketch";
+    /// The size of the window the sketch is designed for.
+    const SIZE: (u32, u32) = (1024, 720);
+
+    /// Make the sketch. This is the `model` function.
+    fn init(ctx: &Context) -> Self;
+
+    /// Called every frame with the seconds since the last frame.
+    fn update(&mut self, _ctx: &Context, _dt: f32) {}
+
+    /// Draw the sketch. The background is not cleared, so do that here if you want to.
+    fn view(&self, ctx: &Context, draw: &Draw);
+
+    /// Keys, mouse and other window events.
+    fn on_event(&mut self, _ctx: &Context, _event: &WindowEvent) {}
+
+    /// The numbers that can be tweaked while the sketch runs.
+    fn params(&mut self) -> Vec<Param<'_>> {
+        Vec::new()
+    }
+
+    /// The seed the sketch uses, saved with captured frames.
+    fn seed(&self) -> u64 {
+        0
+    }
+}
+
+/// Run a sketch in a window. `S` to capture a frame is handled here, all other events are
+/// passed on to the sketch.
+pub fn run<S: Sketch>() {
+    nannou::app(model::<S>).update(update::<S>).run();
+}
+
+//The model of the nannou app that runs the sketch
+struct Runner<S> {
+    sketch: S,
+}
+
+fn model<S: Sketch>(app: &App) -> Runner<S> {
+    let (w, h) = S::SIZE;
+    app.new_window()
+        .size(w, h)
+        .title(S::TITLE)
+        .view(view::<S>)
+        .event(event::<S>)
+        .build()
+        .unwrap();
+    Runner {
+        sketch: S::init(&Context::from_app(app)),
+    }
+}
+
+fn update<S: Sketch>(app: &App, runner: &mut Runner<S>, update: Update) {
+    runner
+        .sketch
+        .update(&Context::from_app(app), update.since_last.as_secs_f32());
+}
+
+fn event<S: Sketch>(app: &App, runner: &mut Runner<S>, event: WindowEvent) {
+    if let WindowEvent::KeyPressed(key) = event {
+        if key == capture::CAPTURE_KEY {
+            let metadata = FrameMetadata::new(app, runner.sketch.seed());
+            if let Err(e) = capture::capture_frame(app, &metadata) {
+                eprintln!("Could not capture frame: {}", e);
+            }
+            return;
+        }
+    }
+    runner.sketch.on_event(&Context::from_app(app), &event);
+}
+
+fn view<S: Sketch>(app: &App, runner: &Runner<S>, frame: Frame) {
+    let draw = app.draw();
+    runner.sketch.view(&Context::from_app(app), &draw);
+    draw.to_frame(app, &frame).unwrap();
+}
+
+/// Runs a sketch without a window, with a clock that only moves when it is stepped.
+/// Useful for tests, benchmarks and offline renders.
+pub struct Headless<S> {
+    pub sketch: S,
+    pub ctx: Context,
+}
+
+impl<S: Sketch> Headless<S> {
+    pub fn new() -> Self {
+        let (w, h) = S::SIZE;
+        let ctx = Context::new(w as f32, h as f32);
+        Headless {
+            sketch: S::init(&ctx),
+            ctx,
+        }
+    }
+
+    /// Move the clock `dt` seconds and update the sketch.
+    pub fn step(&mut self, dt: f32) {
+        self.ctx.time += dt;
+        self.ctx.frame += 1;
+        self.sketch.update(&self.ctx, dt);
+    }
+
+    /// Step in steps of `dt` until the clock shows `time`, or the step closest to it.
+    pub fn run_until(&mut self, time: f32, dt: f32) {
+        //Half a step of slack, so rounding errors in the sum of many small steps don't cost a
+        // step
+        while self.ctx.time + dt * 0.5 < time {
+            self.step(dt);
+        }
+    }
+
+    pub fn event(&mut self, event: WindowEvent) {
+        self.sketch.on_event(&self.ctx, &event);
+    }
+
+    /// Draw the current frame into `draw`.
+    pub fn view(&self, draw: &Draw) {
+        self.sketch.view(&self.ctx, draw);
+    }
+}
+
+impl<S: Sketch> Default for Headless<S> {
+    fn default() -> Self {
+        Headless::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Counter {
+        updates: u32,
+        elapsed: f32,
+        presses: u32,
+    }
+
+    impl Sketch for Counter {
+        const SIZE: (u32, u32) = (200, 100);
+
+        fn init(_ctx: &Context) -> Self {
+            Counter {
+                updates: 0,
+                elapsed: 0.0,
+                presses: 0,
+            }
+        }
+
+        fn update(&mut self, _ctx: &Context, dt: f32) {
+            self.updates += 1;
+            self.elapsed += dt;
+        }
+
+        fn view(&self, ctx: &Context, draw: &Draw) {
+            draw.ellipse().x(ctx.time).radius(self.updates as f32);
+        }
+
+        fn on_event(&mut self, _ctx: &Context, event: &WindowEvent) {
+            if let KeyPressed(_) = event {
+                self.presses += 1;
+            }
+        }
+    }
+
+    #[test]
+    fn starts_at_zero() {
+        let headless = Headless::<Counter>::new();
+        assert_eq!(headless.ctx.time, 0.0);
+        assert_eq!(headless.ctx.frame, 0);
+        assert_eq!(headless.ctx.window_rect, Rect::from_w_h(200.0, 100.0));
+        assert_eq!(headless.sketch.updates, 0);
+    }
+
+    #[test]
+    fn steps_move_the_clock() {
+        let mut headless = Headless::<Counter>::new();
+        headless.step(0.5);
+        headless.step(0.25);
+        assert_eq!(headless.ctx.time, 0.75);
+        assert_eq!(headless.ctx.frame, 2);
+        assert_eq!(headless.sketch.updates, 2);
+        assert_eq!(headless.sketch.elapsed, 0.75);
+    }
+
+    #[test]
+    fn runs_until_a_time() {
+        let mut headless = Headless::<Counter>::new();
+        headless.run_until(1.0, 1.0 / 60.0);
+        assert_eq!(headless.ctx.frame, 60);
+        assert!((headless.ctx.time - 1.0).abs() < 1e-4);
+        //Running until a time that has passed does nothing
+        headless.run_until(0.5, 1.0 / 60.0);
+        assert_eq!(headless.ctx.frame, 60);
+
+        let mut headless = Headless::<Counter>::new();
+        headless.run_until(2.0, 0.25);
+        assert_eq!(headless.ctx.frame, 8);
+        assert_eq!(headless.ctx.time, 2.0);
+        //The step closest to the time
+        headless.run_until(2.3, 0.25);
+        assert_eq!(headless.ctx.time, 2.25);
+    }
+
+    #[test]
+    fn events_reach_the_sketch() {
+        let mut headless = Headless::<Counter>::new();
+        headless.event(KeyPressed(Key::Space));
+        headless.event(KeyReleased(Key::Space));
+        headless.event(KeyPressed(Key::A));
+        assert_eq!(headless.sketch.presses, 2);
+    }
+}
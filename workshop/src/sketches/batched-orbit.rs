@@ -0,0 +1,44 @@
+use nannou::prelude::*;
+use workshop::batch::{ShapeBatch, DEFAULT_RESOLUTION};
+
+fn main() {
+    nannou::app(model)
+        .update(update)
+        .simple_window(view)
+        .run();
+}
+
+//The orbit from `p0/src/main.rs`, with 50 000 circles drawn as one batch
+const NUM_ITEMS: usize = 50_000;
+
+struct Model {
+    batch: ShapeBatch,
+}
+
+fn model(_app: &App) -> Model {
+    Model {
+        batch: ShapeBatch::circles(DEFAULT_RESOLUTION).with_capacity(NUM_ITEMS),
+    }
+}
+
+//The batch is filled again every frame. `clear` keeps the memory, so this doesn't allocate.
+fn update(app: &App, model: &mut Model, _update: Update) {
+    model.batch.clear();
+    for i in 0..NUM_ITEMS {
+        let t = i as f32 / NUM_ITEMS as f32;
+        let angle = t * TAU * 7.0 + app.time * (0.2 + t);
+        let rotation_radius = 50.0 + t * 300.0;
+        model.batch.push_circle(
+            vec2(angle.cos(), angle.sin()) * rotation_radius,
+            2.0 + (t * 40.0 + app.time).sin().abs() * 2.0,
+            hsv(t, 0.8, 1.0),
+        );
+    }
+}
+
+fn view(app: &App, model: &Model, frame: Frame) {
+    let draw = app.draw();
+    draw.background().color(BLACK);
+    model.batch.draw(&draw);
+    draw.to_frame(app, &frame).unwrap();
+}
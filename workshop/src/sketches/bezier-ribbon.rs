@@ -0,0 +1,71 @@
+use nannou::prelude::*;
+use workshop::bezier::CubicBezier;
+
+fn main() {
+    nannou::app(model)
+        .update(update)
+        .simple_window(view)
+        .run();
+}
+
+struct Model {}
+
+fn model(_app: &App) -> Model {
+    Model{}
+}
+
+fn update(_app: &App, _model: &mut Model, _update: Update) {}
+
+//The curve from `p2_b`, drawn as a ribbon that is thicker in the middle, with evenly spaced dots
+// along it.
+fn view(app: &App, _model: &Model, frame: Frame) {
+    let draw = app.draw();
+    let win = app.window_rect();
+
+    draw.background().color(BLACK);
+
+    let width = (app.time * 0.05).sin() * 400.0 + 40.0;
+    let curve = CubicBezier::new(
+        vec2(0.0, win.bottom() + 20.0),
+        vec2((app.time * 0.3 ).cos() * width, (app.time * 0.1).sin() * width),
+        vec2((app.time * 0.3 + 0.1).sin() * width, (app.time * 0.1).cos() * width),
+        vec2(0.0, win.top() - 20.0),
+    );
+
+    //A sine shaped thickness: thin at the ends and 30 pixels in the middle
+    let ribbon = curve.ribbon(100, |t| (t * PI).sin() * 30.0);
+    draw.polygon()
+        .points(ribbon)
+        .color(rgba(1.0, 1.0, 1.0, 0.2));
+
+    //Dots with the same distance between them, no matter how the curve bends.
+    // The dots crawl along the curve with the time.
+    let table = curve.arc_length_table(200);
+    let spacing = 25.0;
+    let crawl = (app.time * 40.0) % spacing;
+    let count = ((table.length() - crawl) / spacing) as usize + 1;
+    for i in 0..count {
+        let t = table.t_at_length(crawl + i as f32 * spacing);
+        let p = curve.point(t);
+        draw.ellipse()
+            .xy(p)
+            .radius(3.0)
+            .color(WHITE);
+        //A short line along the normal of the curve
+        draw.line()
+            .start(p)
+            .end(p + curve.normal(t) * 10.0)
+            .weight(1.0)
+            .color(ORANGE);
+    }
+
+    let bounds = curve.bounding_rect();
+    draw.rect()
+        .xy(bounds.xy())
+        .wh(bounds.wh())
+        .no_fill()
+        .stroke(rgba(0.0, 1.0, 1.0, 0.5))
+        .stroke_weight(1.0);
+
+    draw.to_frame(app, &frame).unwrap();
+}
@@ -0,0 +1,112 @@
+use nannou::lyon::math::point;
+use nannou::lyon::path::Path;
+use nannou::math::ConvertAngle;
+use nannou::prelude::*;
+use workshop::geometry::{GeometryCache, Mesh};
+
+fn main() {
+    nannou::app(model)
+        .update(update)
+        .run();
+}
+
+const NUM_ARMS: u16 = 12;
+//The control points are rounded to this many pixels. The kaleidoscope is drawn at half the
+// size, so that is less than a pixel in the window
+const QUANTUM: f32 = 2.0;
+
+//The kaleidoscope from `p2_d`, where the bezier is tessellated once and drawn for every arm.
+// The shape of the bezier changes with time like in `p2_d`, but the mesh is only made again
+// when a control point moves to another rounded position, instead of 24 times every frame.
+// Press Space to pause and see how many times the mesh was reused.
+struct Model {
+    //The rounded control points of the bezier and the height of the window
+    cache: GeometryCache<[i32; 5]>,
+    time: f32,
+    paused: bool,
+}
+
+fn model(app: &App) -> Model {
+    app.new_window()
+        .size(1024, 768)
+        .view(view)
+        .key_pressed(key_pressed)
+        .build()
+        .unwrap();
+    Model {
+        cache: GeometryCache::new(),
+        time: 0.0,
+        paused: false,
+    }
+}
+
+fn key_pressed(_app: &App, model: &mut Model, key: Key) {
+    if key == Key::Space {
+        model.paused = !model.paused;
+        println!(
+            "Tessellated {} times, reused {} times",
+            model.cache.misses(),
+            model.cache.hits()
+        );
+    }
+}
+
+fn update(app: &App, model: &mut Model, update: Update) {
+    if !model.paused {
+        model.time += update.since_last.as_secs_f32();
+    }
+    //The path only depends on these, so they are the key of the cache
+    let key = bezier_key(model.time, app.window_rect().h());
+    model.cache.get(key, || Mesh::stroke(&bezier(key), 1.0));
+}
+
+//The control points of the bezier in `p2_d` at `time`, and the height of the window, in steps
+// of `QUANTUM` pixels
+fn bezier_key(time: f32, h: f32) -> [i32; 5] {
+    let width = (time * 0.05).sin() * 400.0 + 40.0;
+    let round = |v: f32| (v / QUANTUM).round() as i32;
+    [
+        round((time * 0.3).cos() * width),
+        round((time * 0.1).sin() * width),
+        round((time * 0.3 + 0.1).sin() * width),
+        round((time * 0.1).cos() * width),
+        round(h),
+    ]
+}
+
+fn bezier([ax, ay, bx, by, h]: [i32; 5]) -> Path {
+    let at = |x: i32, y: i32| point(x as f32 * QUANTUM, y as f32 * QUANTUM);
+    let h = h as f32 * QUANTUM;
+    let mut builder = nannou::geom::path::Builder::new().with_svg();
+    builder.move_to(point(0.0, -h * 0.5));
+    builder.cubic_bezier_to(at(ax, ay), at(bx, by), point(0.0, h * 0.5 - 20.0));
+    builder.build().into()
+}
+
+fn view(app: &App, model: &Model, frame: Frame) {
+    let draw = app.draw();
+    let win = app.window_rect();
+
+    draw.rect()
+        .x_y(0.0, 0.0)
+        .w_h(win.w(), win.h())
+        .color(rgba(0.0, 0.0, 0.0, 0.01));
+
+    //`update` has already made the mesh for this frame
+    let mesh = match model.cache.mesh() {
+        Some(mesh) => mesh,
+        None => return,
+    };
+
+    //The same transforms as the draw contexts in `p2_d`, one for each arm and one mirrored
+    let transforms = (0..NUM_ARMS).flat_map(|i| {
+        let angle = (i as f32 * (360.0 / NUM_ARMS as f32)).deg_to_rad();
+        let arm = Mat4::from_scale(Vec3::splat(0.5))
+            * Mat4::from_rotation_z(angle)
+            * Mat4::from_translation(vec3(0.0, win.h() * 0.5, 0.0));
+        [arm, arm * Mat4::from_scale(vec3(-1.0, 1.0, 1.0))]
+    });
+    mesh.draw_instances(&draw, transforms, WHITE);
+
+    draw.to_frame(app, &frame).unwrap();
+}
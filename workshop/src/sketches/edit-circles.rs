@@ -0,0 +1,180 @@
+use nannou::prelude::*;
+use workshop::history::{Command, History};
+
+fn main() {
+    nannou::app(model)
+        .update(update)
+        .run();
+}
+
+//Click to add a circle, drag to move it, scroll to resize it, and right click to remove it.
+// Ctrl+Z undoes, and Ctrl+Shift+Z or Ctrl+Y redoes.
+struct Model {
+    circles: Vec<Circle>,
+    history: History<Edit>,
+    //The circle being dragged, and where the mouse grabbed it
+    dragging: Option<(usize, Vec2)>,
+}
+
+#[derive(Clone, Debug)]
+struct Circle {
+    position: Vec2,
+    radius: f32,
+    color: Rgb<u8>,
+}
+
+//Every change to the circles is one of these
+enum Edit {
+    Add { index: usize, circle: Circle },
+    Remove { index: usize, circle: Option<Circle> },
+    Move { index: usize, from: Vec2, to: Vec2 },
+    Resize { index: usize, from: f32, to: f32 },
+}
+
+impl Command<Vec<Circle>> for Edit {
+    fn apply(&mut self, circles: &mut Vec<Circle>) {
+        match self {
+            Edit::Add { index, circle } => {
+                *index = circles.len();
+                circles.push(circle.clone());
+            }
+            Edit::Remove { index, circle } => *circle = Some(circles.remove(*index)),
+            Edit::Move { index, to, .. } => circles[*index].position = *to,
+            Edit::Resize { index, to, .. } => circles[*index].radius = *to,
+        }
+    }
+
+    fn undo(&mut self, circles: &mut Vec<Circle>) {
+        match self {
+            Edit::Add { index, .. } => {
+                circles.remove(*index);
+            }
+            Edit::Remove { index, circle } => {
+                if let Some(circle) = circle.take() {
+                    circles.insert(*index, circle);
+                }
+            }
+            Edit::Move { index, from, .. } => circles[*index].position = *from,
+            Edit::Resize { index, from, .. } => circles[*index].radius = *from,
+        }
+    }
+
+    //Moving or resizing the same circle twice in a row is one edit
+    fn merge(&mut self, next: &Self) -> bool {
+        match (self, next) {
+            (Edit::Move { index, to, .. }, Edit::Move { index: i, to: t, .. }) if index == i => {
+                *to = *t;
+                true
+            }
+            (Edit::Resize { index, to, .. }, Edit::Resize { index: i, to: t, .. }) if index == i => {
+                *to = *t;
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+fn model(app: &App) -> Model {
+    app.new_window()
+        .size(1024, 720)
+        .view(view)
+        .key_pressed(key_pressed)
+        .mouse_pressed(mouse_pressed)
+        .mouse_released(mouse_released)
+        .mouse_moved(mouse_moved)
+        .mouse_wheel(mouse_wheel)
+        .build()
+        .unwrap();
+    Model {
+        circles: Vec::new(),
+        history: History::new().capacity(100),
+        dragging: None,
+    }
+}
+
+fn update(_app: &App, _model: &mut Model, _update: Update) {}
+
+//The top most circle under the mouse
+fn circle_at(circles: &[Circle], p: Vec2) -> Option<usize> {
+    circles.iter().rposition(|c| c.position.distance(p) <= c.radius)
+}
+
+fn key_pressed(app: &App, model: &mut Model, key: Key) {
+    if model.history.key_pressed(app, &mut model.circles, key) {
+        model.dragging = None;
+    }
+}
+
+fn mouse_pressed(app: &App, model: &mut Model, button: MouseButton) {
+    let mouse = app.mouse.position();
+    match (button, circle_at(&model.circles, mouse)) {
+        (MouseButton::Left, Some(index)) => {
+            model.history.begin_group();
+            model.dragging = Some((index, mouse - model.circles[index].position));
+        }
+        (MouseButton::Left, None) => {
+            let colors = [MAGENTA, ORANGE, YELLOW, WHITE];
+            let circle = Circle {
+                position: mouse,
+                radius: 30.0,
+                color: colors[model.circles.len() % colors.len()],
+            };
+            model
+                .history
+                .execute(&mut model.circles, Edit::Add { index: 0, circle });
+        }
+        (MouseButton::Right, Some(index)) => {
+            model
+                .history
+                .execute(&mut model.circles, Edit::Remove { index, circle: None });
+        }
+        _ => {}
+    }
+}
+
+fn mouse_released(_app: &App, model: &mut Model, _button: MouseButton) {
+    if model.dragging.take().is_some() {
+        model.history.end_group();
+    }
+}
+
+fn mouse_moved(_app: &App, model: &mut Model, position: Point2) {
+    if let Some((index, grab)) = model.dragging {
+        let from = model.circles[index].position;
+        let to = position - grab;
+        model
+            .history
+            .execute(&mut model.circles, Edit::Move { index, from, to });
+    }
+}
+
+fn mouse_wheel(app: &App, model: &mut Model, delta: MouseScrollDelta, _phase: TouchPhase) {
+    let index = match circle_at(&model.circles, app.mouse.position()) {
+        Some(index) => index,
+        None => return,
+    };
+    let amount = match delta {
+        MouseScrollDelta::LineDelta(_, y) => y * 5.0,
+        MouseScrollDelta::PixelDelta(p) => p.y as f32 * 0.5,
+    };
+    let from = model.circles[index].radius;
+    let to = (from + amount).max(5.0);
+    model
+        .history
+        .execute(&mut model.circles, Edit::Resize { index, from, to });
+}
+
+fn view(app: &App, model: &Model, frame: Frame){
+    let draw = app.draw();
+    draw.background().color(CYAN);
+
+    for circle in model.circles.iter() {
+        draw.ellipse()
+            .xy(circle.position)
+            .radius(circle.radius)
+            .color(circle.color);
+    }
+
+    draw.to_frame(app, &frame).unwrap();
+}
@@ -0,0 +1,96 @@
+use nannou::prelude::*;
+use workshop::noise::Simplex;
+use workshop::particles::{Curve, Emitter, NoiseFlow, ParticleSystem};
+use workshop::rng::Randomness;
+
+fn main() {
+    nannou::app(model)
+        .update(update)
+        .simple_window(view)
+        .run();
+}
+
+//The circles from `p1_e`, now trailing particles through a noise field
+struct Model {
+    circle_a: Circle,
+    circle_b: Circle,
+    particles: ParticleSystem,
+    flow: NoiseFlow<Simplex>,
+}
+
+struct Circle {
+    position: Vec2,
+    speed: f32,
+    radius: f32,
+    color: Rgb<u8>,
+}
+
+fn model(_app: &App) -> Model {
+    let mut randomness = Randomness::from_args();
+    let a = Circle {
+        position: vec2(0.0, 0.0),
+        speed: 1.0,
+        radius: 50.0,
+        color: MAGENTA,
+    };
+    let b = Circle {
+        position: vec2(0.0, 0.0),
+        speed: a.speed * 2.0,
+        radius: a.radius / 2.0,
+        color: ORANGE,
+    };
+    let particles = ParticleSystem::new(randomness.stream("particles").clone())
+        .emitter(Emitter::new(a.position, 400.0).spread(a.radius))
+        .emitter(Emitter::new(b.position, 200.0).spread(b.radius))
+        .color_over_life(Curve::new(vec![
+            (0.0, lin_srgba(1.0, 1.0, 1.0, 0.0)),
+            (0.1, lin_srgba(1.0, 0.6, 0.9, 0.8)),
+            (1.0, lin_srgba(0.2, 0.4, 1.0, 0.0)),
+        ]))
+        .size_over_life(Curve::linear(3.0, 0.5))
+        .trail_length(8);
+    let flow = NoiseFlow::new(Simplex::new(randomness.derived_seed("flow")));
+    Model {
+        circle_a: a,
+        circle_b: b,
+        particles,
+        flow,
+    }
+}
+
+fn update(app: &App, model: &mut Model, update: Update) {
+    let r = app.window_rect();
+    let time = app.time;
+
+    for circle in [&mut model.circle_a, &mut model.circle_b] {
+        let ts = time * circle.speed;
+        let radius = circle.radius;
+        circle.position = vec2(
+            map_range( ts.sin(), -1.0, 1.0, r.left() + radius, r.right() - radius ),
+            map_range( ts.cos(), -1.0, 1.0, r.bottom() + radius, r.top() - radius ),
+            );
+    }
+
+    //Keep the emitters on the circles
+    model.particles.emitters[0].position = model.circle_a.position;
+    model.particles.emitters[1].position = model.circle_b.position;
+    model.particles
+        .update(&model.flow, update.since_last.as_secs_f32(), time);
+}
+
+fn view(app: &App, model: &Model, frame: Frame){
+    let draw = app.draw();
+
+    draw.background().color(BLACK);
+
+    model.particles.draw(&draw);
+
+    for circle in [&model.circle_a, &model.circle_b] {
+        draw.ellipse()
+            .xy(circle.position)
+            .radius(circle.radius)
+            .color(circle.color);
+    }
+
+    draw.to_frame(app, &frame).unwrap();
+}
@@ -0,0 +1,158 @@
+use nannou::color::{Hue, Lab, Lch};
+use nannou::prelude::*;
+use workshop::batch::{ShapeBatch, DEFAULT_RESOLUTION};
+use workshop::image::{self, Dot, Halftone, Image, PaletteColor, CRAB_PATH};
+use workshop::layout::{Anchor, Fit, Grid};
+use workshop::rng::SketchRng;
+use workshop::text::Label;
+
+fn main() {
+    nannou::app(model)
+        .update(update)
+        .run();
+}
+
+//The crab from the `images` folder, drawn with circles.
+// Press Space to switch between a black and white halftone, a colour halftone, pointillism,
+// and a halftone in the main colours of the crab, with the hue turning like in `p2_e`.
+// The main colours are shown at the bottom, as `Lch` values.
+struct Model {
+    image: Image,
+    palette: Vec<PaletteColor>,
+    mode: Mode,
+    dots: Vec<Dot>,
+    //The window rect and mode the dots were made for
+    dots_for: Option<(Rect, Mode)>,
+    batch: ShapeBatch,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum Mode {
+    Halftone,
+    ColorHalftone,
+    Pointillism,
+    Palette,
+}
+
+const PALETTE_HEIGHT: f32 = 60.0;
+
+fn model(app: &App) -> Model {
+    app.new_window()
+        .size(1024, 768)
+        .view(view)
+        .key_pressed(key_pressed)
+        .build()
+        .unwrap();
+    let image = Image::open(CRAB_PATH).expect("the crab should be in the images folder");
+    let palette = image::palette(&image, 6, 4096, &mut SketchRng::from_seed(1));
+    Model {
+        image,
+        palette,
+        mode: Mode::Halftone,
+        dots: Vec::new(),
+        dots_for: None,
+        batch: ShapeBatch::circles(DEFAULT_RESOLUTION),
+    }
+}
+
+fn key_pressed(_app: &App, model: &mut Model, key: Key) {
+    if key == Key::Space {
+        model.mode = match model.mode {
+            Mode::Halftone => Mode::ColorHalftone,
+            Mode::ColorHalftone => Mode::Pointillism,
+            Mode::Pointillism => Mode::Palette,
+            Mode::Palette => Mode::Halftone,
+        };
+    }
+}
+
+fn update(app: &App, model: &mut Model, _update: Update) {
+    let win = app.window_rect();
+    let area = Rect::from_x_y_w_h(
+        win.x(),
+        win.y() + PALETTE_HEIGHT * 0.5,
+        win.w(),
+        win.h() - PALETTE_HEIGHT,
+    );
+    let rect = model.image.rect_in(area.pad(20.0), Fit::Contain);
+
+    //Looking up thousands of pixels takes a moment, so only make the dots again when needed
+    if model.dots_for != Some((win, model.mode)) {
+        model.dots = match model.mode {
+            Mode::Halftone => Halftone::new(8.0).dots(&model.image, rect),
+            Mode::ColorHalftone | Mode::Palette => {
+                Halftone::new(8.0).colored(true).dots(&model.image, rect)
+            }
+            Mode::Pointillism => {
+                let mut rng = SketchRng::from_seed(2);
+                image::pointillism(&model.image, rect, 20_000, 5.0, &mut rng)
+            }
+        };
+        model.dots_for = Some((win, model.mode));
+    }
+
+    model.batch.clear();
+    if model.mode == Mode::Palette {
+        //Each dot gets the palette colour closest to it, and all the hues turn together
+        let turn = app.time * 20.0;
+        for dot in model.dots.iter() {
+            let color = closest(&model.palette, dot).shift_hue(turn);
+            model.batch.push_circle(dot.position, dot.radius, color);
+        }
+    } else {
+        image::push_dots(&mut model.batch, &model.dots);
+    }
+}
+
+fn closest(palette: &[PaletteColor], dot: &Dot) -> Lch {
+    let c = dot.color;
+    let lab: Lab = LinSrgb::new(c.red, c.green, c.blue).into();
+    //Compare in `Lab`, like `image::palette` does
+    let distance = |p: &PaletteColor| {
+        let other: Lab = p.color.into();
+        (other.l - lab.l).powi(2) + (other.a - lab.a).powi(2) + (other.b - lab.b).powi(2)
+    };
+    palette
+        .iter()
+        .min_by(|x, y| distance(x).total_cmp(&distance(y)))
+        .map(|p| p.color)
+        .unwrap_or_else(|| lab.into())
+}
+
+fn view(app: &App, model: &Model, frame: Frame) {
+    let draw = app.draw();
+    let win = app.window_rect();
+    let background = match model.mode {
+        Mode::Halftone => WHITE,
+        _ => BLACK,
+    };
+    draw.background().color(background);
+    model.batch.draw(&draw);
+
+    //The palette, with the most common colour first
+    let strip = Rect::from_x_y_w_h(
+        win.x(),
+        win.bottom() + PALETTE_HEIGHT * 0.5,
+        win.w(),
+        PALETTE_HEIGHT,
+    );
+    let grid = Grid::new(strip, 1, model.palette.len().max(1));
+    for (i, swatch) in model.palette.iter().enumerate() {
+        let cell = grid.cell(0, i);
+        draw.rect().xy(cell.xy()).wh(cell.wh()).color(swatch.color);
+        let text_color = if swatch.color.l > 50.0 { BLACK } else { WHITE };
+        Label::new(format!(
+            "L {:.0} C {:.0} h {:.0}\n{:.0}%",
+            swatch.color.l,
+            swatch.color.chroma,
+            swatch.color.hue.to_positive_degrees(),
+            swatch.share * 100.0
+        ))
+        .size(12)
+        .anchor(Anchor::Center)
+        .color(text_color)
+        .draw(&draw, cell);
+    }
+
+    draw.to_frame(app, &frame).unwrap();
+}
@@ -0,0 +1,155 @@
+use nannou::prelude::*;
+use workshop::bezier::CubicBezier;
+use workshop::layout::{self, Anchor};
+use workshop::text::{self, Fonts, Label, DEFAULT_FONT};
+
+fn main() {
+    nannou::app(model)
+        .update(update)
+        .run();
+}
+
+//The two circles from `p1_e` with their names, a title, and a line of text riding on a bezier.
+// Press O to save the outline of the title as `title.svg`, e.g. for a plotter.
+// Run with `-- --font <file.ttf>` to use another font.
+struct Model {
+    fonts: Fonts,
+    circles: Vec<Circle>,
+}
+
+struct Circle {
+    name: &'static str,
+    position: Vec2,
+    speed: f32,
+    radius: f32,
+    color: Rgb<u8>,
+}
+
+fn model(app: &App) -> Model {
+    app.new_window()
+        .size(1024, 768)
+        .view(view)
+        .key_pressed(key_pressed)
+        .build()
+        .unwrap();
+
+    let mut fonts = Fonts::new();
+    let args: Vec<String> = std::env::args().collect();
+    if let Some(i) = args.iter().position(|arg| arg == "--font") {
+        if let Some(path) = args.get(i + 1) {
+            if let Err(e) = fonts.load("title", path) {
+                eprintln!("Could not load {}: {}", path, e);
+            }
+        }
+    }
+
+    let a = Circle {
+        name: "Magenta",
+        position: Vec2::ZERO,
+        speed: 1.0,
+        radius: 50.0,
+        color: MAGENTA,
+    };
+    let b = Circle {
+        name: "Orange",
+        position: Vec2::ZERO,
+        speed: a.speed * 2.0,
+        radius: a.radius / 2.0,
+        color: ORANGE,
+    };
+    Model {
+        fonts,
+        circles: vec![a, b],
+    }
+}
+
+fn title(model: &Model) -> Label {
+    Label::new("Circles in orbit")
+        .font(model.fonts.get_or_default("title"))
+        .size(48)
+        .anchor(Anchor::TopLeft)
+        .color(BLACK)
+}
+
+fn key_pressed(app: &App, model: &mut Model, key: Key) {
+    if key == Key::O {
+        let win = app.window_rect();
+        let outline = title(model).outline(layout::safe_area(win, 0.05));
+        let svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" \
+             viewBox=\"{x} 0 {w} {h}\">\n\
+             <path d=\"{d}\" fill=\"none\" stroke=\"black\"/>\n</svg>\n",
+            w = win.w(),
+            h = win.h(),
+            x = win.left(),
+            d = text::svg_path_data(&outline, win.h()),
+        );
+        match std::fs::write("title.svg", svg) {
+            Ok(()) => println!("Saved title.svg"),
+            Err(e) => eprintln!("Could not save title.svg: {}", e),
+        }
+    }
+}
+
+fn update(app: &App, model: &mut Model, _update: Update) {
+    let r = app.window_rect();
+    for circle in model.circles.iter_mut() {
+        let ts = app.time * circle.speed;
+        let radius = circle.radius;
+        circle.position = vec2(
+            map_range(ts.sin(), -1.0, 1.0, r.left() + radius, r.right() - radius),
+            map_range(ts.cos(), -1.0, 1.0, r.bottom() + radius, r.top() - radius),
+        );
+    }
+}
+
+fn view(app: &App, model: &Model, frame: Frame) {
+    let draw = app.draw();
+    let win = app.window_rect();
+    draw.background().color(CYAN);
+
+    for circle in model.circles.iter() {
+        draw.ellipse()
+            .xy(circle.position)
+            .radius(circle.radius)
+            .color(circle.color);
+        //The name goes just above the circle
+        let above = Rect::from_x_y_w_h(
+            circle.position.x,
+            circle.position.y + circle.radius + 15.0,
+            200.0,
+            30.0,
+        );
+        Label::new(circle.name)
+            .size(16)
+            .anchor(Anchor::Bottom)
+            .color(BLACK)
+            .no_wrap()
+            .draw(&draw, above);
+    }
+
+    let safe = layout::safe_area(win, 0.05);
+    title(model).draw(&draw, safe);
+
+    //The hue from `p2_x`, as a number in the corner
+    let hue = ((app.time * 0.05).cos() * 0.5 + 0.5) * 360.0;
+    Label::new(format!("hue: {:.1}", hue))
+        .font(model.fonts.get_or_default(DEFAULT_FONT))
+        .size(16)
+        .anchor(Anchor::BottomLeft)
+        .color(BLACK)
+        .draw(&draw, safe);
+
+    //A curve between the circles, with text along it
+    let [a, b] = [model.circles[0].position, model.circles[1].position];
+    let normal = (b - a).perp() * 0.5;
+    let curve = CubicBezier::new(a, a.lerp(b, 0.33) + normal, a.lerp(b, 0.66) - normal, b);
+    draw.polyline()
+        .weight(1.0)
+        .color(WHITE)
+        .points((0..=32).map(|i| curve.point(i as f32 / 32.0)));
+    let label = Label::new("the long way round").size(20).color(BLACK);
+    text::draw_on_curve(&draw, &label, &curve, 10.0);
+
+    draw.to_frame(app, &frame).unwrap();
+}
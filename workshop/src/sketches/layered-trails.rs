@@ -0,0 +1,156 @@
+use std::f32::consts::SQRT_2;
+
+use nannou::color::{IntoLinSrgba, Lch};
+use nannou::image::{Rgba, RgbaImage};
+use nannou::lyon::math::point;
+use nannou::lyon::path::Path;
+use nannou::math::ConvertAngle;
+use nannou::prelude::*;
+use workshop::layers::{self, BlendMode, Layer, Layers};
+
+fn main() {
+    nannou::app(model)
+        .update(update)
+        .run();
+}
+
+const NUM_ARMS: u16 = 12;
+
+//The kaleidoscope from `p2_e` on three layers:
+// "trails" is never cleared and fades out slowly, like the transparent rect in `p2_e`,
+// "arms" is cleared every frame, so the newest arms stay crisp on top of the trails,
+// "vignette" is an image made on the CPU that darkens the corners.
+//
+// Press 1, 2 and 3 to hide and show the layers, Up and Down to change how fast the trails fade,
+// and B to change how the trails are blended.
+struct Model {
+    layers: Layers,
+    fade: f32,
+}
+
+fn model(app: &App) -> Model {
+    let id = app
+        .new_window()
+        .size(1024, 768)
+        .view(view)
+        .key_pressed(key_pressed)
+        .build()
+        .unwrap();
+    let window = app.window(id).unwrap();
+
+    let mut layers = Layers::new();
+    layers.add(Layer::texture(&window, "trails").blend(BlendMode::Screen).order(0));
+    layers.add(Layer::texture(&window, "arms").order(1));
+    layers.add(
+        Layer::image(&window, "vignette", vignette(256, 192))
+            .blend(BlendMode::Multiply)
+            .order(2),
+    );
+    Model { layers, fade: 0.01 }
+}
+
+//White in the middle, so multiplying with it changes nothing, and darker towards the corners
+fn vignette(w: u32, h: u32) -> RgbaImage {
+    RgbaImage::from_fn(w, h, |x, y| {
+        let uv = vec2(x as f32 / w as f32, y as f32 / h as f32) * 2.0 - 1.0;
+        let d = (uv.length() / SQRT_2).powf(2.0);
+        let v = ((1.0 - d) * 255.0) as u8;
+        Rgba([v, v, v, 255])
+    })
+}
+
+fn key_pressed(_app: &App, model: &mut Model, key: Key) {
+    let name = match key {
+        Key::Key1 => "trails",
+        Key::Key2 => "arms",
+        Key::Key3 => "vignette",
+        Key::Up => {
+            model.fade = (model.fade * 1.5).min(1.0);
+            return;
+        }
+        Key::Down => {
+            model.fade = (model.fade / 1.5).max(0.001);
+            return;
+        }
+        Key::B => {
+            if let Some(layer) = model.layers.get_mut("trails") {
+                layer.blend = match layer.blend {
+                    BlendMode::Normal => BlendMode::Add,
+                    BlendMode::Add => BlendMode::Multiply,
+                    BlendMode::Multiply => BlendMode::Screen,
+                    BlendMode::Screen => BlendMode::Normal,
+                };
+                println!("Trails blend: {:?}", layer.blend);
+            }
+            return;
+        }
+        _ => return,
+    };
+    if let Some(layer) = model.layers.get_mut(name) {
+        layer.visible = !layer.visible;
+    }
+}
+
+fn update(app: &App, model: &mut Model, _update: Update) {
+    let window = app.main_window();
+    let win = window.rect();
+
+    let path = bezier(app.time, win);
+    let hue = (app.time * 0.05).cos() * 0.5 + 0.5;
+    let hue = (hue * 360.0 - 180.0) * 10.0;
+    let color = Lch::new(90.0, 10.0, hue);
+
+    //The trails keep what was drawn before, and only fade a little every frame
+    let fade = model.fade;
+    if let Some(trails) = model.layers.get_mut("trails") {
+        trails.render_over(&window, |draw| {
+            layers::fade(draw, win, fade);
+            draw_arms(draw, win, &path, color, 1.0);
+        });
+    }
+    if let Some(arms) = model.layers.get_mut("arms") {
+        arms.render(&window, |draw| {
+            draw_arms(draw, win, &path, WHITE, 2.0);
+        });
+    }
+    model.layers.upload(&window);
+}
+
+fn bezier(time: f32, win: Rect) -> Path {
+    let mut builder = nannou::geom::path::Builder::new().with_svg();
+    builder.move_to(point(0.0, win.bottom()));
+    let width = (time * 0.05).sin() * 400.0 + 40.0;
+    builder.cubic_bezier_to(
+        point((time * 0.3).cos() * width, (time * 0.1).sin() * width),
+        point((time * 0.3 + 0.1).sin() * width, (time * 0.1).cos() * width),
+        point(0.0, win.top() - 20.0),
+    );
+    builder.build().into()
+}
+
+fn draw_arms<C>(draw: &Draw, win: Rect, path: &Path, color: C, weight: f32)
+where
+    C: IntoLinSrgba<f32> + Copy,
+{
+    let draw = draw.scale(0.5);
+    for i in 0..NUM_ARMS {
+        let angle = i as f32 * (360.0 / NUM_ARMS as f32);
+        let arm = draw
+            .rotate(angle.deg_to_rad())
+            .translate(vec3(0.0, win.h() * 0.5, 0.0));
+        for arm in [arm.clone(), arm.scale_x(-1.0)] {
+            arm.path()
+                .stroke()
+                .color(color)
+                .weight(weight)
+                .events(path.iter());
+        }
+    }
+}
+
+fn view(app: &App, model: &Model, frame: Frame) {
+    let draw = app.draw();
+    draw.background().color(BLACK);
+    model.layers.draw(&draw, app.window_rect());
+    draw.to_frame(app, &frame).unwrap();
+}
@@ -0,0 +1,80 @@
+use nannou::prelude::*;
+use workshop::layout::{self, Anchor, DesignSpace, Fit, Grid};
+
+fn main() {
+    nannou::app(model)
+        .update(update)
+        .run();
+}
+
+//The sketch is designed for a 1024x720 window, and scales to whatever size the window has
+struct Model {
+    design: DesignSpace,
+}
+
+fn model(app: &App) -> Model {
+    app.new_window()
+        .size(1024, 720)
+        .view(view)
+        .key_pressed(key_pressed)
+        .build()
+        .unwrap();
+    Model {
+        design: DesignSpace::new(1024.0, 720.0),
+    }
+}
+
+fn update(_app: &App, _model: &mut Model, _update: Update) {}
+
+//Press F to switch between showing the whole design and filling the window
+fn key_pressed(_app: &App, model: &mut Model, key: Key) {
+    if key == Key::F {
+        let fit = match model.design.fit {
+            Fit::Contain => Fit::Cover,
+            _ => Fit::Contain,
+        };
+        model.design = model.design.fit(fit);
+    }
+}
+
+fn view(app: &App, model: &Model, frame: Frame){
+    let win = app.window_rect();
+    let draw = app.draw();
+    draw.background().color(BLACK);
+
+    //From here on everything is in design coordinates
+    let draw = model.design.apply(&draw, win);
+    let design = model.design.rect();
+    draw.rect().xy(design.xy()).wh(design.wh()).color(CYAN);
+
+    //Keep everything away from the edges, in case a projector cuts them off
+    let safe = layout::safe_area(design, 0.05);
+    let grid = Grid::new(safe, 2, 4).gutter(20.0);
+    for (i, cell) in grid.cells().enumerate() {
+        draw.rect()
+            .xy(cell.xy())
+            .wh(cell.wh())
+            .color(rgba(1.0, 1.0, 1.0, 0.2));
+
+        //A building standing on the bottom of each cell, like in `p0_g`
+        let height = cell.h() * (0.4 + 0.15 * (i % 4) as f32);
+        let building = layout::place(vec2(cell.w() * 0.5, height), Anchor::Bottom, cell, Vec2::ZERO);
+        draw.rect()
+            .xy(building.xy())
+            .wh(building.wh())
+            .color(MAGENTA);
+    }
+
+    //The mouse is in window coordinates, so it has to be converted
+    let mouse = model.design.to_design(win, app.mouse.position());
+    draw.ellipse().xy(mouse).radius(10.0).color(YELLOW);
+
+    let label = layout::place(vec2(300.0, 30.0), Anchor::TopRight, safe, vec2(0.0, 30.0));
+    draw.text("press F to fill / fit")
+        .xy(label.xy())
+        .wh(label.wh())
+        .right_justify()
+        .color(BLACK);
+
+    draw.to_frame(app, &frame).unwrap();
+}
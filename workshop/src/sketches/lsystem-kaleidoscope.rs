@@ -0,0 +1,86 @@
+use nannou::math::ConvertAngle;
+use nannou::prelude::*;
+use workshop::lsystem::{self, Turtle, TurtleDrawing};
+use workshop::rng::Randomness;
+
+fn main() {
+    nannou::app(model)
+        .update(update)
+        .run();
+}
+
+//The kaleidoscope from `p2_d`, with an L-system instead of the bezier line.
+// Press space to go to the next L-system.
+struct Model {
+    drawings: Vec<TurtleDrawing>,
+    current: usize,
+}
+
+fn model(app: &App) -> Model {
+    let _win = app
+        .new_window()
+        .size(1024, 720)
+        .view(view)
+        .key_pressed(key_pressed)
+        .build()
+        .unwrap();
+    let mut randomness = Randomness::from_args();
+    let rng = randomness.stream("lsystem");
+
+    //The L-systems are only generated once, since they don't change over time
+    let drawings = vec![
+        Turtle::new(10.0, 25.0).draw(&lsystem::fractal_plant().generate(4, rng)),
+        Turtle::new(10.0, 25.7).draw(&lsystem::stochastic_bush().generate(4, rng)),
+        Turtle::new(10.0, 30.0).draw(&lsystem::parametric_tree(100.0, 5.0).generate(12, rng)),
+        Turtle::new(10.0, 60.0)
+            .heading(0.0)
+            .draw(&lsystem::koch_curve().generate(4, rng)),
+        Turtle::new(10.0, 90.0).draw(&lsystem::hilbert_curve().generate(4, rng)),
+    ];
+    Model {
+        drawings,
+        current: 0,
+    }
+}
+
+fn update(_app: &App, _model: &mut Model, _update: Update) {}
+
+fn key_pressed(_app: &App, model: &mut Model, key: Key) {
+    if key == Key::Space {
+        model.current = (model.current + 1) % model.drawings.len();
+    }
+}
+
+fn view(app: &App, model: &Model, frame: Frame) {
+    let draw = app.draw();
+    let win = app.window_rect();
+
+    draw.rect()
+        .x_y(0.0, 0.0)
+        .w_h(win.w(), win.h())
+        .color(rgba(0.0, 0.0, 0.0, 0.05));
+
+    let drawing = &model.drawings[model.current];
+    //Fit each arm within half the height of the window, with the bottom of the drawing in the
+    // center
+    let arm = Rect::from_w_h(win.h() * 0.5, win.h() * 0.5);
+    let scale = drawing.scale_to_fit(arm);
+    let offset = vec2(-drawing.bounds.x(), -drawing.bounds.bottom());
+
+    let breathing = (app.time * 0.2).sin() * 0.1 + 0.9;
+    let color = WHITE;
+    const NUM_ARMS: u16 = 12;
+    for i in 0..NUM_ARMS {
+        let angle = i as f32 * (360.0 / NUM_ARMS as f32);
+        draw.rotate(angle.deg_to_rad())
+            .scale(scale * breathing)
+            .translate(offset.extend(0.0))
+            .path()
+            .stroke()
+            .color(color)
+            .weight(1.0 / scale)
+            .events(drawing.path.iter());
+    }
+
+    draw.to_frame(app, &frame).unwrap();
+}
@@ -0,0 +1,67 @@
+use nannou::prelude::*;
+use workshop::noise::{self, Fbm, Simplex, Warp};
+use workshop::rng::Randomness;
+
+fn main() {
+    nannou::app(model)
+        .update(update)
+        .simple_window(view)
+        .run();
+}
+
+//The same bezier line as in `p2_b`, but the control points wander around using noise instead of
+// following the sines.
+struct Model {
+    field: Warp<Fbm<Simplex>, Simplex>,
+}
+
+fn model(_app: &App) -> Model {
+    let randomness = Randomness::from_args();
+    let source = Fbm::new(Simplex::new(randomness.derived_seed("control points")))
+        .octaves(3)
+        .frequency(1.0);
+    let warp = Simplex::new(randomness.derived_seed("warp"));
+    Model {
+        field: Warp::new(source, warp).strength(0.4),
+    }
+}
+
+fn update(_app: &App, _model: &mut Model, _update: Update) {}
+
+fn view(app: &App, model: &Model, frame: Frame) {
+    let draw = app.draw();
+    let win = app.window_rect();
+
+    draw.background().color(BLACK);
+    use nannou::lyon::math::point;
+
+    let mut builder = nannou::geom::path::Builder::new().with_svg();
+    builder.move_to(point(0.0, win.bottom() + 20.0));
+
+    let width = win.w() * 0.5;
+    let t = app.time * 0.1;
+    let control_a = noise::wander2(&model.field, t, 0.0) * width;
+    let control_b = noise::wander2(&model.field, t, 100.0) * width;
+
+    builder.cubic_bezier_to(
+        point(control_a.x, control_a.y),
+        point(control_b.x, control_b.y),
+        point(0.0, win.top() - 20.0)
+        );
+    let path = builder.build();
+
+    draw.path()
+        .stroke()
+        .color(WHITE)
+        .weight(1.0)
+        .events(path.iter());
+
+    draw.scale_x(-1.0)
+        .path()
+        .stroke()
+        .color(WHITE)
+        .weight(1.0)
+        .events(path.iter());
+
+    draw.to_frame(app, &frame).unwrap();
+}
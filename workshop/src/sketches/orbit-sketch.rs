@@ -0,0 +1,84 @@
+use nannou::prelude::*;
+use workshop::sketch::{self, Context, Param, Sketch};
+
+//The whole `main`: the runner makes the window and calls the sketch
+fn main() {
+    sketch::run::<Orbits>();
+}
+
+//The two circles from `p1_e` as a sketch type.
+// Press Up and Down to change the speed.
+struct Orbits {
+    circles: Vec<Circle>,
+    speed: f32,
+}
+
+struct Circle {
+    position: Vec2,
+    speed: f32,
+    radius: f32,
+    color: Rgb<u8>,
+}
+
+impl Sketch for Orbits {
+    const TITLE: &'static str = "orbit-sketch";
+
+    fn init(_ctx: &Context) -> Self {
+        let a = Circle {
+            position: Vec2::ZERO,
+            speed: 1.0,
+            radius: 50.0,
+            color: MAGENTA,
+        };
+        let b = Circle {
+            position: Vec2::ZERO,
+            speed: a.speed * 2.0,
+            radius: a.radius / 2.0,
+            color: ORANGE,
+        };
+        Orbits {
+            circles: vec![a, b],
+            speed: 1.0,
+        }
+    }
+
+    fn update(&mut self, ctx: &Context, _dt: f32) {
+        let r = ctx.window_rect;
+        for circle in self.circles.iter_mut() {
+            let ts = ctx.time * circle.speed * self.speed;
+            let radius = circle.radius;
+            circle.position = vec2(
+                map_range(ts.sin(), -1.0, 1.0, r.left() + radius, r.right() - radius),
+                map_range(ts.cos(), -1.0, 1.0, r.bottom() + radius, r.top() - radius),
+            );
+        }
+    }
+
+    fn view(&self, _ctx: &Context, draw: &Draw) {
+        draw.background().color(CYAN);
+        for circle in self.circles.iter() {
+            draw.ellipse()
+                .xy(circle.position)
+                .radius(circle.radius)
+                .color(circle.color);
+        }
+    }
+
+    fn on_event(&mut self, _ctx: &Context, event: &WindowEvent) {
+        let step = match event {
+            KeyPressed(Key::Up) => 0.1,
+            KeyPressed(Key::Down) => -0.1,
+            _ => return,
+        };
+        for mut param in self.params() {
+            if param.name == "speed" {
+                let value = *param.value + step;
+                param.set(value);
+            }
+        }
+    }
+
+    fn params(&mut self) -> Vec<Param<'_>> {
+        vec![Param::new("speed", &mut self.speed, 0.0..=4.0)]
+    }
+}
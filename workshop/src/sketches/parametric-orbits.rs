@@ -0,0 +1,68 @@
+use nannou::prelude::*;
+use workshop::curves::{
+    Harmonograph, Lissajous, ParametricCurve, Pendulum, Rose, Spirograph, Superformula,
+};
+
+fn main() {
+    nannou::app(model)
+        .update(update)
+        .simple_window(view)
+        .run();
+}
+
+//Six curves, each drawn as a path with a circle travelling along it
+struct Model {
+    curves: Vec<Box<dyn ParametricCurve>>,
+}
+
+fn model(_app: &App) -> Model {
+    let size = 80.0;
+    let curves: Vec<Box<dyn ParametricCurve>> = vec![
+        Box::new(Lissajous::new(3.0, 2.0, PI / 2.0, vec2(size, size))),
+        Box::new(Harmonograph::new(
+            vec![Pendulum::new(size * 0.5, 2.0, 0.0, 0.02), Pendulum::new(size * 0.5, 3.01, 1.0, 0.01)],
+            vec![Pendulum::new(size * 0.5, 3.0, 0.5, 0.015), Pendulum::new(size * 0.5, 2.0, 0.0, 0.02)],
+            120.0,
+        )),
+        Box::new(Rose::new(5, 3, size)),
+        Box::new(Spirograph::hypotrochoid(size, size * 0.3, size * 0.5)),
+        Box::new(Spirograph::epicycloid(size * 0.6, size * 0.15)),
+        Box::new(Superformula::new(7.0, 2.0, 7.0, 7.0, size)),
+    ];
+    Model { curves }
+}
+
+fn update(_app: &App, _model: &mut Model, _update: Update) {}
+
+fn view(app: &App, model: &Model, frame: Frame){
+    let draw = app.draw();
+    let win = app.window_rect();
+
+    draw.background().color(CYAN);
+
+    let columns = 3;
+    let cell_w = win.w() / columns as f32;
+    let cell_h = win.h() / 2.0;
+    for (i, curve) in model.curves.iter().enumerate() {
+        let center = vec2(
+            win.left() + cell_w * ((i % columns) as f32 + 0.5),
+            win.top() - cell_h * ((i / columns) as f32 + 0.5),
+        );
+        let draw = draw.xy(center);
+
+        draw.path()
+            .stroke()
+            .weight(1.0)
+            .color(rgba(0.0, 0.0, 0.0, 0.4))
+            .events(curve.to_path(1000).iter());
+
+        //Loop the time over the period of the curve, so the circle goes around and around
+        let t = (app.time * 0.5) % curve.period();
+        draw.ellipse()
+            .xy(curve.point(t))
+            .radius(8.0)
+            .color(MAGENTA);
+    }
+
+    draw.to_frame(app, &frame).unwrap();
+}
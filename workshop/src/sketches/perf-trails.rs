@@ -0,0 +1,116 @@
+use nannou::color::Lch;
+use nannou::lyon::math::point;
+use nannou::lyon::path::Path;
+use nannou::math::ConvertAngle;
+use nannou::prelude::*;
+use workshop::perf::PerfHud;
+use workshop::geometry::Mesh;
+use workshop::record::{self, record};
+
+fn main() {
+    nannou::app(model)
+        .update(update)
+        .run();
+}
+
+//The trails from `p2_e` with a performance overlay.
+// Press Up and Down to change the number of arms, H to hide the overlay, and run with
+// `-- --perf-csv perf.csv` to save the timing of every frame.
+struct Model {
+    hud: PerfHud,
+    num_arms: u16,
+}
+
+fn model(app: &App) -> Model {
+    app.new_window()
+        .size(1024, 720)
+        .view(view)
+        .key_pressed(key_pressed)
+        .build()
+        .unwrap();
+    Model {
+        hud: PerfHud::from_args(),
+        num_arms: 12,
+    }
+}
+
+fn key_pressed(_app: &App, model: &mut Model, key: Key) {
+    model.hud.key_pressed(key);
+    match key {
+        Key::Up => model.num_arms = model.num_arms.saturating_mul(2).min(4096),
+        Key::Down => model.num_arms = (model.num_arms / 2).max(1),
+        _ => {}
+    }
+}
+
+fn update(app: &App, model: &mut Model, update: Update) {
+    model.hud.frame(app, &update);
+    let _timer = model.hud.time_update();
+}
+
+fn view(app: &App, model: &Model, frame: Frame) {
+    let timer = model.hud.time_view();
+    let draw = app.draw();
+    let win = app.window_rect();
+    let path = bezier(win, app.time);
+    draw_trails(&draw, win, &path, app.time, model.num_arms);
+    model.hud.draw(&draw, win);
+    //Most of the work happens in `to_frame`, where nannou tessellates the paths and sends them
+    // to the GPU, so it is timed as well
+    draw.to_frame(app, &frame).unwrap();
+    drop(timer);
+
+    //Rendering takes the shapes out of the `Draw`, so the trails are drawn once more into a
+    // `Draw` of their own to count them. That is not timed
+    let recording = record(|draw| draw_trails(draw, win, &path, app.time, model.num_arms));
+    //nannou keeps the points of paths to itself, so the vertices of the arms are counted by
+    // tessellating the bezier once
+    let arm = Mesh::stroke(&path, 1.0).vertices.len();
+    let paths = recording.count::<record::Path>();
+    model
+        .hud
+        .count(recording.primitives.len(), recording.vertices() + arm * paths);
+}
+
+//The bezier line from `p2_d`
+fn bezier(win: Rect, time: f32) -> Path {
+    let mut builder = nannou::geom::path::Builder::new().with_svg();
+    builder.move_to(point(0.0, win.bottom()));
+    let width = (time * 0.05).sin() * 400.0 + 40.0;
+    let control_a = point((time * 0.3).cos() * width, (time * 0.1).sin() * width);
+    let control_b = point((time * 0.3 + 0.1).sin() * width, (time * 0.1).cos() * width);
+    builder.cubic_bezier_to(control_a, control_b, point(0.0, win.top() - 20.0));
+    builder.build().into()
+}
+
+fn draw_trails(draw: &Draw, win: Rect, path: &Path, time: f32, num_arms: u16) {
+    draw.rect()
+        .x_y(0.0, 0.0)
+        .w_h(win.w(), win.h())
+        .color(rgba(0.0, 0.0, 0.0, 0.01));
+
+    let hue = (time * 0.05).cos() * 0.5 + 0.5;
+    let hue = (hue * 360.0 - 180.0) * 10.0;
+    let color = Lch::new(90.0, 10.0, hue);
+
+    let arms = draw.scale(0.5);
+    for i in 0..num_arms {
+        let angle = i as f32 * (360.0 / num_arms as f32);
+        arms.rotate(angle.deg_to_rad())
+            .translate(vec3(0.0, win.h() * 0.5, 0.0))
+            .path()
+            .stroke()
+            .color(color)
+            .weight(1.0)
+            .events(path.iter());
+
+        arms.rotate(angle.deg_to_rad())
+            .translate(vec3(0.0, win.h() * 0.5, 0.0))
+            .scale_x(-1.0)
+            .path()
+            .stroke()
+            .color(color)
+            .weight(1.0)
+            .events(path.iter());
+    }
+}
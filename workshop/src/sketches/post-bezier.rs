@@ -0,0 +1,131 @@
+use nannou::lyon::math::point;
+use nannou::prelude::*;
+use workshop::offscreen::Offscreen;
+use workshop::postfx::{Effect, Lut, PostChain, PostRenderer};
+
+fn main() {
+    nannou::app(model)
+        .update(update)
+        .run();
+}
+
+//The mirrored bezier from `p2_b`, with post-processing effects on top.
+// Press 1 to 6 to turn the effects on and off.
+// Run with `-- --lut <file.cube>` to grade the colours with a LUT from a photo or video editor.
+struct Model {
+    offscreen: Offscreen,
+    post: PostRenderer,
+    chain: PostChain,
+}
+
+fn model(app: &App) -> Model {
+    let id = app
+        .new_window()
+        .size(1024, 768)
+        .view(view)
+        .key_pressed(key_pressed)
+        .build()
+        .unwrap();
+    let window = app.window(id).unwrap();
+    let (w, h) = window.inner_size_pixels();
+
+    let lut = match lut_from_args() {
+        Some(path) => Lut::load(&path).unwrap_or_else(|e| {
+            eprintln!("Could not load {}: {}", path, e);
+            warm()
+        }),
+        None => warm(),
+    };
+    let mut chain = PostChain::new()
+        .with(Effect::bloom())
+        .with(Effect::chromatic_aberration())
+        .with(Effect::lut(lut))
+        .with(Effect::vignette())
+        .with(Effect::grain())
+        .with(Effect::pixelate());
+    //Pixelation hides everything else, so start with it off
+    chain.toggle(5);
+
+    Model {
+        offscreen: Offscreen::new(&window, w, h),
+        post: PostRenderer::new(&window),
+        chain,
+    }
+}
+
+fn lut_from_args() -> Option<String> {
+    let args: Vec<String> = std::env::args().collect();
+    let i = args.iter().position(|arg| arg == "--lut")?;
+    args.get(i + 1).cloned()
+}
+
+//A grading made in code: warmer highlights and bluer shadows
+fn warm() -> Lut {
+    Lut::from_fn(17, |[r, g, b]| {
+        let l = (r + g + b) / 3.0;
+        [
+            (r + 0.08 * l).min(1.0),
+            g,
+            (b + 0.1 * (1.0 - l) - 0.05 * l).clamp(0.0, 1.0),
+        ]
+    })
+}
+
+fn key_pressed(_app: &App, model: &mut Model, key: Key) {
+    let index = match key {
+        Key::Key1 => 0,
+        Key::Key2 => 1,
+        Key::Key3 => 2,
+        Key::Key4 => 3,
+        Key::Key5 => 4,
+        Key::Key6 => 5,
+        _ => return,
+    };
+    model.chain.toggle(index);
+    let (effect, on) = &model.chain.effects[index];
+    println!("{}: {}", effect.name(), if *on { "on" } else { "off" });
+}
+
+fn update(app: &App, model: &mut Model, _update: Update) {
+    let window = app.main_window();
+    //The texture is in pixels, so scale the drawing up on high resolution screens
+    let scale = window.scale_factor();
+    let win = window.rect();
+    let time = app.time;
+
+    model.offscreen.render(&window, |draw| {
+        let draw = draw.scale(scale);
+        draw.background().color(BLACK);
+
+        let mut builder = nannou::geom::path::Builder::new().with_svg();
+        builder.move_to(point(0.0, win.bottom() + 20.0));
+        let width = (time * 0.05).sin() * 400.0 + 40.0;
+        builder.cubic_bezier_to(
+            point((time * 0.3).cos() * width, (time * 0.1).sin() * width),
+            point((time * 0.3 + 0.1).sin() * width, (time * 0.1).cos() * width),
+            point(0.0, win.top() - 20.0),
+        );
+        let path = builder.build();
+
+        for draw in [draw.clone(), draw.scale_x(-1.0)] {
+            draw.path()
+                .stroke()
+                .color(WHITE)
+                .weight(1.0)
+                .events(path.iter());
+        }
+    });
+
+    let seed = app.elapsed_frames() as u32;
+    model
+        .post
+        .render(&window, &model.chain, model.offscreen.texture(), seed);
+}
+
+fn view(app: &App, model: &Model, frame: Frame) {
+    let draw = app.draw();
+    let win = app.window_rect();
+    draw.texture(model.post.output(model.offscreen.texture()))
+        .w_h(win.w(), win.h());
+    draw.to_frame(app, &frame).unwrap();
+}
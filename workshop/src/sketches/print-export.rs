@@ -0,0 +1,74 @@
+use nannou::prelude::*;
+use workshop::export::{self, TiledExport};
+use workshop::layout::DesignSpace;
+
+fn main() {
+    nannou::app(model)
+        .update(update)
+        .run();
+}
+
+//An A-paper shaped design, so it can be printed.
+// Run with `-- --export-size 7016x9921` and press E to render it for A2 at 300 dpi.
+struct Model {
+    design: DesignSpace,
+    export: TiledExport,
+}
+
+fn model(app: &App) -> Model {
+    app.new_window()
+        .size(510, 720)
+        .view(view)
+        .key_pressed(key_pressed)
+        .build()
+        .unwrap();
+    let design = DesignSpace::new(707.0, 1000.0);
+    //Without `--export-size` we export at four times the design size
+    let export = TiledExport::from_args(design).unwrap_or_else(|| TiledExport::new(design, 2828, 4000));
+    Model { design, export }
+}
+
+fn update(_app: &App, _model: &mut Model, _update: Update) {}
+
+fn key_pressed(app: &App, model: &mut Model, key: Key) {
+    if key == export::EXPORT_KEY {
+        let path = format!(
+            "captures/print-export/export_{}x{}.png",
+            model.export.width, model.export.height
+        );
+        let time = app.time;
+        if let Err(e) = model.export.render(&app.main_window(), path, |draw| {
+            draw_design(draw, model.design, time)
+        }) {
+            eprintln!("Could not export: {}", e);
+        }
+    }
+}
+
+fn view(app: &App, model: &Model, frame: Frame){
+    let draw = app.draw();
+    draw.background().color(BLACK);
+    draw_design(&model.design.apply(&draw, app.window_rect()), model.design, app.time);
+    draw.to_frame(app, &frame).unwrap();
+}
+
+//Everything is drawn in design units, so the window and the export look the same
+fn draw_design(draw: &Draw, design: DesignSpace, time: f32) {
+    let rect = design.rect();
+    draw.rect().xy(rect.xy()).wh(rect.wh()).color(WHITE);
+
+    //Rings of circles, like the circles in `p1`
+    for ring in 1..12 {
+        let radius = ring as f32 * 28.0;
+        let count = ring * 6;
+        for i in 0..count {
+            let angle = i as f32 / count as f32 * TAU + time * 0.1 * ring as f32;
+            draw.ellipse()
+                .xy(vec2(angle.cos(), angle.sin()) * radius)
+                .radius(4.0 + ring as f32 * 0.5)
+                .no_fill()
+                .stroke(BLACK)
+                .stroke_weight(1.0);
+        }
+    }
+}
@@ -0,0 +1,226 @@
+use nannou::lyon::math::point;
+use nannou::lyon::path::Path;
+use nannou::math::ConvertAngle;
+use nannou::prelude::*;
+use workshop::geometry::{GeometryCache, Mesh};
+use workshop::offscreen::Offscreen;
+use workshop::projection::ProjectionConfig;
+
+fn main() {
+    nannou::app(model)
+        .update(update)
+        .run();
+}
+
+const NUM_ARMS: u16 = 12;
+//The width the bezier is tessellated at, see `cached-kaleidoscope`
+const BASE_WIDTH: f32 = 400.0;
+const CONFIG_PATH: &str = "projection.ron";
+
+//The kaleidoscope from `p2_d`, drawn once into a texture and shown in two output windows, one
+// for each projector, and a control window on the laptop.
+//
+// In the control window, drag the corners to warp the selected output.
+// Tab selects the next output, R resets its corners and W saves all outputs to `projection.ron`.
+// F in an output window makes it fullscreen, so drag it to the projector first.
+struct Model {
+    offscreen: Offscreen,
+    //Keyed on the height of the texture, the only thing the bezier is made from
+    cache: GeometryCache<f32>,
+    config: ProjectionConfig,
+    control: WindowId,
+    outputs: Vec<WindowId>,
+    selected: usize,
+    dragging: Option<usize>,
+}
+
+fn model(app: &App) -> Model {
+    let control = app
+        .new_window()
+        .title("control")
+        .size(800, 600)
+        .view(control_view)
+        .key_pressed(key_pressed)
+        .mouse_pressed(mouse_pressed)
+        .mouse_moved(mouse_moved)
+        .mouse_released(mouse_released)
+        .build()
+        .unwrap();
+
+    //Two projectors next to each other, overlapping by a fifth, unless there is a saved config
+    // A config that can't be loaded is not replaced, so the corners pinned in it aren't lost
+    // when saving
+    let config = match ProjectionConfig::load(CONFIG_PATH) {
+        Ok(config) => config,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            ProjectionConfig::side_by_side(2, 0.2)
+        }
+        Err(e) => {
+            eprintln!("Could not load {}: {}", CONFIG_PATH, e);
+            std::process::exit(1);
+        }
+    };
+    let outputs = config
+        .outputs
+        .iter()
+        .map(|output| {
+            app.new_window()
+                .title(&output.name)
+                .size(800, 600)
+                .view(output_view)
+                .key_pressed(key_pressed)
+                .build()
+                .unwrap()
+        })
+        .collect();
+
+    let window = app.window(control).unwrap();
+    Model {
+        offscreen: Offscreen::new(&window, 2048, 1024),
+        cache: GeometryCache::new(),
+        config,
+        control,
+        outputs,
+        selected: 0,
+        dragging: None,
+    }
+}
+
+fn update(app: &App, model: &mut Model, _update: Update) {
+    let rect = model.offscreen.rect();
+    let mesh = model.cache.get(rect.h(), || Mesh::stroke(&bezier(rect.h()), 1.0));
+
+    //The same transforms as the draw contexts in `p2_d`, one for each arm and one mirrored,
+    // turned and stretched with time instead of changing the shape of the bezier
+    let t = app.time;
+    let width = (t * 0.05).sin() * 400.0 + 40.0;
+    let stretch = Mat4::from_scale(vec3(width / BASE_WIDTH, 1.0, 1.0));
+    let transforms = (0..NUM_ARMS).flat_map(|i| {
+        let angle = (i as f32 * (360.0 / NUM_ARMS as f32)).deg_to_rad();
+        let arm = Mat4::from_scale(Vec3::splat(0.5))
+            * Mat4::from_rotation_z(t * 0.1 + angle)
+            * Mat4::from_translation(vec3(0.0, rect.h() * 0.5, 0.0))
+            * stretch;
+        [arm, arm * Mat4::from_scale(vec3(-1.0, 1.0, 1.0))]
+    });
+
+    //All windows share the GPU, so the texture can be drawn in any of them
+    let window = app.window(model.control).unwrap();
+    model.offscreen.render(&window, |draw| {
+        draw.background().color(BLACK);
+        //Two kaleidoscopes, so each projector has one
+        for x in [-0.25, 0.25] {
+            let draw = draw.x(x * rect.w());
+            mesh.draw_instances(&draw, transforms.clone(), WHITE);
+        }
+    });
+}
+
+//The bezier of `p2_d` at the start
+fn bezier(h: f32) -> Path {
+    let mut builder = nannou::geom::path::Builder::new().with_svg();
+    builder.move_to(point(0.0, -h * 0.5));
+    builder.cubic_bezier_to(
+        point(BASE_WIDTH, 0.0),
+        point(0.1_f32.sin() * BASE_WIDTH, BASE_WIDTH),
+        point(0.0, h * 0.5 - 20.0),
+    );
+    builder.build().into()
+}
+
+//Each output window finds its own settings by its id
+fn output_view(app: &App, model: &Model, frame: Frame) {
+    let index = match model.outputs.iter().position(|id| *id == frame.window_id()) {
+        Some(index) => index,
+        None => return,
+    };
+    let draw = app.draw();
+    draw.background().color(BLACK);
+    let win = app.window(frame.window_id()).unwrap().rect();
+    model.config.outputs[index].draw(&draw, win, model.offscreen.texture());
+    draw.to_frame(app, &frame).unwrap();
+}
+
+//The selected output as it looks on the projector, with handles on the corners
+fn control_view(app: &App, model: &Model, frame: Frame) {
+    let draw = app.draw();
+    draw.background().color(rgb(0.1, 0.1, 0.1));
+    let win = app.window(model.control).unwrap().rect();
+    let output = &model.config.outputs[model.selected];
+    output.draw(&draw, win, model.offscreen.texture());
+
+    let half = win.wh() * 0.5;
+    let corners: Vec<Vec2> = (0..4).map(|i| output.pin.corner(i) * half).collect();
+    draw.polygon()
+        .no_fill()
+        .stroke(YELLOW)
+        .stroke_weight(1.0)
+        .points(corners.iter().copied());
+    for (i, corner) in corners.iter().enumerate() {
+        let color = if model.dragging == Some(i) { ORANGE } else { YELLOW };
+        draw.ellipse().xy(*corner).radius(8.0).color(color);
+    }
+
+    draw.text(&format!(
+        "{} ({}/{})\nTab: next output  R: reset  W: save",
+        output.name,
+        model.selected + 1,
+        model.config.outputs.len()
+    ))
+    .xy(win.top_left() + vec2(130.0, -30.0))
+    .w(240.0)
+    .left_justify()
+    .color(WHITE);
+
+    draw.to_frame(app, &frame).unwrap();
+}
+
+fn key_pressed(app: &App, model: &mut Model, key: Key) {
+    match key {
+        Key::Tab => {
+            model.selected = (model.selected + 1) % model.config.outputs.len();
+        }
+        Key::R => {
+            model.config.outputs[model.selected].pin = Default::default();
+        }
+        Key::W => {
+            if let Err(e) = model.config.save(CONFIG_PATH) {
+                eprintln!("Could not save {}: {}", CONFIG_PATH, e);
+            }
+        }
+        Key::F => {
+            let window = app.window(app.window_id()).unwrap();
+            if window.id() != model.control {
+                window.set_fullscreen(!window.is_fullscreen());
+            }
+        }
+        _ => {}
+    }
+}
+
+//The mouse in the control window, as corner coordinates from -1 to 1
+fn mouse_to_corner(app: &App, model: &Model, position: Point2) -> Vec2 {
+    let win = app.window(model.control).unwrap().rect();
+    position / (win.wh() * 0.5)
+}
+
+fn mouse_pressed(app: &App, model: &mut Model, _button: MouseButton) {
+    let p = mouse_to_corner(app, model, app.mouse.position());
+    let pin = &model.config.outputs[model.selected].pin;
+    let nearest = pin.nearest_corner(p);
+    //Only grab a corner when the mouse is close to it
+    if pin.corner(nearest).distance(p) < 0.1 {
+        model.dragging = Some(nearest);
+    }
+}
+
+fn mouse_moved(app: &App, model: &mut Model, position: Point2) {
+    if let Some(i) = model.dragging {
+        let p = mouse_to_corner(app, model, position);
+        model.config.outputs[model.selected].pin.set_corner(i, p);
+    }
+}
+
+fn mouse_released(_app: &App, model: &mut Model, _button: MouseButton) {
+    model.dragging = None;
+}
@@ -0,0 +1,43 @@
+use nannou::prelude::*;
+use workshop::record;
+
+//The kaleidoscope from `p2_d` itself, not a copy
+#[path = "../../../p2/src/suggestion/p2_d.rs"]
+#[allow(dead_code)]
+mod p2_d;
+
+fn main() {
+    nannou::app(model)
+        .update(update)
+        .simple_window(view)
+        .run();
+}
+
+const NUM_ARMS: usize = 12;
+
+struct Model {}
+
+//Before opening the window we check the kaleidoscope without drawing it: it should be 24 stroked
+// paths, rotated every 30 degrees
+fn model(_app: &App) -> Model {
+    let win = Rect::from_w_h(1024.0, 768.0);
+    let recording = record::record(|draw| p2_d::draw_kaleidoscope(draw, win, 0.0));
+
+    recording.assert_count::<record::Path>(NUM_ARMS * 2);
+    recording.assert_all_stroked::<record::Path>();
+    let angles: Vec<f32> = (0..NUM_ARMS)
+        .map(|i| i as f32 * 360.0 / NUM_ARMS as f32)
+        .collect();
+    recording.assert_rotations::<record::Path>(&angles, 0.5);
+    println!("The kaleidoscope has {} paths", recording.count::<record::Path>());
+
+    Model {}
+}
+
+fn update(_app: &App, _model: &mut Model, _update: Update) {}
+
+fn view(app: &App, _model: &Model, frame: Frame) {
+    let draw = app.draw();
+    p2_d::draw_kaleidoscope(&draw, app.window_rect(), app.time);
+    draw.to_frame(app, &frame).unwrap();
+}
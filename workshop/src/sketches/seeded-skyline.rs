@@ -0,0 +1,104 @@
+use nannou::prelude::*;
+use workshop::capture::{self, FrameMetadata};
+use workshop::rng::Randomness;
+
+fn main() {
+    nannou::app(model)
+        .update(update)
+        .run();
+}
+
+struct Model {
+    randomness: Randomness,
+    stars: Vec<Vec2>,
+}
+
+fn model(app: &App) -> Model {
+    let _win = app
+        .new_window()
+        .size(1024, 720)
+        .view(view)
+        .key_pressed(key_pressed)
+        .build()
+        .unwrap();
+    let mut randomness = Randomness::from_args();
+    let stars = make_stars(app, &mut randomness);
+    Model { randomness, stars }
+}
+
+//The stars use their own stream, so they are placed the same way no matter how many random
+// numbers the buildings use.
+fn make_stars(app: &App, randomness: &mut Randomness) -> Vec<Vec2> {
+    let win = app.window_rect();
+    let rng = randomness.stream("stars");
+    (0..80).map(|_| rng.in_rect(win)).collect()
+}
+
+fn update(_app: &App, _model: &mut Model, _update: Update) {}
+
+fn key_pressed(app: &App, model: &mut Model, key: Key) {
+    if model.randomness.key_pressed(key) {
+        model.stars = make_stars(app, &mut model.randomness);
+    }
+    if key == capture::CAPTURE_KEY {
+        let metadata = FrameMetadata::new(app, model.randomness.seed());
+        if let Err(e) = capture::capture_frame(app, &metadata) {
+            eprintln!("Could not capture frame: {}", e);
+        }
+    }
+}
+
+fn view(app: &App, model: &Model, frame: Frame) {
+    let draw = app.draw();
+    let win = app.window_rect();
+
+    draw.background().color(MIDNIGHTBLUE);
+
+    for star in &model.stars {
+        let twinkle = (app.time * 2.0 + star.x).sin() * 0.5 + 0.5;
+        draw.ellipse()
+            .xy(*star)
+            .radius(1.0 + twinkle)
+            .color(WHITE);
+    }
+
+    //`fresh` restarts the stream every frame, so we get the same skyline every time
+    let mut buildings = model.randomness.fresh("buildings");
+    let mut windows = model.randomness.fresh("windows");
+    let unit_size = 20.0;
+    let mut x = win.left();
+    while x < win.right() {
+        let windows_per_floor = 2 + buildings.index(4);
+        let num_floors = 3 + buildings.index(12);
+        let building_w = unit_size * windows_per_floor as f32;
+        let building_h = unit_size * num_floors as f32;
+        let building_rect = Rect::from_x_y_w_h(
+            x + building_w * 0.5,
+            win.bottom() + building_h * 0.5,
+            building_w,
+            building_h,
+        );
+        let shade = buildings.range(0.1, 0.3);
+        draw.rect()
+            .xy(building_rect.xy())
+            .wh(building_rect.wh())
+            .color(rgb(shade, shade, shade));
+
+        for floor in 0..num_floors {
+            for column in 0..windows_per_floor {
+                if windows.chance(0.4) {
+                    draw.rect()
+                        .x_y(
+                            building_rect.left() + (column as f32 + 0.5) * unit_size,
+                            building_rect.bottom() + (floor as f32 + 0.5) * unit_size,
+                        )
+                        .w_h(unit_size * 0.5, unit_size * 0.5)
+                        .color(rgb(1.0, 0.9, windows.range(0.3, 0.7)));
+                }
+            }
+        }
+        x += building_rect.w() + buildings.range(0.0, unit_size);
+    }
+
+    draw.to_frame(app, &frame).unwrap();
+}
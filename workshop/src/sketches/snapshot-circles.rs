@@ -0,0 +1,125 @@
+use nannou::prelude::*;
+use serde::{Deserialize, Serialize};
+use workshop::rng::Randomness;
+use workshop::snapshot::{self, Clock, Snapshot};
+
+fn main() {
+    nannou::app(model)
+        .update(update)
+        .run();
+}
+
+//The circles from `p1_e`, with some random circles added, which change colour every two seconds.
+// Press P to save a snapshot, and run with `-- --load <file>` to carry on from it.
+struct Model {
+    state: State,
+    randomness: Randomness,
+    clock: Clock,
+}
+
+//The part of the model that is saved in snapshots
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct State {
+    circles: Vec<Circle>,
+    //The clock time of the last colour change
+    #[serde(default)]
+    recolored_at: f32,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct Circle {
+    #[serde(with = "snapshot::vec2")]
+    position: Vec2,
+    speed: f32,
+    radius: f32,
+    #[serde(with = "snapshot::rgb8")]
+    color: Rgb<u8>,
+}
+
+fn model(app: &App) -> Model {
+    app.new_window()
+        .size(1024, 720)
+        .view(view)
+        .key_pressed(key_pressed)
+        .build()
+        .unwrap();
+
+    if let Some(snapshot) = Snapshot::<State>::from_args() {
+        return Model {
+            randomness: snapshot.randomness(),
+            state: snapshot.model,
+            clock: Clock::resumed_from(app, snapshot.time),
+        };
+    }
+
+    let mut randomness = Randomness::from_args();
+    let rng = randomness.stream("circles");
+    let circles = (0..8)
+        .map(|_| Circle {
+            position: Vec2::ZERO,
+            speed: rng.range(0.2, 2.0),
+            radius: rng.range(10.0, 50.0),
+            color: *rng.pick(&[MAGENTA, ORANGE, YELLOW, WHITE]).unwrap(),
+        })
+        .collect();
+    Model {
+        state: State {
+            circles,
+            recolored_at: 0.0,
+        },
+        randomness,
+        clock: Clock::new(),
+    }
+}
+
+fn key_pressed(app: &App, model: &mut Model, key: Key) {
+    if key == snapshot::SNAPSHOT_KEY {
+        let snapshot = Snapshot::new(
+            app.exe_name().unwrap_or_default(),
+            model.randomness.seed(),
+            model.clock.time(app),
+            model.state.clone(),
+        )
+        .with_randomness(&model.randomness);
+        if let Err(e) = snapshot.save(snapshot::snapshot_path(app)) {
+            eprintln!("Could not save snapshot: {}", e);
+        }
+    }
+}
+
+fn update(app: &App, model: &mut Model, _update: Update) {
+    let r = app.window_rect();
+    //Use the clock instead of `app.time`, so that a loaded snapshot carries on at the same time
+    let time = model.clock.time(app);
+    for circle in model.state.circles.iter_mut() {
+        let ts = time * circle.speed;
+        let radius = circle.radius;
+        circle.position = vec2(
+            map_range(ts.sin(), -1.0, 1.0, r.left() + radius, r.right() - radius),
+            map_range(ts.cos(), -1.0, 1.0, r.bottom() + radius, r.top() - radius),
+        );
+    }
+
+    //The colours come from their own stream, which a snapshot saves the position of, so a
+    // loaded sketch picks the same colours as it would have without stopping
+    if time - model.state.recolored_at > 2.0 {
+        model.state.recolored_at = time;
+        let rng = model.randomness.stream("colors");
+        let i = rng.index(model.state.circles.len());
+        model.state.circles[i].color = *rng.pick(&[MAGENTA, ORANGE, YELLOW, WHITE]).unwrap();
+    }
+}
+
+fn view(app: &App, model: &Model, frame: Frame){
+    let draw = app.draw();
+    draw.background().color(CYAN);
+
+    for circle in model.state.circles.iter() {
+        draw.ellipse()
+            .xy(circle.position)
+            .radius(circle.radius)
+            .color(circle.color);
+    }
+
+    draw.to_frame(app, &frame).unwrap();
+}
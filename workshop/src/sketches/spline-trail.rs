@@ -0,0 +1,113 @@
+use nannou::prelude::*;
+use workshop::spline;
+
+fn main() {
+    nannou::app(model)
+        .update(update)
+        .simple_window(view)
+        .run();
+}
+
+//The circles from `p1_e`, leaving smooth trails through the positions they have visited
+struct Model {
+    circle_a: Circle,
+    circle_b: Circle,
+}
+
+struct Circle {
+    position: Vec2,
+    speed: f32,
+    radius: f32,
+    color: Rgb<u8>,
+    visited: Vec<Vec2>,
+}
+
+//Remember a position every this many frames, and keep this many of them
+const VISIT_EVERY: u64 = 10;
+const MAX_VISITED: usize = 30;
+
+fn model(_app: &App) -> Model {
+    let a = Circle {
+        position: vec2(0.0, 0.0),
+        speed: 1.0,
+        radius: 50.0,
+        color: MAGENTA,
+        visited: Vec::new(),
+    };
+    let b = Circle {
+        position: vec2(0.0, 0.0),
+        speed: a.speed * 2.0,
+        radius: a.radius / 2.0,
+        color: ORANGE,
+        visited: Vec::new(),
+    };
+    Model {
+        circle_a: a,
+        circle_b: b,
+    }
+}
+
+fn update(app: &App, model: &mut Model, _update: Update) {
+    let r = app.window_rect();
+    let time = app.time;
+
+    for circle in [&mut model.circle_a, &mut model.circle_b] {
+        //A bit of wobble on the radius of the orbit, so the trails don't overlap
+        let ts = time * circle.speed;
+        let wobble = 1.0 - (time * 0.3).sin().abs() * 0.5;
+        let radius = circle.radius;
+        circle.position = vec2(
+            map_range( ts.sin() * wobble, -1.0, 1.0, r.left() + radius, r.right() - radius ),
+            map_range( ts.cos() * wobble, -1.0, 1.0, r.bottom() + radius, r.top() - radius ),
+            );
+        if app.elapsed_frames().is_multiple_of(VISIT_EVERY) {
+            circle.visited.push(circle.position);
+            if circle.visited.len() > MAX_VISITED {
+                circle.visited.remove(0);
+            }
+        }
+    }
+}
+
+fn view(app: &App, model: &Model, frame: Frame){
+    let draw = app.draw();
+
+    draw.background().color(CYAN);
+
+    let circle = &model.circle_a;
+    //Catmull-Rom passes through every visited position
+    let mut points = circle.visited.clone();
+    points.push(circle.position);
+    let path = spline::to_path(&spline::catmull_rom(&points, false), false);
+    draw.path()
+        .stroke()
+        .weight(3.0)
+        .color(circle.color)
+        .events(path.iter());
+
+    let circle = &model.circle_b;
+    //A B-spline is pulled towards the positions, which gives an even smoother trail
+    let mut points = circle.visited.clone();
+    points.push(circle.position);
+    let path = spline::to_path(&spline::uniform_bspline(&points, true), false);
+    draw.path()
+        .stroke()
+        .weight(3.0)
+        .color(circle.color)
+        .events(path.iter());
+
+    for circle in [&model.circle_a, &model.circle_b] {
+        for p in &circle.visited {
+            draw.ellipse()
+                .xy(*p)
+                .radius(3.0)
+                .color(BLACK);
+        }
+        draw.ellipse()
+            .xy(circle.position)
+            .radius(circle.radius)
+            .color(circle.color);
+    }
+
+    draw.to_frame(app, &frame).unwrap();
+}
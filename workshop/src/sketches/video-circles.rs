@@ -0,0 +1,159 @@
+use nannou::prelude::*;
+use workshop::layers::{Layer, Layers};
+use workshop::layout::Fit;
+use workshop::motion::Motion;
+use workshop::rng::Randomness;
+use workshop::video::Video;
+
+fn main() {
+    nannou::app(model)
+        .update(update)
+        .run();
+}
+
+//The circles from `p1_e`, pushed around by whatever moves in a video.
+// Run with `-- --video <file>` for a video file, which needs `ffmpeg`, or `-- --video <folder>`
+// for a folder of PNG frames.
+// Press D to show where the video changed, and V to hide and show the video.
+struct Model {
+    video: Video,
+    motion: Motion,
+    layers: Layers,
+    circles: Vec<Circle>,
+    show_difference: bool,
+}
+
+struct Circle {
+    position: Vec2,
+    velocity: Vec2,
+    radius: f32,
+    color: Rgb<u8>,
+}
+
+fn model(app: &App) -> Model {
+    let id = app
+        .new_window()
+        .size(1024, 768)
+        .view(view)
+        .key_pressed(key_pressed)
+        .build()
+        .unwrap();
+    let window = app.window(id).unwrap();
+
+    let path = video_from_args().unwrap_or_else(|| {
+        eprintln!("Run with -- --video <file or folder>");
+        std::process::exit(1);
+    });
+    let video = Video::open(&path).unwrap_or_else(|e| {
+        eprintln!("Could not open {}: {}", path, e);
+        std::process::exit(1);
+    });
+
+    let mut layers = Layers::new();
+    layers.add(Layer::image(&window, "video", video.frame().pixels.clone()).opacity(0.6));
+
+    let mut randomness = Randomness::from_args();
+    let rng = randomness.stream("circles");
+    let win = window.rect();
+    let circles = (0..12)
+        .map(|_| Circle {
+            position: rng.in_rect(win),
+            velocity: Vec2::ZERO,
+            radius: rng.range(10.0, 50.0),
+            color: *rng.pick(&[MAGENTA, ORANGE, YELLOW, WHITE]).unwrap(),
+        })
+        .collect();
+
+    Model {
+        video,
+        motion: Motion::new(64, 48),
+        layers,
+        circles,
+        show_difference: false,
+    }
+}
+
+fn video_from_args() -> Option<String> {
+    let args: Vec<String> = std::env::args().collect();
+    let i = args.iter().position(|arg| arg == "--video")?;
+    args.get(i + 1).cloned()
+}
+
+fn key_pressed(_app: &App, model: &mut Model, key: Key) {
+    match key {
+        Key::D => model.show_difference = !model.show_difference,
+        Key::V => {
+            if let Some(layer) = model.layers.get_mut("video") {
+                layer.visible = !layer.visible;
+            }
+        }
+        _ => {}
+    }
+}
+
+//The video fills the window, cut off at the sides or the top and bottom
+fn video_rect(app: &App, model: &Model) -> Rect {
+    model.video.frame().rect_in(app.window_rect(), Fit::Cover)
+}
+
+fn update(app: &App, model: &mut Model, _update: Update) {
+    match model.video.update(app.time) {
+        Ok(true) => {
+            model.motion.update(model.video.frame());
+            if let Some(pixels) = model.layers.get_mut("video").and_then(Layer::image_mut) {
+                pixels.clone_from(&model.video.frame().pixels);
+            }
+        }
+        Ok(false) => {}
+        Err(e) => eprintln!("Could not read the video: {}", e),
+    }
+    model.layers.upload(&app.main_window());
+
+    let win = app.window_rect();
+    let rect = video_rect(app, model);
+    for circle in model.circles.iter_mut() {
+        //Smaller circles are lighter, so they get pushed further
+        let push = model.motion.push(rect, circle.position, circle.radius);
+        circle.velocity += push * (20.0 / circle.radius);
+        circle.velocity *= 0.95;
+        circle.position += circle.velocity;
+
+        //Bounce off the edges of the window
+        let r = circle.radius;
+        if circle.position.x < win.left() + r || circle.position.x > win.right() - r {
+            circle.velocity.x = -circle.velocity.x;
+        }
+        if circle.position.y < win.bottom() + r || circle.position.y > win.top() - r {
+            circle.velocity.y = -circle.velocity.y;
+        }
+        circle.position = circle.position.clamp(
+            vec2(win.left() + r, win.bottom() + r),
+            vec2(win.right() - r, win.top() - r),
+        );
+    }
+}
+
+fn view(app: &App, model: &Model, frame: Frame) {
+    let draw = app.draw();
+    let rect = video_rect(app, model);
+    draw.background().color(BLACK);
+    model.layers.draw(&draw, rect);
+
+    if model.show_difference {
+        for (cell, difference) in model.motion.cells(rect).filter(|(_, d)| *d > 0.0) {
+            draw.rect()
+                .xy(cell.xy())
+                .wh(cell.wh())
+                .color(rgba(0.0, 1.0, 1.0, (difference * 4.0).min(0.8)));
+        }
+    }
+
+    for circle in model.circles.iter() {
+        draw.ellipse()
+            .xy(circle.position)
+            .radius(circle.radius)
+            .color(circle.color);
+    }
+
+    draw.to_frame(app, &frame).unwrap();
+}
@@ -0,0 +1,72 @@
+use nannou::math::ConvertAngle;
+use nannou::prelude::*;
+use workshop::stroke::{MeasuredPath, WriteOn};
+
+fn main() {
+    nannou::app(model)
+        .update(update)
+        .simple_window(view)
+        .run();
+}
+
+struct Model {}
+
+fn model(_app: &App) -> Model {
+    Model{}
+}
+
+fn update(_app: &App, _model: &mut Model, _update: Update) {}
+
+//The kaleidoscope from `p2_d`, where the arms are written on over and over again.
+fn view(app: &App, _model: &Model, frame: Frame) {
+    let draw = app.draw();
+    let win = app.window_rect();
+
+    draw.background().color(BLACK);
+
+    use nannou::lyon::math::point;
+    let mut builder = nannou::geom::path::Builder::new().with_svg();
+    builder.move_to(point(0.0, win.bottom()));
+    let width = (app.time * 0.05).sin() * 400.0 + 40.0;
+    let control_a = point((app.time * 0.3 ).cos() * width, (app.time * 0.1).sin() * width);
+    let control_b = point((app.time * 0.3 + 0.1).sin() * width, (app.time * 0.1).cos() * width);
+    builder.cubic_bezier_to(
+        control_a,
+        control_b,
+        point(0.0, win.top() - 20.0)
+        );
+    let path = builder.build();
+    let measured = MeasuredPath::new(path.iter());
+
+    //Write the arm on over 4 seconds, then start over
+    let loop_time = app.time % 6.0;
+    let (start, end) = WriteOn::new(4.0).delay(0.5).range(loop_time);
+    let written = measured.trim(start, end);
+    //A snake of dashes running along the arm
+    let (snake_start, snake_end) = WriteOn::new(3.0).tail(0.3).range(app.time % 3.0);
+    let snake = measured.trim(snake_start, snake_end);
+    let dashes = measured.dashes(12.0, 8.0, app.time * 30.0);
+
+    let draw = draw.scale(0.5);
+    const NUM_ARMS: u16 = 12;
+    for i in 0..NUM_ARMS {
+        let angle = i as f32 * (360.0 / NUM_ARMS as f32);
+        for mirror in [1.0, -1.0] {
+            let arm = draw.rotate(angle.deg_to_rad())
+                .translate(vec3(0.0, win.h() * 0.5, 0.0))
+                .scale_x(mirror);
+            for stroke in &dashes {
+                stroke.draw(&arm, 1.0, rgba(1.0, 1.0, 1.0, 0.2));
+            }
+            //Thick in the middle of the arm, and thin at the ends
+            for stroke in &written {
+                stroke.draw_variable_width(&arm, |u| 1.0 + (u * PI).sin() * 6.0, WHITE);
+            }
+            for stroke in &snake {
+                stroke.draw(&arm, 3.0, ORANGE);
+            }
+        }
+    }
+
+    draw.to_frame(app, &frame).unwrap();
+}
@@ -0,0 +1,249 @@
+//! Saving the state of a sketch, and starting from it again.
+//!
+//! A `Snapshot` holds the model together with the seed, the clock time and any parameters, so
+//! a performance can be picked up exactly where it left off.
+//! Press `P` to save a snapshot to `captures/<sketch name>/snapshot_<frame>.ron`, and start the
+//! sketch with `-- --load <file>` to load it.
+//!
+//! The model must implement `Serialize` and `Deserialize` from serde, which is mostly a matter
+//! of adding `#[derive(Serialize, Deserialize)]`.
+//! nannou types like `Vec2` and `Rgb<u8>` are saved with the helper modules in this file, e.g.
+//! `#[serde(with = "workshop::snapshot::vec2")]`.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use nannou::prelude::*;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::capture;
+use crate::rng::Randomness;
+
+/// The key that saves a snapshot in the sketches.
+pub const SNAPSHOT_KEY: Key = Key::P;
+
+/// Everything needed to resume a sketch.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Snapshot<M> {
+    pub sketch: String,
+    pub seed: u64,
+    /// The random streams and how far they have come, see `with_randomness`.
+    #[serde(default)]
+    pub randomness: Option<Randomness>,
+    /// The sketch time when the snapshot was taken, see `Clock`.
+    pub time: f32,
+    /// Named values that are not part of the model, e.g. sliders.
+    #[serde(default)]
+    pub params: BTreeMap<String, f32>,
+    pub model: M,
+}
+
+impl<M> Snapshot<M>
+where
+    M: Serialize + DeserializeOwned,
+{
+    pub fn new(sketch: impl Into<String>, seed: u64, time: f32, model: M) -> Self {
+        Snapshot {
+            sketch: sketch.into(),
+            seed,
+            randomness: None,
+            time,
+            params: BTreeMap::new(),
+            model,
+        }
+    }
+
+    pub fn param(mut self, name: impl Into<String>, value: f32) -> Self {
+        self.params.insert(name.into(), value);
+        self
+    }
+
+    /// Save the random streams too, so the sketch draws the same random numbers after loading
+    /// as it would have without stopping.
+    pub fn with_randomness(mut self, randomness: &Randomness) -> Self {
+        self.seed = randomness.seed();
+        self.randomness = Some(randomness.clone());
+        self
+    }
+
+    /// The saved randomness, or streams that start from the beginning for the saved seed if
+    /// the snapshot has none.
+    pub fn randomness(&self) -> Randomness {
+        self.randomness
+            .clone()
+            .unwrap_or_else(|| Randomness::new(self.seed))
+    }
+
+    /// Save as RON, or as JSON when the file name ends with `.json`.
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let path = path.as_ref();
+        let text = if is_json(path) {
+            serde_json::to_string_pretty(self).map_err(invalid_data)?
+        } else {
+            ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+                .map_err(invalid_data)?
+        };
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, text)?;
+        println!("Saved snapshot {}", path.display());
+        Ok(())
+    }
+
+    /// Load a snapshot saved with `save`.
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path)?;
+        if is_json(path) {
+            serde_json::from_str(&text).map_err(invalid_data)
+        } else {
+            ron::from_str(&text).map_err(invalid_data)
+        }
+    }
+
+    /// Load the snapshot given with `--load <file>`, if there is one.
+    /// Prints an error and returns `None` if the file can't be loaded, so the sketch can start
+    /// from scratch instead.
+    pub fn from_args() -> Option<Self> {
+        let path = load_path_from_args(std::env::args())?;
+        match Snapshot::load(&path) {
+            Ok(snapshot) => {
+                println!("Loaded snapshot {}", path.display());
+                Some(snapshot)
+            }
+            Err(e) => {
+                eprintln!("Could not load snapshot {}: {}", path.display(), e);
+                None
+            }
+        }
+    }
+}
+
+/// Where to save a snapshot of the current frame, i.e.
+/// `captures/<sketch name>/snapshot_<frame>.ron`.
+pub fn snapshot_path(app: &App) -> PathBuf {
+    capture::capture_dir(app).join(format!("snapshot_{:06}.ron", app.elapsed_frames()))
+}
+
+/// `app.time` always starts at zero, so a resumed sketch keeps its own clock with an offset.
+/// Use `clock.time(app)` wherever the sketch used `app.time`.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Clock {
+    offset: f32,
+}
+
+impl Clock {
+    /// A clock that follows `app.time`.
+    pub fn new() -> Self {
+        Clock { offset: 0.0 }
+    }
+
+    /// A clock that shows `time` right now, and goes on from there.
+    pub fn resumed_from(app: &App, time: f32) -> Self {
+        Clock {
+            offset: time - app.time,
+        }
+    }
+
+    pub fn time(&self, app: &App) -> f32 {
+        app.time + self.offset
+    }
+}
+
+fn is_json(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "json")
+}
+
+fn invalid_data(e: impl std::fmt::Display) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, e.to_string())
+}
+
+fn load_path_from_args(args: impl Iterator<Item = String>) -> Option<PathBuf> {
+    let args: Vec<String> = args.collect();
+    args.iter()
+        .position(|arg| arg == "--load")
+        .and_then(|i| args.get(i + 1))
+        .map(PathBuf::from)
+}
+
+/// Save a `Vec2` as `(x, y)`. Use with `#[serde(with = "workshop::snapshot::vec2")]`.
+pub mod vec2 {
+    use nannou::prelude::*;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(v: &Vec2, serializer: S) -> Result<S::Ok, S::Error> {
+        (v.x, v.y).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec2, D::Error> {
+        let (x, y) = <(f32, f32)>::deserialize(deserializer)?;
+        Ok(vec2(x, y))
+    }
+}
+
+/// Save an `Rgb<u8>` as `(red, green, blue)`. Use with
+/// `#[serde(with = "workshop::snapshot::rgb8")]`.
+pub mod rgb8 {
+    use nannou::prelude::*;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(c: &Rgb<u8>, serializer: S) -> Result<S::Ok, S::Error> {
+        (c.red, c.green, c.blue).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Rgb<u8>, D::Error> {
+        let (r, g, b) = <(u8, u8, u8)>::deserialize(deserializer)?;
+        Ok(rgb(r, g, b))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(file_name: &str) {
+        let mut randomness = Randomness::new(1234);
+        for _ in 0..10 {
+            randomness.stream("circles").next_u64();
+        }
+        randomness.stream("colors").next_u64();
+
+        let snapshot = Snapshot::new("test", 0, 12.5, vec![1.0f32, 2.0])
+            .param("size", 3.0)
+            .with_randomness(&randomness);
+        let path = std::env::temp_dir().join(file_name);
+        snapshot.save(&path).unwrap();
+        let loaded = Snapshot::<Vec<f32>>::load(&path).unwrap();
+        let _ = fs::remove_file(&path);
+
+        assert_eq!(loaded, snapshot);
+        assert_eq!(loaded.seed, 1234);
+        //The streams carry on where they were, instead of starting over
+        let mut resumed = loaded.randomness();
+        for name in ["circles", "colors", "new stream"] {
+            let expected: Vec<u64> = (0..5).map(|_| randomness.stream(name).next_u64()).collect();
+            let actual: Vec<u64> = (0..5).map(|_| resumed.stream(name).next_u64()).collect();
+            assert_eq!(actual, expected, "stream {}", name);
+        }
+    }
+
+    #[test]
+    fn randomness_round_trips_through_ron() {
+        round_trip("workshop_snapshot_test.ron");
+    }
+
+    #[test]
+    fn randomness_round_trips_through_json() {
+        round_trip("workshop_snapshot_test.json");
+    }
+
+    #[test]
+    fn snapshots_without_randomness_start_the_streams_over() {
+        let snapshot = Snapshot::new("test", 99, 0.0, ());
+        assert_eq!(snapshot.randomness(), Randomness::new(99));
+    }
+}
@@ -0,0 +1,403 @@
+//! Curves through a list of points.
+//!
+//! Placing the control points of a cubic bezier by hand, like `control_a` and `control_b` in
+//! `p2_b`, is fine for a single curve.
+//! When we want a smooth curve that passes through many points, e.g. the positions a circle
+//! has visited, these builders work out the control points for us.
+//!
+//! All the builders give a list of `CubicBezier` segments, which `to_path` turns into a path
+//! that can be drawn with `draw.path()`.
+
+use nannou::lyon::path::path::Builder;
+use nannou::lyon::math::point;
+use nannou::lyon::path::Path;
+use nannou::prelude::*;
+
+use crate::bezier::CubicBezier;
+
+const DEGREE: usize = 3;
+
+/// Build a path from connected segments, e.g. from `catmull_rom`.
+pub fn to_path(segments: &[CubicBezier], closed: bool) -> Path {
+    let mut builder = Builder::new();
+    append_to(segments, closed, &mut builder);
+    builder.build()
+}
+
+/// Add connected segments to a path builder as one sub path.
+pub fn append_to(segments: &[CubicBezier], closed: bool, builder: &mut Builder) {
+    let first = match segments.first() {
+        Some(first) => first,
+        None => return,
+    };
+    builder.begin(point(first.from.x, first.from.y));
+    for s in segments {
+        builder.cubic_bezier_to(
+            point(s.ctrl1.x, s.ctrl1.y),
+            point(s.ctrl2.x, s.ctrl2.y),
+            point(s.to.x, s.to.y),
+        );
+    }
+    builder.end(closed);
+}
+
+/// A centripetal Catmull-Rom spline, which passes through all the points.
+///
+/// Centripetal means that the spacing between the points is taken into account, so the curve
+/// doesn't overshoot or make loops when points are close together.
+/// With `closed` the curve goes back to the first point.
+pub fn catmull_rom(points: &[Vec2], closed: bool) -> Vec<CubicBezier> {
+    let n = points.len();
+    if n < 2 {
+        return Vec::new();
+    }
+    //The point before the first and after the last are needed to find the direction at the
+    // ends. For open curves we mirror the neighbouring points.
+    let get = |i: isize| -> Vec2 {
+        if closed {
+            points[i.rem_euclid(n as isize) as usize]
+        } else if i < 0 {
+            points[0] * 2.0 - points[1]
+        } else if i as usize >= n {
+            points[n - 1] * 2.0 - points[n - 2]
+        } else {
+            points[i as usize]
+        }
+    };
+    let segment_count = if closed { n } else { n - 1 };
+    (0..segment_count as isize)
+        .map(|i| catmull_rom_segment(get(i - 1), get(i), get(i + 1), get(i + 2)))
+        .collect()
+}
+
+//The segment between p1 and p2, with knots spaced by the square root of the distance between
+// the points (alpha = 0.5).
+fn catmull_rom_segment(p0: Vec2, p1: Vec2, p2: Vec2, p3: Vec2) -> CubicBezier {
+    let knot = |a: Vec2, b: Vec2| a.distance(b).sqrt().max(1e-4);
+    let d01 = knot(p0, p1);
+    let d12 = knot(p1, p2);
+    let d23 = knot(p2, p3);
+    let m1 = ((p1 - p0) / d01 - (p2 - p0) / (d01 + d12) + (p2 - p1) / d12) * d12;
+    let m2 = ((p2 - p1) / d12 - (p3 - p1) / (d12 + d23) + (p3 - p2) / d23) * d12;
+    CubicBezier::new(p1, p1 + m1 / 3.0, p2 - m2 / 3.0, p2)
+}
+
+/// A Hermite spline: a curve through the points, leaving each point in the direction (and
+/// speed) of its tangent.
+/// Returns `None` unless there is one tangent for each point.
+pub fn hermite(points: &[Vec2], tangents: &[Vec2]) -> Option<Vec<CubicBezier>> {
+    if points.len() != tangents.len() {
+        return None;
+    }
+    let segments = points
+        .windows(2)
+        .zip(tangents.windows(2))
+        .map(|(p, m)| CubicBezier::new(p[0], p[0] + m[0] / 3.0, p[1] - m[1] / 3.0, p[1]))
+        .collect();
+    Some(segments)
+}
+
+/// A uniform cubic B-spline. The curve is pulled towards the control points, but doesn't pass
+/// through them, which makes it very smooth.
+/// With `clamped` the curve starts at the first point and ends at the last point.
+pub fn uniform_bspline(points: &[Vec2], clamped: bool) -> Vec<CubicBezier> {
+    if points.len() < 2 {
+        return Vec::new();
+    }
+    let mut padded = Vec::with_capacity(points.len() + 4);
+    if clamped {
+        //Repeating the end points pulls the curve all the way to them
+        padded.extend([points[0]; 2]);
+    }
+    padded.extend_from_slice(points);
+    if clamped {
+        padded.extend([points[points.len() - 1]; 2]);
+    }
+    padded
+        .windows(4)
+        .map(|p| {
+            CubicBezier::new(
+                (p[0] + p[1] * 4.0 + p[2]) / 6.0,
+                (p[1] * 2.0 + p[2]) / 3.0,
+                (p[1] + p[2] * 2.0) / 3.0,
+                (p[1] + p[2] * 4.0 + p[3]) / 6.0,
+            )
+        })
+        .collect()
+}
+
+/// A cubic B-spline with a knot vector, for when the spacing of the knots should not be
+/// uniform.
+/// The knot vector must be sorted and have `points.len() + 4` values, otherwise this returns
+/// `None`.
+/// Repeating a knot value makes the curve sharper there, and repeating the first and last
+/// values four times makes the curve start and end at the first and last points.
+pub fn bspline(points: &[Vec2], knots: &[f32]) -> Option<Vec<CubicBezier>> {
+    if knots.len() != points.len() + DEGREE + 1 || knots.windows(2).any(|k| k[0] > k[1]) {
+        return None;
+    }
+    if points.len() <= DEGREE {
+        return Some(Vec::new());
+    }
+    let mut points = points.to_vec();
+    let mut knots = knots.to_vec();
+    let n = points.len();
+    let (start, end) = (knots[DEGREE], knots[n]);
+
+    //Insert knots until every knot in the domain of the curve is repeated 3 times.
+    // Then the four control points of each span are the points of a bezier curve.
+    let mut distinct: Vec<f32> = knots
+        .iter()
+        .copied()
+        .filter(|&u| u >= start && u <= end)
+        .collect();
+    distinct.dedup();
+    for u in distinct {
+        while multiplicity(&knots, u) < DEGREE {
+            insert_knot(&mut knots, &mut points, u);
+        }
+    }
+
+    let mut segments = Vec::new();
+    for k in DEGREE..points.len() {
+        if knots[k] < knots[k + 1] && knots[k] >= start && knots[k + 1] <= end {
+            segments.push(CubicBezier::new(
+                points[k - 3],
+                points[k - 2],
+                points[k - 1],
+                points[k],
+            ));
+        }
+    }
+    Some(segments)
+}
+
+/// A knot vector for `count` points, with the knots spaced evenly and the ends repeated so the
+/// curve starts and ends at the first and last point.
+pub fn clamped_knots(count: usize) -> Vec<f32> {
+    let inner = count.saturating_sub(DEGREE + 1);
+    let mut knots = vec![0.0; DEGREE + 1];
+    knots.extend((1..=inner).map(|i| i as f32 / (inner + 1) as f32));
+    knots.extend([1.0; DEGREE + 1]);
+    knots
+}
+
+fn multiplicity(knots: &[f32], u: f32) -> usize {
+    knots.iter().filter(|&&k| k == u).count()
+}
+
+//Boehm's knot insertion: adds the knot `u` without changing the shape of the curve.
+fn insert_knot(knots: &mut Vec<f32>, points: &mut Vec<Vec2>, u: f32) {
+    //The span u is in, i.e. knots[k] <= u < knots[k + 1]. At the end of the domain we use the
+    // last non-empty span.
+    let last_span = points.len() - 1;
+    let k = (DEGREE..=last_span)
+        .rev()
+        .find(|&k| knots[k] <= u && (u < knots[k + 1] || k == last_span))
+        .unwrap_or(DEGREE);
+    let mut inserted = Vec::with_capacity(points.len() + 1);
+    for i in 0..=points.len() {
+        let point = if i + DEGREE <= k {
+            points[i]
+        } else if i > k {
+            points[i - 1]
+        } else {
+            let span = knots[i + DEGREE] - knots[i];
+            let a = if span > 0.0 { (u - knots[i]) / span } else { 0.0 };
+            points[i - 1] * (1.0 - a) + points[i] * a
+        };
+        inserted.push(point);
+    }
+    *points = inserted;
+    knots.insert(k + 1, u);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: Vec2, b: Vec2) {
+        assert!(a.distance(b) < 1e-3, "{:?} is not {:?}", a, b);
+    }
+
+    fn points() -> Vec<Vec2> {
+        vec![
+            vec2(0.0, 0.0),
+            vec2(10.0, 40.0),
+            vec2(30.0, 35.0),
+            vec2(32.0, 0.0),
+            vec2(60.0, -20.0),
+            vec2(90.0, 10.0),
+        ]
+    }
+
+    //The point at `u` on a B-spline (de Boor's algorithm)
+    fn de_boor(points: &[Vec2], knots: &[f32], u: f32) -> Vec2 {
+        let last_span = points.len() - 1;
+        let k = (DEGREE..=last_span)
+            .rev()
+            .find(|&k| knots[k] <= u && (u < knots[k + 1] || k == last_span))
+            .unwrap();
+        let mut d: Vec<Vec2> = (0..=DEGREE).map(|j| points[j + k - DEGREE]).collect();
+        for r in 1..=DEGREE {
+            for j in (r..=DEGREE).rev() {
+                let i = j + k - DEGREE;
+                let a = (u - knots[i]) / (knots[i + 1 + DEGREE - r] - knots[i]);
+                d[j] = d[j - 1] * (1.0 - a) + d[j] * a;
+            }
+        }
+        d[DEGREE]
+    }
+
+    #[test]
+    fn catmull_rom_passes_through_the_points() {
+        let points = points();
+        let open = catmull_rom(&points, false);
+        assert_eq!(open.len(), points.len() - 1);
+        for (segment, pair) in open.iter().zip(points.windows(2)) {
+            assert_close(segment.from, pair[0]);
+            assert_close(segment.to, pair[1]);
+        }
+
+        let closed = catmull_rom(&points, true);
+        assert_eq!(closed.len(), points.len());
+        assert_close(closed[closed.len() - 1].to, points[0]);
+        //The segments meet smoothly
+        for pair in closed.windows(2) {
+            let (a, b) = (pair[0].tangent(1.0), pair[1].tangent(0.0));
+            assert!(a.dot(b) > 0.999);
+        }
+    }
+
+    #[test]
+    fn hermite_needs_a_tangent_per_point() {
+        let points = points();
+        assert!(hermite(&points, &[Vec2::X; 2]).is_none());
+        let segments = hermite(&points, &vec![Vec2::X; points.len()]).unwrap();
+        assert_eq!(segments.len(), points.len() - 1);
+        assert_close(segments[0].derivative(0.0), Vec2::X);
+        assert_close(segments[0].to, points[1]);
+    }
+
+    #[test]
+    fn hermite_leaves_and_arrives_along_the_tangents() {
+        let points = points();
+        let tangents: Vec<Vec2> = (0..points.len())
+            .map(|i| vec2((i as f32).cos(), (i as f32).sin()) * (10.0 + i as f32 * 5.0))
+            .collect();
+        let segments = hermite(&points, &tangents).unwrap();
+        for (i, segment) in segments.iter().enumerate() {
+            assert_close(segment.from, points[i]);
+            assert_close(segment.to, points[i + 1]);
+            assert_close(segment.derivative(0.0), tangents[i]);
+            assert_close(segment.derivative(1.0), tangents[i + 1]);
+        }
+    }
+
+    //Check each segment against de Boor's algorithm, when segment `i` is the span between
+    // `spans[i]` and `spans[i + 1]`
+    fn assert_follows(segments: &[CubicBezier], points: &[Vec2], knots: &[f32], spans: &[f32]) {
+        assert_eq!(segments.len(), spans.len() - 1);
+        for (i, segment) in segments.iter().enumerate() {
+            for &t in &[0.0, 0.25, 0.6, 1.0] {
+                let u = spans[i] + (spans[i + 1] - spans[i]) * t;
+                assert_close(segment.point(t), de_boor(points, knots, u));
+            }
+        }
+    }
+
+    #[test]
+    fn uniform_bspline_without_clamping() {
+        let points = points();
+        let segments = uniform_bspline(&points, false);
+        //The knots are 0, 1, 2, ... and the curve is defined from knot 3 to knot 6
+        let knots: Vec<f32> = (0..points.len() + DEGREE + 1).map(|k| k as f32).collect();
+        assert_follows(&segments, &points, &knots, &[3.0, 4.0, 5.0, 6.0]);
+        //The curve doesn't reach the end points
+        assert!(segments[0].from.distance(points[0]) > 1.0);
+        //And the knot vector version gives the same curve
+        let with_knots = bspline(&points, &knots).unwrap();
+        for (a, b) in segments.iter().zip(&with_knots) {
+            assert_close(a.from, b.from);
+            assert_close(a.ctrl1, b.ctrl1);
+            assert_close(a.ctrl2, b.ctrl2);
+            assert_close(a.to, b.to);
+        }
+    }
+
+    #[test]
+    fn clamped_uniform_bspline_reaches_the_ends() {
+        let points = points();
+        let segments = uniform_bspline(&points, true);
+        assert_close(segments[0].from, points[0]);
+        assert_close(segments[segments.len() - 1].to, points[points.len() - 1]);
+        for pair in segments.windows(2) {
+            assert_close(pair[0].to, pair[1].from);
+        }
+    }
+
+    #[test]
+    fn non_uniform_bspline() {
+        let points = points();
+        //Clamped, with uneven spans
+        let knots = [0.0, 0.0, 0.0, 0.0, 0.2, 0.7, 1.0, 1.0, 1.0, 1.0];
+        let segments = bspline(&points, &knots).unwrap();
+        assert_follows(&segments, &points, &knots, &[0.0, 0.2, 0.7, 1.0]);
+
+        //Not clamped, with uneven spans, so the curve is defined from `knots[3]` to `knots[6]`
+        let knots = [0.0, 1.0, 3.0, 4.0, 6.0, 7.0, 9.0, 10.0, 12.0, 13.0];
+        let segments = bspline(&points, &knots).unwrap();
+        assert_follows(&segments, &points, &knots, &[4.0, 6.0, 7.0, 9.0]);
+
+        //A knot repeated three times makes the curve pass through a control point
+        let mut points = points;
+        points.push(vec2(100.0, 50.0));
+        let knots = [0.0, 0.0, 0.0, 0.0, 0.5, 0.5, 0.5, 1.0, 1.0, 1.0, 1.0];
+        let segments = bspline(&points, &knots).unwrap();
+        assert_follows(&segments, &points, &knots, &[0.0, 0.5, 1.0]);
+        assert_close(segments[0].to, points[3]);
+    }
+
+    #[test]
+    fn bspline_rejects_bad_knots() {
+        let points = points();
+        assert!(bspline(&points, &clamped_knots(points.len() - 1)).is_none());
+        let mut unsorted = clamped_knots(points.len());
+        unsorted.swap(4, 6);
+        assert!(bspline(&points, &unsorted).is_none());
+    }
+
+    #[test]
+    fn bspline_segments_follow_the_curve() {
+        let points = points();
+        let knots = clamped_knots(points.len());
+        let segments = bspline(&points, &knots).unwrap();
+        //Three distinct inner spans
+        assert_eq!(segments.len(), 3);
+        assert_close(segments[0].from, points[0]);
+        assert_close(segments[2].to, points[points.len() - 1]);
+        //Each segment covers a third of the domain here
+        for (i, segment) in segments.iter().enumerate() {
+            for &t in &[0.0, 0.3, 0.7] {
+                let u = (i as f32 + t) / 3.0;
+                assert_close(segment.point(t), de_boor(&points, &knots, u));
+            }
+        }
+    }
+
+    #[test]
+    fn knot_insertion_keeps_the_curve() {
+        let mut points = points();
+        let mut knots = clamped_knots(points.len());
+        let before: Vec<Vec2> = (0..=20)
+            .map(|i| de_boor(&points, &knots, i as f32 / 20.0))
+            .collect();
+        for &u in &[0.5, 0.1, 0.5, 1.0 / 3.0] {
+            insert_knot(&mut knots, &mut points, u);
+            assert_eq!(knots.len(), points.len() + DEGREE + 1);
+            for (i, p) in before.iter().enumerate() {
+                assert_close(de_boor(&points, &knots, i as f32 / 20.0), *p);
+            }
+        }
+    }
+}
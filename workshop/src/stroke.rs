@@ -0,0 +1,442 @@
+//! Drawing paths a bit at a time, for titles and reveals.
+//!
+//! `draw.path().stroke()` always draws the whole path.
+//! A `MeasuredPath` flattens a path into lines and measures their length, so that we can cut
+//! out the part between two distances along it.
+//! Animating where the part starts and ends makes the path look like it is being written.
+//!
+//! Distances are given _normalised_, i.e. `0.0` is the start of the path and `1.0` the end, so
+//! the same animation works for short and long paths.
+
+use nannou::color::IntoLinSrgba;
+use nannou::lyon::path::PathEvent;
+use nannou::prelude::*;
+
+use crate::bezier::CubicBezier;
+
+//Curves are cut into straight lines about this many pixels long
+const FLATTEN_STEP: f32 = 2.0;
+
+//One connected run of lines in the path
+#[derive(Clone, Debug)]
+struct Polyline {
+    points: Vec<Vec2>,
+    //Distance from the start of the whole path to each point
+    distances: Vec<f32>,
+}
+
+/// A piece of a path, as given by `MeasuredPath::trim` and `MeasuredPath::dashes`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Stroke {
+    pub points: Vec<Vec2>,
+    /// The normalised distance along the whole path for each point, from `0.0` to `1.0`.
+    pub positions: Vec<f32>,
+}
+
+impl Stroke {
+    /// Draw the stroke with the same weight all along.
+    pub fn draw(&self, draw: &Draw, weight: f32, color: impl IntoLinSrgba<f32> + Copy) {
+        if self.points.len() < 2 {
+            return;
+        }
+        draw.polyline()
+            .weight(weight)
+            .points(self.points.iter().copied())
+            .color(color);
+    }
+
+    /// Draw the stroke with a weight that changes along the path. `weight` gets the normalised
+    /// distance along the whole path, so e.g. `|u| (u * PI).sin() * 10.0` is thick in the
+    /// middle of the path and thin at the ends.
+    pub fn draw_variable_width<F>(
+        &self,
+        draw: &Draw,
+        weight: F,
+        color: impl IntoLinSrgba<f32> + Copy,
+    ) where
+        F: Fn(f32) -> f32,
+    {
+        for i in 1..self.points.len() {
+            let (a, b) = (self.points[i - 1], self.points[i]);
+            let normal = (b - a).normalize_or_zero().perp();
+            let wa = normal * weight(self.positions[i - 1]) * 0.5;
+            let wb = normal * weight(self.positions[i]) * 0.5;
+            draw.quad()
+                .points(a + wa, b + wb, b - wb, a - wa)
+                .color(color);
+        }
+    }
+}
+
+/// A path flattened into lines, with the distance to every point measured.
+#[derive(Clone, Debug)]
+pub struct MeasuredPath {
+    polylines: Vec<Polyline>,
+    length: f32,
+}
+
+impl MeasuredPath {
+    /// Measure a path, e.g. `MeasuredPath::new(path.iter())`.
+    pub fn new<I>(events: I) -> Self
+    where
+        I: IntoIterator<Item = PathEvent>,
+    {
+        let mut polylines: Vec<Polyline> = Vec::new();
+        let mut length = 0.0;
+        let v = |p: nannou::lyon::math::Point| vec2(p.x, p.y);
+
+        for event in events {
+            if let PathEvent::Begin { at } = event {
+                polylines.push(Polyline {
+                    points: vec![v(at)],
+                    distances: vec![length],
+                });
+                continue;
+            }
+            let curve = match CubicBezier::from_event(event) {
+                Some(curve) => curve,
+                None => continue,
+            };
+            let polyline = match polylines.last_mut() {
+                Some(polyline) => polyline,
+                None => continue,
+            };
+            let steps = (curve.length(8) / FLATTEN_STEP).ceil().clamp(1.0, 256.0) as usize;
+            let mut last = curve.from;
+            for i in 1..=steps {
+                let p = curve.point(i as f32 / steps as f32);
+                length += last.distance(p);
+                polyline.points.push(p);
+                polyline.distances.push(length);
+                last = p;
+            }
+        }
+
+        MeasuredPath { polylines, length }
+    }
+
+    /// Measure a list of points as a single line.
+    pub fn from_points<I>(points: I) -> Self
+    where
+        I: IntoIterator<Item = Vec2>,
+    {
+        let mut polyline = Polyline {
+            points: Vec::new(),
+            distances: Vec::new(),
+        };
+        let mut length = 0.0;
+        for p in points {
+            if let Some(&last) = polyline.points.last() {
+                length += last.distance(p);
+            }
+            polyline.points.push(p);
+            polyline.distances.push(length);
+        }
+        let polylines = if polyline.points.is_empty() {
+            Vec::new()
+        } else {
+            vec![polyline]
+        };
+        MeasuredPath { polylines, length }
+    }
+
+    /// The length of the whole path in pixels.
+    pub fn length(&self) -> f32 {
+        self.length
+    }
+
+    /// The point at the normalised distance `u` along the path.
+    pub fn point_at(&self, u: f32) -> Vec2 {
+        let distance = u.clamp(0.0, 1.0) * self.length;
+        for polyline in &self.polylines {
+            let last = polyline.distances[polyline.distances.len() - 1];
+            if distance <= last {
+                return point_on(polyline, distance);
+            }
+        }
+        self.polylines
+            .last()
+            .and_then(|p| p.points.last().copied())
+            .unwrap_or(Vec2::ZERO)
+    }
+
+    /// The part of the path between the normalised distances `start` and `end`.
+    /// When `start` is after `end` the part wraps around, i.e. it goes from `start` to the end of
+    /// the path and from the start of the path to `end`.
+    pub fn trim(&self, start: f32, end: f32) -> Vec<Stroke> {
+        let (start, end) = (start.clamp(0.0, 1.0), end.clamp(0.0, 1.0));
+        if start <= end {
+            self.trim_distance(start * self.length, end * self.length)
+        } else {
+            let mut strokes = self.trim_distance(start * self.length, self.length);
+            strokes.extend(self.trim_distance(0.0, end * self.length));
+            strokes
+        }
+    }
+
+    /// The part of the path between two distances in pixels.
+    pub fn trim_distance(&self, start: f32, end: f32) -> Vec<Stroke> {
+        let mut strokes = Vec::new();
+        if end <= start || self.length <= 0.0 {
+            return strokes;
+        }
+        for polyline in &self.polylines {
+            let first = polyline.distances[0];
+            let last = polyline.distances[polyline.distances.len() - 1];
+            if last < start || first > end {
+                continue;
+            }
+            let (from, to) = (start.max(first), end.min(last));
+            let mut stroke = Stroke::default();
+            stroke.points.push(point_on(polyline, from));
+            stroke.positions.push(from / self.length);
+            for (p, &d) in polyline.points.iter().zip(&polyline.distances) {
+                if d > from && d < to {
+                    stroke.points.push(*p);
+                    stroke.positions.push(d / self.length);
+                }
+            }
+            stroke.points.push(point_on(polyline, to));
+            stroke.positions.push(to / self.length);
+            strokes.push(stroke);
+        }
+        strokes
+    }
+
+    /// Cut the path into dashes `dash` pixels long with `gap` pixels between them.
+    /// Animate `offset` to make the dashes crawl along the path.
+    pub fn dashes(&self, dash: f32, gap: f32, offset: f32) -> Vec<Stroke> {
+        let period = dash + gap;
+        if dash <= 0.0 || period <= 0.0 {
+            return Vec::new();
+        }
+        let mut strokes = Vec::new();
+        let mut start = offset.rem_euclid(period) - period;
+        while start < self.length {
+            strokes.extend(self.trim_distance(start.max(0.0), (start + dash).min(self.length)));
+            start += period;
+        }
+        strokes
+    }
+}
+
+//The point `distance` along the polyline, which must be within the polyline
+fn point_on(polyline: &Polyline, distance: f32) -> Vec2 {
+    if polyline.points.len() < 2 {
+        return polyline.points[0];
+    }
+    let i = polyline
+        .distances
+        .partition_point(|&d| d < distance)
+        .clamp(1, polyline.points.len() - 1);
+    let (d0, d1) = (polyline.distances[i - 1], polyline.distances[i]);
+    let local = if d1 > d0 { (distance - d0) / (d1 - d0) } else { 0.0 };
+    polyline.points[i - 1].lerp(polyline.points[i], local)
+}
+
+/// Timing for a write-on animation: the path is written over `duration` seconds, starting
+/// after `delay` seconds.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct WriteOn {
+    pub delay: f32,
+    pub duration: f32,
+    /// How long the written part is, as a fraction of the path. `None` keeps the whole written
+    /// part, `Some(0.2)` gives a snake that is a fifth of the path long.
+    pub tail: Option<f32>,
+}
+
+impl WriteOn {
+    pub fn new(duration: f32) -> Self {
+        WriteOn {
+            delay: 0.0,
+            duration,
+            tail: None,
+        }
+    }
+
+    pub fn delay(mut self, delay: f32) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    pub fn tail(mut self, tail: f32) -> Self {
+        self.tail = Some(tail);
+        self
+    }
+
+    /// The normalised start and end of the visible part at `time`, e.g. `app.time`.
+    /// Use the result with `MeasuredPath::trim`.
+    pub fn range(&self, time: f32) -> (f32, f32) {
+        let progress = ((time - self.delay) / self.duration.max(f32::EPSILON)).clamp(0.0, 1.0);
+        //Ease in and out, so that the pen starts and stops softly
+        let eased = progress * progress * (3.0 - 2.0 * progress);
+        match self.tail {
+            None => (0.0, eased),
+            Some(tail) => {
+                let head = eased * (1.0 + tail);
+                ((head - tail).max(0.0), head.min(1.0))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nannou::lyon::math::point;
+    use nannou::lyon::path::Path;
+
+    fn assert_close(a: Vec2, b: Vec2) {
+        assert!(a.distance(b) < 1e-3, "{:?} is not {:?}", a, b);
+    }
+
+    //Two separate lines, each 100 long: along the x axis, then up the y axis from (0, 50)
+    fn two_lines() -> MeasuredPath {
+        let mut builder = Path::builder();
+        builder.begin(point(0.0, 0.0));
+        builder.line_to(point(100.0, 0.0));
+        builder.end(false);
+        builder.begin(point(0.0, 50.0));
+        builder.line_to(point(0.0, 150.0));
+        builder.end(false);
+        MeasuredPath::new(builder.build().iter())
+    }
+
+    fn ends(stroke: &Stroke) -> (Vec2, Vec2) {
+        (stroke.points[0], stroke.points[stroke.points.len() - 1])
+    }
+
+    #[test]
+    fn measures_every_subpath() {
+        let path = two_lines();
+        assert!((path.length() - 200.0).abs() < 1e-3);
+        assert_eq!(MeasuredPath::from_points(Vec::new()).length(), 0.0);
+    }
+
+    #[test]
+    fn point_at_a_distance() {
+        let path = two_lines();
+        assert_close(path.point_at(0.0), vec2(0.0, 0.0));
+        assert_close(path.point_at(0.25), vec2(50.0, 0.0));
+        //The gap between the subpaths has no length
+        assert_close(path.point_at(0.5), vec2(100.0, 0.0));
+        assert_close(path.point_at(0.75), vec2(0.0, 100.0));
+        assert_close(path.point_at(1.0), vec2(0.0, 150.0));
+        assert_close(path.point_at(-1.0), vec2(0.0, 0.0));
+        assert_close(path.point_at(2.0), vec2(0.0, 150.0));
+
+        let line = MeasuredPath::from_points([vec2(0.0, 0.0), vec2(10.0, 0.0), vec2(10.0, 30.0)]);
+        assert_close(line.point_at(0.5), vec2(10.0, 10.0));
+    }
+
+    #[test]
+    fn trim_across_subpaths() {
+        let path = two_lines();
+        let strokes = path.trim_distance(50.0, 150.0);
+        assert_eq!(strokes.len(), 2);
+        let (a, b) = ends(&strokes[0]);
+        assert_close(a, vec2(50.0, 0.0));
+        assert_close(b, vec2(100.0, 0.0));
+        let (a, b) = ends(&strokes[1]);
+        assert_close(a, vec2(0.0, 50.0));
+        assert_close(b, vec2(0.0, 100.0));
+        for stroke in &strokes {
+            assert_eq!(stroke.points.len(), stroke.positions.len());
+            assert!(stroke.positions.windows(2).all(|w| w[0] <= w[1]));
+        }
+        assert!((strokes[0].positions[0] - 0.25).abs() < 1e-4);
+        assert!((strokes[1].positions[strokes[1].positions.len() - 1] - 0.75).abs() < 1e-4);
+
+        //Within one subpath, the other one is left out
+        let strokes = path.trim_distance(120.0, 130.0);
+        assert_eq!(strokes.len(), 1);
+        let (a, b) = ends(&strokes[0]);
+        assert_close(a, vec2(0.0, 70.0));
+        assert_close(b, vec2(0.0, 80.0));
+
+        assert!(path.trim_distance(80.0, 80.0).is_empty());
+        assert!(path.trim_distance(90.0, 10.0).is_empty());
+    }
+
+    #[test]
+    fn trim_wraps_around() {
+        let path = two_lines();
+        let strokes = path.trim(0.9, 0.1);
+        assert_eq!(strokes.len(), 2);
+        //The end of the path comes first, then the start
+        let (a, b) = ends(&strokes[0]);
+        assert_close(a, vec2(0.0, 130.0));
+        assert_close(b, vec2(0.0, 150.0));
+        let (a, b) = ends(&strokes[1]);
+        assert_close(a, vec2(0.0, 0.0));
+        assert_close(b, vec2(20.0, 0.0));
+
+        let strokes = path.trim(0.25, 0.75);
+        assert_eq!(strokes.len(), 2);
+        assert!(path.trim(0.3, 0.3).is_empty());
+        //Values outside 0..1 are clamped
+        let whole = path.trim(-1.0, 2.0);
+        assert_close(ends(&whole[0]).0, vec2(0.0, 0.0));
+        assert_close(ends(&whole[1]).1, vec2(0.0, 150.0));
+    }
+
+    fn dash_ends(dashes: &[Stroke]) -> Vec<(f32, f32)> {
+        dashes
+            .iter()
+            .map(|d| {
+                let (a, b) = ends(d);
+                ((a.x * 10.0).round() / 10.0, (b.x * 10.0).round() / 10.0)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn dashes_with_an_offset() {
+        let line = MeasuredPath::from_points([vec2(0.0, 0.0), vec2(100.0, 0.0)]);
+        assert_eq!(
+            dash_ends(&line.dashes(10.0, 10.0, 0.0)),
+            [(0.0, 10.0), (20.0, 30.0), (40.0, 50.0), (60.0, 70.0), (80.0, 90.0)]
+        );
+        //An offset moves the dashes along, and cuts the ones at the ends
+        let expected = [
+            (0.0, 5.0),
+            (15.0, 25.0),
+            (35.0, 45.0),
+            (55.0, 65.0),
+            (75.0, 85.0),
+            (95.0, 100.0),
+        ];
+        assert_eq!(dash_ends(&line.dashes(10.0, 10.0, 15.0)), expected);
+        //Offsets repeat every dash and gap, also backwards
+        assert_eq!(dash_ends(&line.dashes(10.0, 10.0, 35.0)), expected);
+        assert_eq!(dash_ends(&line.dashes(10.0, 10.0, -5.0)), expected);
+
+        assert!(line.dashes(0.0, 10.0, 0.0).is_empty());
+    }
+
+    #[test]
+    fn write_on_eases_from_start_to_end() {
+        let write = WriteOn::new(2.0).delay(1.0);
+        assert_eq!(write.range(0.0), (0.0, 0.0));
+        assert_eq!(write.range(1.0), (0.0, 0.0));
+        assert_eq!(write.range(2.0), (0.0, 0.5));
+        assert_eq!(write.range(3.0), (0.0, 1.0));
+        assert_eq!(write.range(10.0), (0.0, 1.0));
+        //Eased: slow at the start
+        assert!(write.range(1.2).1 < 0.1);
+    }
+
+    #[test]
+    fn write_on_with_a_tail() {
+        let write = WriteOn::new(1.0).tail(0.2);
+        assert_eq!(write.range(0.0), (0.0, 0.0));
+        let (start, end) = write.range(0.5);
+        assert!((start - 0.4).abs() < 1e-6 && (end - 0.6).abs() < 1e-6);
+        //The snake leaves the path at the end
+        assert_eq!(write.range(1.0), (1.0, 1.0));
+        for i in 0..=100 {
+            let (start, end) = write.range(i as f32 / 100.0);
+            assert!(start <= end && end - start <= 0.2 + 1e-6);
+        }
+    }
+}
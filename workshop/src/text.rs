@@ -0,0 +1,446 @@
+//! Text for titles and labels.
+//!
+//! `draw.text()` can already draw a string, but placing it takes some fiddling with
+//! justification and alignment.
+//! A `Label` is laid out in a `Rect` with a `layout::Anchor`, e.g. `Anchor::TopLeft` for a title
+//! in the corner of the window.
+//! `on_curve` places the letters one by one along a `bezier::CubicBezier`, and `Label::outline`
+//! turns the letters into path events, so text can be stroked and filled like any other path,
+//! or written to an SVG file for a plotter.
+//!
+//! nannou comes with Noto Sans, which is used unless another font is loaded into `Fonts`.
+
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::io;
+use std::path::Path as FilePath;
+
+use nannou::color::{IntoLinSrgba, LinSrgba};
+use nannou::lyon::path::{Path, PathEvent};
+use nannou::prelude::*;
+use nannou::text::{self, Font, FontSize};
+
+use crate::bezier::CubicBezier;
+use crate::layout::Anchor;
+
+/// The font size used when none is given, in points.
+pub const DEFAULT_FONT_SIZE: FontSize = 24;
+
+/// The name of the font nannou comes with in `Fonts`.
+pub const DEFAULT_FONT: &str = "sans";
+
+/// Fonts by name.
+pub struct Fonts {
+    fonts: HashMap<String, Font>,
+}
+
+impl Default for Fonts {
+    fn default() -> Self {
+        Fonts::new()
+    }
+}
+
+impl Fonts {
+    /// Only the font nannou comes with, as `"sans"`.
+    pub fn new() -> Self {
+        let mut fonts = HashMap::new();
+        fonts.insert(DEFAULT_FONT.to_string(), text::font::default_notosans());
+        Fonts { fonts }
+    }
+
+    /// Load a `.ttf` or `.otf` file, e.g. from the `assets` folder, as `name`.
+    pub fn load(&mut self, name: impl Into<String>, path: impl AsRef<FilePath>) -> io::Result<()> {
+        let font = text::font::from_file(path.as_ref())
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))?;
+        self.fonts.insert(name.into(), font);
+        Ok(())
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Font> {
+        self.fonts.get(name)
+    }
+
+    /// The font called `name`, or the default font if there is none.
+    pub fn get_or_default(&self, name: &str) -> &Font {
+        self.get(name)
+            .or_else(|| self.get(DEFAULT_FONT))
+            .expect("the default font is always there")
+    }
+}
+
+/// A piece of text placed in a `Rect`.
+#[derive(Clone)]
+pub struct Label {
+    pub text: String,
+    pub font: Font,
+    pub size: FontSize,
+    /// Where in the rect the text goes, e.g. `Anchor::TopLeft` for the top left corner.
+    pub anchor: Anchor,
+    pub color: LinSrgba,
+    /// Break lines between words when they are wider than the rect.
+    pub wrap: bool,
+}
+
+impl Label {
+    pub fn new(text: impl Into<String>) -> Self {
+        Label {
+            text: text.into(),
+            font: text::font::default_notosans(),
+            size: DEFAULT_FONT_SIZE,
+            anchor: Anchor::Center,
+            color: WHITE.into_lin_srgba(),
+            wrap: true,
+        }
+    }
+
+    pub fn font(mut self, font: &Font) -> Self {
+        self.font = font.clone();
+        self
+    }
+
+    pub fn size(mut self, size: FontSize) -> Self {
+        self.size = size;
+        self
+    }
+
+    pub fn anchor(mut self, anchor: Anchor) -> Self {
+        self.anchor = anchor;
+        self
+    }
+
+    pub fn color(mut self, color: impl IntoLinSrgba<f32>) -> Self {
+        self.color = color.into_lin_srgba();
+        self
+    }
+
+    /// Keep the text on one line, even if it is wider than the rect.
+    pub fn no_wrap(mut self) -> Self {
+        self.wrap = false;
+        self
+    }
+
+    /// Draw the text in `rect`.
+    pub fn draw(&self, draw: &Draw, rect: Rect) {
+        let drawing = draw
+            .text(&self.text)
+            .font(self.font.clone())
+            .font_size(self.size)
+            .xy(rect.xy())
+            .wh(rect.wh())
+            .color(self.color);
+        let drawing = match self.anchor.unit().x as i32 {
+            -1 => drawing.left_justify(),
+            1 => drawing.right_justify(),
+            _ => drawing.center_justify(),
+        };
+        let drawing = match self.anchor.unit().y as i32 {
+            1 => drawing.align_text_top(),
+            -1 => drawing.align_text_bottom(),
+            _ => drawing.align_text_middle_y(),
+        };
+        if !self.wrap {
+            drawing.no_line_wrap();
+        }
+    }
+
+    /// The laid out text, e.g. for its `bounding_rect()`.
+    pub fn layout(&self, rect: Rect) -> text::Text<'_> {
+        let builder = text::text(&self.text)
+            .font(self.font.clone())
+            .font_size(self.size);
+        let builder = match self.anchor.unit().x as i32 {
+            -1 => builder.left_justify(),
+            1 => builder.right_justify(),
+            _ => builder.center_justify(),
+        };
+        let builder = match self.anchor.unit().y as i32 {
+            1 => builder.align_top(),
+            -1 => builder.align_bottom(),
+            _ => builder.align_middle_y(),
+        };
+        let builder = if self.wrap {
+            builder
+        } else {
+            builder.no_line_wrap()
+        };
+        builder.build(rect)
+    }
+
+    /// The smallest rect around the text when it is placed in `rect`.
+    pub fn bounding_rect(&self, rect: Rect) -> Rect {
+        self.layout(rect).bounding_rect()
+    }
+
+    /// The outlines of the letters, e.g. for `draw.path().stroke().events(outline.iter())`.
+    pub fn outline(&self, rect: Rect) -> Path {
+        to_path(self.layout(rect).path_events())
+    }
+}
+
+/// Collect path events into a `Path`, so they can be used more than once.
+pub fn to_path<I>(events: I) -> Path
+where
+    I: IntoIterator<Item = PathEvent>,
+{
+    let mut builder = Path::builder();
+    for event in events {
+        match event {
+            PathEvent::Begin { at } => {
+                builder.begin(at);
+            }
+            PathEvent::Line { to, .. } => {
+                builder.line_to(to);
+            }
+            PathEvent::Quadratic { ctrl, to, .. } => {
+                builder.quadratic_bezier_to(ctrl, to);
+            }
+            PathEvent::Cubic {
+                ctrl1, ctrl2, to, ..
+            } => {
+                builder.cubic_bezier_to(ctrl1, ctrl2, to);
+            }
+            PathEvent::End { close, .. } => builder.end(close),
+        }
+    }
+    builder.build()
+}
+
+/// The path as the `d` attribute of an SVG `<path>`.
+///
+/// SVG has y pointing down, so the y coordinates are flipped, and `height` is added to put
+/// nannou's origin in the middle of an SVG that is `height` tall. Use `0.0` to only flip.
+pub fn svg_path_data(path: &Path, height: f32) -> String {
+    let y = |y: f32| height * 0.5 - y;
+    let mut d = String::new();
+    for event in path.iter() {
+        //Writing to a `String` never fails
+        let _ = match event {
+            PathEvent::Begin { at } => write!(d, "M{} {} ", at.x, y(at.y)),
+            PathEvent::Line { to, .. } => write!(d, "L{} {} ", to.x, y(to.y)),
+            PathEvent::Quadratic { ctrl, to, .. } => {
+                write!(d, "Q{} {} {} {} ", ctrl.x, y(ctrl.y), to.x, y(to.y))
+            }
+            PathEvent::Cubic {
+                ctrl1, ctrl2, to, ..
+            } => write!(
+                d,
+                "C{} {} {} {} {} {} ",
+                ctrl1.x,
+                y(ctrl1.y),
+                ctrl2.x,
+                y(ctrl2.y),
+                to.x,
+                y(to.y)
+            ),
+            PathEvent::End { close: true, .. } => write!(d, "Z "),
+            PathEvent::End { .. } => Ok(()),
+        };
+    }
+    d.trim_end().to_string()
+}
+
+/// One letter placed on a curve.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct PlacedGlyph {
+    pub character: char,
+    /// The middle of the letter, on the curve.
+    pub position: Vec2,
+    /// The angle of the curve at the letter, in radians.
+    pub rotation: f32,
+}
+
+/// Place each letter of `text` along `curve`, starting `start` pixels from the start of the
+/// curve. Letters that don't fit on the curve are left out.
+pub fn on_curve(
+    text: &str,
+    font: &Font,
+    size: FontSize,
+    curve: &CubicBezier,
+    start: f32,
+) -> Vec<PlacedGlyph> {
+    let scale = text::pt_to_scale(size);
+    let table = curve.arc_length_table(256);
+    let mut pen = start;
+    let mut last = None;
+    let mut glyphs = Vec::new();
+    for character in text.chars() {
+        if let Some(last) = last {
+            pen += font.pair_kerning(scale, last, character);
+        }
+        last = Some(character);
+        let advance = font.glyph(character).scaled(scale).h_metrics().advance_width;
+        let middle = pen + advance * 0.5;
+        pen += advance;
+        if middle < 0.0 {
+            continue;
+        }
+        if middle > table.length() {
+            break;
+        }
+        if character.is_whitespace() {
+            continue;
+        }
+        let t = table.t_at_length(middle);
+        let tangent = curve.tangent(t);
+        glyphs.push(PlacedGlyph {
+            character,
+            position: curve.point(t),
+            rotation: tangent.y.atan2(tangent.x),
+        });
+    }
+    glyphs
+}
+
+/// Draw the text of `label` along `curve`, starting `start` pixels from the start of the curve.
+pub fn draw_on_curve(draw: &Draw, label: &Label, curve: &CubicBezier, start: f32) {
+    let size = label.size as f32 * 2.0;
+    for glyph in on_curve(&label.text, &label.font, label.size, curve, start) {
+        let mut buffer = [0; 4];
+        draw.xy(glyph.position)
+            .rotate(glyph.rotation)
+            .text(glyph.character.encode_utf8(&mut buffer))
+            .font(label.font.clone())
+            .font_size(label.size)
+            .w_h(size, size)
+            .color(label.color);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nannou::lyon::math::point;
+
+    fn assert_close(a: f32, b: f32) {
+        assert!((a - b).abs() < 1e-3, "{} != {}", a, b);
+    }
+
+    fn characters(glyphs: &[PlacedGlyph]) -> String {
+        glyphs.iter().map(|g| g.character).collect()
+    }
+
+    #[test]
+    fn glyphs_follow_the_text() {
+        let font = text::font::default_notosans();
+        let line = CubicBezier::line(vec2(0.0, 0.0), vec2(1000.0, 0.0));
+        let glyphs = on_curve("Hello world", &font, 24, &line, 0.0);
+        assert_eq!(characters(&glyphs), "Helloworld");
+        for pair in glyphs.windows(2) {
+            assert!(pair[1].position.x > pair[0].position.x, "{:?}", pair);
+        }
+        for glyph in &glyphs {
+            assert_close(glyph.position.y, 0.0);
+            assert_close(glyph.rotation, 0.0);
+        }
+        //The space leaves a gap wider than the one between two letters
+        let gap = |a: usize, b: usize| glyphs[b].position.x - glyphs[a].position.x;
+        assert!(gap(4, 5) > gap(3, 4) * 1.5);
+    }
+
+    #[test]
+    fn glyphs_start_later_with_start() {
+        let font = text::font::default_notosans();
+        let line = CubicBezier::line(vec2(0.0, 0.0), vec2(1000.0, 0.0));
+        let glyphs = on_curve("ab", &font, 24, &line, 0.0);
+        let later = on_curve("ab", &font, 24, &line, 100.0);
+        assert_close(later[0].position.x - glyphs[0].position.x, 100.0);
+        //Letters before the start of the curve are left out
+        let earlier = on_curve("abc", &font, 24, &line, -1000.0);
+        assert!(earlier.is_empty());
+    }
+
+    #[test]
+    fn glyphs_past_the_end_are_dropped() {
+        let font = text::font::default_notosans();
+        let short = CubicBezier::line(vec2(0.0, 0.0), vec2(40.0, 0.0));
+        let glyphs = on_curve("abcdefgh", &font, 24, &short, 0.0);
+        assert!(!glyphs.is_empty());
+        assert!(glyphs.len() < 8);
+        assert!(characters(&glyphs).starts_with('a'));
+        assert!(glyphs.iter().all(|g| g.position.x <= 40.0));
+    }
+
+    #[test]
+    fn glyphs_are_turned_with_the_curve() {
+        let font = text::font::default_notosans();
+        let diagonal = CubicBezier::line(vec2(0.0, 0.0), vec2(500.0, 500.0));
+        for glyph in on_curve("slope", &font, 24, &diagonal, 0.0) {
+            assert_close(glyph.rotation, PI / 4.0);
+            assert_close(glyph.position.x, glyph.position.y);
+        }
+        //Along a curve bending to the left, every letter is turned further
+        let arc = CubicBezier::new(
+            vec2(0.0, 0.0),
+            vec2(300.0, 0.0),
+            vec2(400.0, 100.0),
+            vec2(400.0, 400.0),
+        );
+        let glyphs = on_curve("bending text", &font, 24, &arc, 0.0);
+        assert_eq!(glyphs.len(), 11);
+        for pair in glyphs.windows(2) {
+            assert!(pair[1].rotation > pair[0].rotation, "{:?}", pair);
+        }
+        let table = arc.arc_length_table(256);
+        for glyph in &glyphs {
+            let closest = (0..=1000)
+                .map(|i| table.t_at_fraction(i as f32 / 1000.0))
+                .min_by(|a, b| {
+                    let da = arc.point(*a).distance(glyph.position);
+                    let db = arc.point(*b).distance(glyph.position);
+                    da.partial_cmp(&db).unwrap()
+                })
+                .unwrap();
+            let tangent = arc.tangent(closest);
+            assert!((glyph.rotation - tangent.y.atan2(tangent.x)).abs() < 0.01);
+        }
+    }
+
+    #[test]
+    fn events_are_collected_in_order() {
+        let mut builder = Path::builder();
+        builder.begin(point(0.0, 0.0));
+        builder.line_to(point(10.0, 0.0));
+        builder.quadratic_bezier_to(point(20.0, 0.0), point(20.0, 10.0));
+        builder.cubic_bezier_to(point(20.0, 20.0), point(10.0, 20.0), point(0.0, 20.0));
+        builder.end(true);
+        builder.begin(point(50.0, 50.0));
+        builder.line_to(point(60.0, 50.0));
+        builder.end(false);
+        let path = builder.build();
+        let collected = to_path(path.iter());
+        assert_eq!(
+            collected.iter().collect::<Vec<_>>(),
+            path.iter().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn svg_y_points_down() {
+        let mut builder = Path::builder();
+        builder.begin(point(0.0, 0.0));
+        builder.line_to(point(10.0, 10.0));
+        builder.quadratic_bezier_to(point(20.0, 30.0), point(30.0, -10.0));
+        builder.end(true);
+        let path = builder.build();
+        assert_eq!(svg_path_data(&path, 0.0), "M0 0 L10 -10 Q20 -30 30 10 Z");
+        assert_eq!(svg_path_data(&path, 100.0), "M0 50 L10 40 Q20 20 30 60 Z");
+    }
+
+    #[test]
+    fn outline_fits_the_text() {
+        let label = Label::new("AH").size(48).no_wrap();
+        let rect = Rect::from_w_h(400.0, 200.0);
+        let outline = label.outline(rect);
+        let bounds = label.bounding_rect(rect);
+        let mut points = 0;
+        for event in outline.iter() {
+            if let PathEvent::Begin { at } | PathEvent::Line { to: at, .. } = event {
+                points += 1;
+                assert!(bounds.pad(-2.0).x.contains(at.x), "{:?} {:?}", at, bounds);
+                assert!(rect.y.contains(at.y), "{:?}", at);
+            }
+        }
+        assert!(points > 4);
+        assert!(svg_path_data(&outline, 200.0).starts_with('M'));
+    }
+}
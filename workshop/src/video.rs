@@ -0,0 +1,364 @@
+//! Video files and image sequences as input for sketches, without a camera.
+//!
+//! A `Video` shows the frame that belongs to the sketch time, so a sketch that runs slowly skips
+//! frames instead of playing the video in slow motion, and the video loops when it reaches the
+//! end.
+//! The current frame is an `image::Image`, so its colours can be sampled like any other image.
+//!
+//! There are two kinds of sources:
+//!
+//! * A folder of PNG or JPEG files, played in the order of their names, e.g. `frame_000001.png`,
+//!   `frame_000002.png`, ..., like the frames saved with `capture`.
+//! * A video file, e.g. an `.mp4` or `.mov`. These are decoded by `ffmpeg`, which has to be
+//!   installed and on the `PATH`; we start it in the background and read the frames from it.
+//!   Frames are shown as they are stored in the file: phones often store videos on their side
+//!   with a note to turn them when playing, which is ignored here.
+
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::process::{Child, ChildStdout, Command, Stdio};
+
+use nannou::image::RgbaImage;
+
+use crate::image::Image;
+
+/// The frame rate of an image sequence when none is given.
+pub const DEFAULT_FPS: f32 = 30.0;
+
+/// A video that plays along with the sketch clock.
+pub struct Video {
+    source: Source,
+    fps: f32,
+    frame: Image,
+    //The number of the current frame since the start of the video
+    index: usize,
+    //Only known for video files once the end has been reached
+    frame_count: Option<usize>,
+}
+
+enum Source {
+    Images(Vec<PathBuf>),
+    Ffmpeg(Decoder),
+}
+
+impl Video {
+    /// Open a folder as an image sequence, or anything else as a video file.
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref();
+        if path.is_dir() {
+            Video::image_sequence(path, DEFAULT_FPS)
+        } else {
+            Video::file(path)
+        }
+    }
+
+    /// Play the PNG and JPEG files in `dir`, sorted by name, at `fps` frames per second.
+    pub fn image_sequence(dir: impl AsRef<Path>, fps: f32) -> io::Result<Self> {
+        let mut paths: Vec<PathBuf> = fs::read_dir(dir.as_ref())?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| is_image(path))
+            .collect();
+        paths.sort();
+        if paths.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("no images in {}", dir.as_ref().display()),
+            ));
+        }
+        let frame = Image::open(&paths[0])?;
+        Ok(Video {
+            frame_count: Some(paths.len()),
+            source: Source::Images(paths),
+            fps: fps.max(0.001),
+            frame,
+            index: 0,
+        })
+    }
+
+    /// Decode a video file with `ffmpeg`, at the frame rate of the file.
+    pub fn file(path: impl AsRef<Path>) -> io::Result<Self> {
+        let mut decoder = Decoder::start(path.as_ref())?;
+        let fps = decoder.fps;
+        let frame = decoder
+            .next_frame()?
+            .ok_or_else(|| invalid_data(format!("no frames in {}", path.as_ref().display())))?;
+        Ok(Video {
+            source: Source::Ffmpeg(decoder),
+            fps,
+            frame: Image::from_pixels(frame)?,
+            index: 0,
+            frame_count: None,
+        })
+    }
+
+    /// Play at another speed than the file says, e.g. `video.fps(15.0)` for half speed.
+    pub fn fps(mut self, fps: f32) -> Self {
+        self.fps = fps.max(0.001);
+        self
+    }
+
+    /// Go to the frame for `time`, in seconds, e.g. `app.time` or the time of a
+    /// `snapshot::Clock`. Returns whether the frame changed.
+    ///
+    /// Call this in `update`. Going back in time is slow for video files, since they have to be
+    /// decoded from the start again.
+    pub fn update(&mut self, time: f32) -> io::Result<bool> {
+        let mut target = (time.max(0.0) * self.fps) as usize;
+        if let Some(count) = self.frame_count {
+            target %= count;
+        }
+        if target == self.index {
+            return Ok(false);
+        }
+        match &mut self.source {
+            Source::Images(paths) => {
+                self.frame = Image::open(&paths[target])?;
+                self.index = target;
+            }
+            Source::Ffmpeg(decoder) => {
+                if target < self.index {
+                    decoder.restart()?;
+                }
+                //Frames between the current one and the target are decoded and thrown away
+                let mut pixels = None;
+                while decoder.next <= target {
+                    match decoder.next_frame()? {
+                        Some(frame) => pixels = Some(frame),
+                        None if decoder.next == 0 => {
+                            return Err(invalid_data(format!(
+                                "no frames in {}",
+                                decoder.path.display()
+                            )));
+                        }
+                        None => {
+                            //The end of the video: now we know how long it is, so loop
+                            self.frame_count = Some(decoder.next);
+                            target %= decoder.next;
+                            decoder.restart()?;
+                        }
+                    }
+                }
+                if let Some(pixels) = pixels {
+                    self.frame = Image::from_pixels(pixels)?;
+                }
+                self.index = target;
+            }
+        }
+        Ok(true)
+    }
+
+    /// The current frame.
+    pub fn frame(&self) -> &Image {
+        &self.frame
+    }
+
+    /// The number of the current frame, starting at `0`.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// How many frames the video has, if known. For video files this is only known after
+    /// playing to the end once.
+    pub fn frame_count(&self) -> Option<usize> {
+        self.frame_count
+    }
+
+    pub fn frames_per_second(&self) -> f32 {
+        self.fps
+    }
+
+    /// The width and height of the frames in pixels.
+    pub fn size(&self) -> (u32, u32) {
+        (self.frame.width(), self.frame.height())
+    }
+}
+
+//An `ffmpeg` process writing raw RGBA frames to its output
+struct Decoder {
+    path: PathBuf,
+    width: u32,
+    height: u32,
+    fps: f32,
+    //The number of the frame `next_frame` returns
+    next: usize,
+    child: Child,
+    output: ChildStdout,
+}
+
+impl Decoder {
+    fn start(path: &Path) -> io::Result<Self> {
+        let (width, height, fps) = probe(path)?;
+        let (child, output) = spawn_ffmpeg(path)?;
+        Ok(Decoder {
+            path: path.to_path_buf(),
+            width,
+            height,
+            fps,
+            next: 0,
+            child,
+            output,
+        })
+    }
+
+    fn restart(&mut self) -> io::Result<()> {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+        let (child, output) = spawn_ffmpeg(&self.path)?;
+        self.child = child;
+        self.output = output;
+        self.next = 0;
+        Ok(())
+    }
+
+    //`None` at the end of the video
+    fn next_frame(&mut self) -> io::Result<Option<RgbaImage>> {
+        let mut bytes = vec![0; self.width as usize * self.height as usize * 4];
+        match self.output.read_exact(&mut bytes) {
+            Ok(()) => {
+                self.next += 1;
+                Ok(RgbaImage::from_raw(self.width, self.height, bytes))
+            }
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+impl Drop for Decoder {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+//Without `-noautorotate` ffmpeg turns videos that are stored on their side, and the frames
+// would not have the size `probe` found
+fn spawn_ffmpeg(path: &Path) -> io::Result<(Child, ChildStdout)> {
+    let mut child = Command::new("ffmpeg")
+        .args(["-v", "error", "-noautorotate", "-i"])
+        .arg(path)
+        .args(["-f", "rawvideo", "-pix_fmt", "rgba", "-"])
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::inherit())
+        .spawn()
+        .map_err(|e| io::Error::new(e.kind(), format!("could not start ffmpeg: {}", e)))?;
+    let output = child.stdout.take().expect("stdout is piped");
+    Ok((child, output))
+}
+
+//The width, height and frame rate of the first video stream, from `ffprobe`
+fn probe(path: &Path) -> io::Result<(u32, u32, f32)> {
+    let output = Command::new("ffprobe")
+        .args(["-v", "error", "-select_streams", "v:0"])
+        .args(["-show_entries", "stream=width,height,r_frame_rate"])
+        .args(["-of", "csv=p=0"])
+        .arg(path)
+        .output()
+        .map_err(|e| io::Error::new(e.kind(), format!("could not start ffprobe: {}", e)))?;
+    if !output.status.success() {
+        return Err(invalid_data(format!(
+            "ffprobe could not read {}: {}",
+            path.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    parse_probe(&String::from_utf8_lossy(&output.stdout))
+        .ok_or_else(|| invalid_data(format!("no video stream in {}", path.display())))
+}
+
+//E.g. `1920,1080,30000/1001`
+fn parse_probe(text: &str) -> Option<(u32, u32, f32)> {
+    let mut parts = text.lines().next()?.trim().split(',');
+    let width = parts.next()?.parse().ok()?;
+    let height = parts.next()?.parse().ok()?;
+    let fps = match parts.next()?.split_once('/') {
+        Some((n, d)) => n.parse::<f32>().ok()? / d.parse::<f32>().ok()?,
+        None => return None,
+    };
+    if width == 0 || height == 0 || !fps.is_finite() || fps <= 0.0 {
+        return None;
+    }
+    Some((width, height, fps))
+}
+
+fn is_image(path: &Path) -> bool {
+    let extension = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_ascii_lowercase());
+    matches!(extension.as_deref(), Some("png" | "jpg" | "jpeg"))
+}
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_probe_output() {
+        assert_eq!(parse_probe("1920,1080,30/1\n"), Some((1920, 1080, 30.0)));
+        let (w, h, fps) = parse_probe("640,480,30000/1001").unwrap();
+        assert_eq!((w, h), (640, 480));
+        assert!((fps - 29.97).abs() < 0.01);
+        //Only the first stream counts
+        assert_eq!(parse_probe("320,240,25/1\n1920,1080,60/1\n"), Some((320, 240, 25.0)));
+        //Some versions of ffprobe end the line with a comma
+        assert_eq!(parse_probe("320,240,25/1,\n"), Some((320, 240, 25.0)));
+    }
+
+    #[test]
+    fn parse_probe_rejects_nonsense() {
+        assert_eq!(parse_probe(""), None);
+        assert_eq!(parse_probe("1920,1080"), None);
+        assert_eq!(parse_probe("1920,1080,30"), None);
+        assert_eq!(parse_probe("1920,1080,0/0"), None);
+        assert_eq!(parse_probe("0,1080,30/1"), None);
+        assert_eq!(parse_probe("wide,1080,30/1"), None);
+    }
+
+    #[test]
+    fn images_by_extension() {
+        assert!(is_image(Path::new("frames/frame_000001.png")));
+        assert!(is_image(Path::new("photo.JPG")));
+        assert!(is_image(Path::new("photo.jpeg")));
+        assert!(!is_image(Path::new("video.mp4")));
+        assert!(!is_image(Path::new("README")));
+    }
+
+    #[test]
+    fn image_sequence_needs_images() {
+        let dir = std::env::temp_dir().join("workshop-video-test-empty");
+        fs::create_dir_all(&dir).unwrap();
+        let error = Video::image_sequence(&dir, 30.0).err().unwrap();
+        let _ = fs::remove_dir_all(&dir);
+        assert_eq!(error.kind(), io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn image_sequence_follows_the_clock() {
+        let dir = std::env::temp_dir().join("workshop-video-test-sequence");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        for i in 0..3 {
+            let image = RgbaImage::from_pixel(4, 2, nannou::image::Rgba([i * 100, 0, 0, 255]));
+            image.save(dir.join(format!("frame_{:06}.png", i))).unwrap();
+        }
+        fs::write(dir.join("notes.txt"), "not a frame").unwrap();
+
+        let mut video = Video::image_sequence(&dir, 10.0).unwrap();
+        assert_eq!(video.frame_count(), Some(3));
+        assert_eq!(video.size(), (4, 2));
+        assert!(!video.update(0.05).unwrap());
+        assert!(video.update(0.15).unwrap());
+        assert_eq!(video.index(), 1);
+        //It loops at the end
+        assert!(video.update(0.35).unwrap());
+        assert_eq!(video.index(), 0);
+        let _ = fs::remove_dir_all(&dir);
+    }
+}